@@ -0,0 +1,23 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+use yaml_peg::{node, NodeRc};
+
+fn config() -> NodeRc {
+    (0..100)
+        .map(|i| (node!(format!("key{i}")), node!(i)))
+        .collect::<yaml_peg::Yaml<_>>()
+        .into()
+}
+
+fn get(c: &mut Criterion) {
+    let n = config();
+    c.bench_function("get", |b| {
+        b.iter(|| n.get_checked(black_box("key50")).unwrap())
+    });
+    c.bench_function("get_str", |b| {
+        b.iter(|| n.get_str_checked(black_box("key50")).unwrap())
+    });
+}
+
+criterion_group!(benches, get);
+criterion_main!(benches);
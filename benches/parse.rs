@@ -0,0 +1,93 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde::Deserialize as _;
+use std::hint::black_box;
+use yaml_peg::{
+    parse,
+    repr::{ArcRepr, RcRepr},
+};
+
+/// A flat map with many plain and quoted scalars.
+fn flat(n: usize) -> String {
+    let mut doc = String::from("{\n");
+    for i in 0..n {
+        doc += &format!("key{i}: value {i},\n'num{i}': {i}.5,\n");
+    }
+    doc += "\"end\": null\n}\n";
+    doc
+}
+
+/// The nested block maps and sequences.
+fn deep(depth: usize) -> String {
+    let mut doc = String::new();
+    for i in 0..depth {
+        let ind = "  ".repeat(i);
+        doc += &format!("{ind}- k{i}: v{i}\n{ind}  next:\n");
+    }
+    doc += &format!("{}- end\n", "  ".repeat(depth));
+    doc
+}
+
+/// Many anchors and the aliases referring to them.
+fn anchors(n: usize) -> String {
+    let mut doc = String::new();
+    for i in 0..n {
+        doc += &format!("a{i}: &x{i} {{name: n{i}, tags: [t{i}, u{i}]}}\n");
+    }
+    for i in 0..n {
+        doc += &format!("b{i}: *x{i}\n");
+    }
+    doc
+}
+
+/// A stream of the small documents.
+fn stream(n: usize) -> String {
+    (0..n)
+        .map(|i| format!("---\nid: {i}\nname: doc {i}\nitems: [1, 2, 3]\n"))
+        .collect()
+}
+
+fn fixtures() -> [(&'static str, String); 4] {
+    [
+        ("flat", flat(2000)),
+        ("deep", deep(30)),
+        ("anchors", anchors(1000)),
+        ("stream", stream(2000)),
+    ]
+}
+
+fn parse_docs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for (name, doc) in fixtures() {
+        group.throughput(Throughput::Bytes(doc.len() as u64));
+        group.bench_with_input(BenchmarkId::new("rc", name), &doc, |b, doc| {
+            b.iter(|| parse::<RcRepr>(black_box(doc)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("arc", name), &doc, |b, doc| {
+            b.iter(|| parse::<ArcRepr>(black_box(doc)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Compare with `serde_yaml` on the same documents for context.
+fn compare_serde_yaml(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serde_yaml");
+    for (name, doc) in fixtures() {
+        group.throughput(Throughput::Bytes(doc.len() as u64));
+        group.bench_with_input(BenchmarkId::new("yaml-peg", name), &doc, |b, doc| {
+            b.iter(|| parse::<RcRepr>(black_box(doc)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("serde_yaml", name), &doc, |b, doc| {
+            b.iter(|| {
+                serde_yaml::Deserializer::from_str(black_box(doc))
+                    .map(serde_yaml::Value::deserialize)
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_docs, compare_serde_yaml);
+criterion_main!(benches);
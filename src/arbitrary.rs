@@ -0,0 +1,51 @@
+use crate::{repr::Repr, *};
+use alloc::format;
+use proptest::{collection::vec, prelude::*};
+
+/// Generate the trees of the scalars, sequences and maps, the keys are the
+/// scalars. The nodes have no tags, anchors and comments, and the floats are
+/// finite, so the trees can be dumped and parsed back as the same value.
+///
+/// ```
+/// use proptest::prelude::*;
+/// use yaml_peg::{dump, parse, NodeRc};
+///
+/// proptest!(|(n in any::<NodeRc>())| {
+///     let doc = dump(&[n.clone()], &[]);
+///     prop_assert_eq!(parse(&doc).unwrap(), vec![n]);
+/// });
+/// ```
+impl<R: Repr + 'static> Arbitrary for Node<R> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        let scalar = prop_oneof![
+            Just(Yaml::Null),
+            any::<bool>().prop_map(Yaml::Bool),
+            any::<i64>().prop_map(Yaml::from),
+            any::<f64>()
+                .prop_filter("finite", |f| f.is_finite())
+                .prop_map(|f| Yaml::Float(format!("{f:?}"))),
+            "[\\PC\t\n]{0,16}".prop_map(Yaml::from),
+        ]
+        .prop_map(|yaml| Node::new(yaml, 0, ""));
+        scalar
+            .clone()
+            .prop_recursive(4, 32, 4, move |inner| {
+                prop_oneof![
+                    vec(inner.clone(), 0..4).prop_map(|v| Node::new(
+                        v.into_iter().collect::<Yaml<R>>(),
+                        0,
+                        ""
+                    )),
+                    vec((scalar.clone(), inner), 0..4).prop_map(|v| Node::new(
+                        v.into_iter().collect::<Yaml<R>>(),
+                        0,
+                        ""
+                    )),
+                ]
+            })
+            .boxed()
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{parser::Anchors, repr::Repr, *};
+use alloc::string::{String, ToString};
+
+/// The data which can be built into a node, the anchors of the builders are
+/// collected into the map.
+///
+/// This trait is implemented for the builders and the types which can be
+/// converted into [`Node`], so they can be mixed in the builders.
+pub trait IntoNode<R: Repr> {
+    /// Build the node, and record the anchors.
+    fn into_node(self, anchors: &mut Anchors<R>) -> Node<R>;
+}
+
+impl<R: Repr, T: Into<Node<R>>> IntoNode<R> for T {
+    fn into_node(self, _anchors: &mut Anchors<R>) -> Node<R> {
+        self.into()
+    }
+}
+
+/// The properties of the built node.
+struct Props<R: Repr> {
+    tag: String,
+    anchor: String,
+    comment: String,
+    anchors: Anchors<R>,
+}
+
+impl<R: Repr> Props<R> {
+    fn new() -> Self {
+        Self {
+            tag: String::new(),
+            anchor: String::new(),
+            comment: String::new(),
+            anchors: Anchors::new(),
+        }
+    }
+
+    fn build(self, yaml: Yaml<R>) -> (Node<R>, Anchors<R>) {
+        let Self { tag, anchor, comment, mut anchors } = self;
+        let mut node = Node::new(yaml, 0, tag);
+        node.set_comment(comment);
+        node.set_anchor(&anchor);
+        if !anchor.is_empty() {
+            anchors.insert(anchor, node.clone());
+        }
+        (node, anchors)
+    }
+}
+
+macro_rules! impl_props {
+    ($($ty:ident)+) => {$(
+        impl<R: Repr> $ty<R> {
+            /// Set the tag of the node, see [`Node::tag`].
+            pub fn with_tag(mut self, tag: impl ToString) -> Self {
+                self.props.tag = tag.to_string();
+                self
+            }
+
+            /// Set the anchor name of the node, the anchors are returned by
+            /// the `build_with_anchors` method of the outermost builder.
+            pub fn with_anchor(mut self, anchor: impl ToString) -> Self {
+                self.props.anchor = anchor.to_string();
+                self
+            }
+
+            /// Set the comment of the node, see [`Node::set_comment`].
+            pub fn with_comment(mut self, comment: impl ToString) -> Self {
+                self.props.comment = comment.to_string();
+                self
+            }
+
+            /// Build the node, the anchors are discarded.
+            pub fn build(self) -> Node<R> {
+                self.build_with_anchors().0
+            }
+        }
+
+        impl<R: Repr> Default for $ty<R> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<R: Repr> IntoNode<R> for $ty<R> {
+            fn into_node(self, anchors: &mut Anchors<R>) -> Node<R> {
+                let (node, a) = self.build_with_anchors();
+                anchors.extend(a);
+                node
+            }
+        }
+    )+};
+}
+
+/// The builder of the map node, for building the trees dynamically.
+///
+/// ```
+/// use yaml_peg::{dump, node, MapBuilder, SeqBuilder, NodeRc, dumper::NL};
+///
+/// let mut hosts = SeqBuilder::new();
+/// for i in 1..=2 {
+///     hosts = hosts.push(format!("10.0.0.{i}"));
+/// }
+/// let base = MapBuilder::new().entry("port", 80).with_anchor("base");
+/// let (n, anchors): (NodeRc, _) = MapBuilder::new()
+///     .entry("base", base)
+///     .entry("hosts", hosts.with_comment("The servers"))
+///     .entry_node(node!("version"), node!(2))
+///     .with_tag("config")
+///     .build_with_anchors();
+/// assert_eq!(n, node!({"base" => node!({"port" => 80}), "hosts" => node!(["10.0.0.1", "10.0.0.2"]), "version" => 2}));
+/// assert_eq!(n.tag(), "config");
+/// assert_eq!(anchors["base"], node!({"port" => 80}));
+/// let doc = "\
+/// !config
+/// base: &base
+///   port: 80
+/// ## The servers
+/// hosts:
+///   - 10.0.0.1
+///   - 10.0.0.2
+/// version: 2
+/// ";
+/// assert_eq!(dump(&[n], &[anchors]), doc.replace('\n', NL));
+/// ```
+pub struct MapBuilder<R: Repr> {
+    map: Map<R>,
+    props: Props<R>,
+}
+
+impl<R: Repr> MapBuilder<R> {
+    /// Create an empty map builder.
+    pub fn new() -> Self {
+        Self { map: Map::default(), props: Props::new() }
+    }
+
+    /// Insert an item, the keys and the values can be the builders, the
+    /// nodes, or the data which can be converted into nodes.
+    ///
+    /// The existing item of the same key is replaced.
+    pub fn entry(mut self, k: impl IntoNode<R>, v: impl IntoNode<R>) -> Self {
+        let k = k.into_node(&mut self.props.anchors);
+        let v = v.into_node(&mut self.props.anchors);
+        self.map.insert(k, v);
+        self
+    }
+
+    /// Insert an item of the nodes, same as [`MapBuilder::entry`].
+    pub fn entry_node(self, k: Node<R>, v: Node<R>) -> Self {
+        self.entry(k, v)
+    }
+
+    /// Build the node and return the anchors of the builders.
+    pub fn build_with_anchors(self) -> (Node<R>, Anchors<R>) {
+        self.props.build(Yaml::Map(self.map))
+    }
+}
+
+/// The builder of the sequence node, see [`MapBuilder`] for an example.
+pub struct SeqBuilder<R: Repr> {
+    seq: Seq<R>,
+    props: Props<R>,
+}
+
+impl<R: Repr> SeqBuilder<R> {
+    /// Create an empty sequence builder.
+    pub fn new() -> Self {
+        Self { seq: Seq::new(), props: Props::new() }
+    }
+
+    /// Push an item, the item can be a builder, a node, or the data which can
+    /// be converted into node.
+    pub fn push(mut self, v: impl IntoNode<R>) -> Self {
+        let v = v.into_node(&mut self.props.anchors);
+        self.seq.push(v);
+        self
+    }
+
+    /// Push a node, same as [`SeqBuilder::push`].
+    pub fn push_node(self, v: Node<R>) -> Self {
+        self.push(v)
+    }
+
+    /// Build the node and return the anchors of the builders.
+    pub fn build_with_anchors(self) -> (Node<R>, Anchors<R>) {
+        self.props.build(Yaml::Seq(self.seq))
+    }
+}
+
+impl_props! { MapBuilder SeqBuilder }
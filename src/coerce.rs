@@ -0,0 +1,216 @@
+//! Convert the scalars to the expected types by their paths.
+//!
+//! The documents produced by the templating systems often quote every
+//! scalar, e.g., `replicas: "3"`. The [`Coercer`] resolves the strings at
+//! the declared paths with the core schema, and reports the scalars that
+//! cannot be converted as [`NodeError`] with their positions and paths.
+//!
+//! ```
+//! use yaml_peg::{coerce::{Coercer, Kind}, node};
+//!
+//! let n = node!({
+//!     "replicas" => "3",
+//!     "ports" => node!([node!({"port" => "80", "public" => "true"})]),
+//!     "version" => 2,
+//! });
+//! let coercer = Coercer::new()
+//!     .path("replicas", Kind::Int)
+//!     .path("ports[*].port", Kind::Int)
+//!     .path("ports[*].public", Kind::Bool)
+//!     .path("version", Kind::Str);
+//! let ans = node!({
+//!     "replicas" => 3,
+//!     "ports" => node!([node!({"port" => 80, "public" => true})]),
+//!     "version" => "2",
+//! });
+//! assert_eq!(ans, coercer.coerce(&n).unwrap());
+//! let e = coercer.coerce(&node!({"replicas" => "three"})).unwrap_err();
+//! assert_eq!(e.to_string(), "expected int, found str at replicas (pos 0)");
+//! ```
+use crate::{repr::Repr, *};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// The expected scalar types of [`Coercer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// The null values, the empty strings are accepted.
+    Null,
+    /// The booleans.
+    Bool,
+    /// The integers.
+    Int,
+    /// The floats, the integers are accepted.
+    Float,
+    /// The strings, the other scalars are converted into their lexemes.
+    Str,
+}
+
+impl Kind {
+    /// The core schema tag name of the type, such as `int`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool => "bool",
+            Self::Int => "int",
+            Self::Float => "float",
+            Self::Str => "str",
+        }
+    }
+}
+
+/// The function decides the expected type of the node by its path, see
+/// [`Coercer::callback`].
+pub type CoerceFn = fn(&NodePath) -> Option<Kind>;
+
+/// The scalar type declarations of the paths.
+///
+/// Please see [module level document](self) for more information.
+#[derive(Clone, Debug, Default)]
+pub struct Coercer {
+    rules: Vec<(String, Kind)>,
+    callback: Option<CoerceFn>,
+}
+
+impl Coercer {
+    /// Create an empty declaration, which keeps the nodes as is.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare the expected type of the path.
+    ///
+    /// The path is written in the syntax of [`Node::get_path_checked`], and `*`
+    /// matches any key or index, e.g., `items[*].*`. The empty path refers to
+    /// the root node. The first matched declaration is used.
+    pub fn path(mut self, path: &str, kind: Kind) -> Self {
+        self.rules.push((path.to_string(), kind));
+        self
+    }
+
+    /// Decide the expected types by the function, for the paths which are
+    /// not declared by [`Coercer::path`].
+    ///
+    /// ```
+    /// use yaml_peg::{coerce::{Coercer, Kind}, node, PathSegment};
+    ///
+    /// let n = node!({"env" => node!({"DEBUG" => "false", "NAME" => "app"})});
+    /// let coercer = Coercer::new().callback(|path| match path.segments().last() {
+    ///     Some(PathSegment::Key(key)) if key == "DEBUG" => Some(Kind::Bool),
+    ///     _ => None,
+    /// });
+    /// let ans = node!({"env" => node!({"DEBUG" => false, "NAME" => "app"})});
+    /// assert_eq!(ans, coercer.coerce(&n).unwrap());
+    /// ```
+    pub fn callback(self, callback: CoerceFn) -> Self {
+        Self { callback: Some(callback), ..self }
+    }
+
+    /// Convert the scalars of the tree, returns the error of the first node
+    /// which cannot be converted.
+    ///
+    /// The positions, tags and comments are kept, and the map keys are not
+    /// converted.
+    pub fn coerce<R: Repr>(&self, node: &Node<R>) -> Result<Node<R>, NodeError> {
+        self.coerce_at(node, &mut NodePath::new())
+    }
+
+    fn coerce_at<R: Repr>(
+        &self,
+        node: &Node<R>,
+        path: &mut NodePath,
+    ) -> Result<Node<R>, NodeError> {
+        let s = path.to_string();
+        let kind = self
+            .rules
+            .iter()
+            .find_map(|(rule, kind)| matches(rule, &s).then_some(*kind))
+            .or_else(|| self.callback.and_then(|f| f(path)));
+        if let Some(kind) = kind {
+            return convert(node, kind).map_err(|e| e.prefixed(path));
+        }
+        let yaml = match node.yaml() {
+            Yaml::Seq(v) => Yaml::Seq(
+                v.iter()
+                    .enumerate()
+                    .map(|(i, n)| {
+                        path.push(PathSegment::Ind(i));
+                        let n = self.coerce_at(n, path);
+                        path.pop();
+                        n
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            Yaml::Map(m) => Yaml::Map(
+                m.iter()
+                    .map(|(k, v)| {
+                        let key = k.as_value_checked().unwrap_or_default();
+                        path.push(PathSegment::Key(key.to_string()));
+                        let v = self.coerce_at(v, path);
+                        path.pop();
+                        Ok((k.clone(), v?))
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => return Ok(node.clone()),
+        };
+        let mut n = node.clone();
+        n.set_yaml(yaml);
+        Ok(n)
+    }
+}
+
+/// Convert the scalar to the type.
+fn convert<R: Repr>(node: &Node<R>, kind: Kind) -> Result<Node<R>, NodeError> {
+    let yaml = match (kind, node.yaml()) {
+        (Kind::Null, Yaml::Str(s)) if s.is_empty() => Yaml::Null,
+        (Kind::Float, Yaml::Int(n)) => Yaml::Float(n.clone()),
+        (Kind::Str, Yaml::Null) => Yaml::Str(String::new()),
+        (Kind::Str, Yaml::Bool(b)) => Yaml::Str(b.to_string()),
+        (Kind::Str, Yaml::Int(n)) => Yaml::Str(n.clone()),
+        (Kind::Str, Yaml::Float(n)) => Yaml::Str(n.clone()),
+        (_, Yaml::Str(_)) if kind != Kind::Str => {
+            let n = node.lenient();
+            return match (kind, n.yaml()) {
+                (Kind::Null, Yaml::Null)
+                | (Kind::Bool, Yaml::Bool(_))
+                | (Kind::Int, Yaml::Int(_))
+                | (Kind::Float, Yaml::Float(_)) => Ok(n),
+                (Kind::Float, Yaml::Int(_)) => convert(&n, kind),
+                _ => Err(NodeError::wrong_type(node, kind.name())),
+            };
+        }
+        (Kind::Null, Yaml::Null)
+        | (Kind::Bool, Yaml::Bool(_))
+        | (Kind::Int, Yaml::Int(_))
+        | (Kind::Float, Yaml::Float(_))
+        | (Kind::Str, Yaml::Str(_)) => return Ok(node.clone()),
+        _ => return Err(NodeError::wrong_type(node, kind.name())),
+    };
+    let mut n = node.clone();
+    n.set_yaml(yaml);
+    Ok(n)
+}
+
+/// Match the path with the pattern, `*` matches a non-empty key or index.
+fn matches(pattern: &str, path: &str) -> bool {
+    const SEP: [char; 3] = ['.', '[', ']'];
+    match pattern.split_once('*') {
+        None => pattern == path,
+        Some((head, tail)) => {
+            let Some(path) = path.strip_prefix(head) else {
+                return false;
+            };
+            // The wildcard matches at least one character of a segment
+            let end = path.find(SEP).unwrap_or(path.len());
+            path.char_indices()
+                .map(|(i, _)| i)
+                .chain([path.len()])
+                .skip(1)
+                .take_while(|i| *i <= end)
+                .any(|i| matches(tail, &path[i..]))
+        }
+    }
+}
@@ -1,11 +1,20 @@
 //! Dumper components.
-use crate::{parser::Anchors, repr::Repr, *};
+use crate::{
+    parser::{shared_nodes, Anchors},
+    repr::Repr,
+    *,
+};
 use alloc::{
+    collections::{BTreeMap, BTreeSet},
     format,
     string::{String, ToString},
     vec::Vec,
 };
-use core::fmt::Write;
+use core::{
+    cell::RefCell,
+    cmp::Ordering,
+    fmt::{self, Write},
+};
 
 /// Newline symbol in common platforms.
 ///
@@ -13,136 +22,941 @@ use core::fmt::Write;
 /// "\r\n" in Windows platform, otherwise uses "\n".
 ///
 /// Please be aware that your storage can be used the symbol of Windows.
+/// This is the default of [`DumpOptions::newline`], which is decided at compile
+/// time.
 pub const NL: &str = if cfg!(windows) { "\r\n" } else { "\n" };
 
-#[derive(Eq, PartialEq)]
+/// The newline symbol of the dumped document, see [`DumpOptions::newline`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Newline {
+    /// Unix style, `"\n"`.
+    Lf,
+    /// Windows style, `"\r\n"`.
+    CrLf,
+}
+
+impl Newline {
+    /// The symbol of the newline.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Same as [`NL`].
+impl Default for Newline {
+    fn default() -> Self {
+        if cfg!(windows) {
+            Self::CrLf
+        } else {
+            Self::Lf
+        }
+    }
+}
+
+/// The symbol of the null values, see [`DumpOptions::null_style`].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum NullStyle {
+    /// `null` (default).
+    #[default]
+    Null,
+    /// `~`.
+    Tilde,
+}
+
+impl NullStyle {
+    /// The symbol of the null value.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Tilde => "~",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
 enum Root {
     Scalar,
     Map,
+    Key,
     Array,
 }
 
+/// The generator of the anchor names, see [`DumpOptions::anchor_namer`].
+///
+/// The arguments are the key of the map value which is dumped first (empty if
+/// the node is not a map value), and the serial number of the generated
+/// anchors starting from 1.
+pub type AnchorNamer = fn(&str, usize) -> String;
+
+/// The action of the node, decided by [`DumpOptions::with_filter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+    /// Dump the node as is.
+    Emit,
+    /// Replace the node with the string.
+    Redact(&'static str),
+    /// Remove the item from its parent collection, the root node is dumped
+    /// as null.
+    Skip,
+}
+
+/// The function decides the action of the node by its path and its scalar
+/// value ([`Node::as_value_checked`]), see [`DumpOptions::with_filter`].
+pub type DumpFilter = fn(&NodePath, Option<&str>) -> Filter;
+
+/// The options of the [`Dumper`].
+///
+/// ```
+/// use yaml_peg::{dumper::{dump_with_options, DumpOptions, NL}, node};
+///
+/// let shared = node!({"a" => "b"});
+/// let n = node!([shared.clone(), shared]);
+/// let opt = DumpOptions::new().auto_anchor(true);
+/// let doc = dump_with_options(&[n], &[], opt);
+/// let ans = "\
+/// - &id001
+///   a: b
+/// - *id001
+/// ";
+/// assert_eq!(doc, ans.replace('\n', NL));
+/// ```
+#[derive(Clone, Copy, Default, Debug)]
+pub struct DumpOptions {
+    auto_anchor: bool,
+    anchor_namer: Option<AnchorNamer>,
+    newline: Newline,
+    null_style: NullStyle,
+    sort_keys: Option<fn(&str, &str) -> Ordering>,
+    canonical: bool,
+    keep_style: bool,
+    flow: usize,
+    finite_only: bool,
+    filter: Option<DumpFilter>,
+}
+
+impl DumpOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Detect the nodes which share the same data holder, e.g., the anchors
+    /// are replaced by [`parse`], then give them the generated anchor names
+    /// (`id001`, `id002`, ...) and dump the repeated nodes as aliases.
+    ///
+    /// The anchors of the nodes ([`Node::anchor`]) will not be replaced, their
+    /// repeated nodes are dumped as the aliases of them. The generated names
+    /// avoid the names of the anchors map.
+    pub fn auto_anchor(self, auto_anchor: bool) -> Self {
+        Self { auto_anchor, ..self }
+    }
+
+    /// Generate the names of the [`DumpOptions::auto_anchor`] with the
+    /// function, instead of `id001`, `id002`, ....
+    ///
+    /// The characters other than the ASCII letters and digits are replaced
+    /// with `-`, and the names which are empty after removing the leading `-`
+    /// fall back to the default ones. The names which conflict with the other
+    /// anchors are suffixed with `-2`, `-3`, ....
+    ///
+    /// ```
+    /// use yaml_peg::{dumper::{dump_with_options, DumpOptions, NL}, node};
+    ///
+    /// let base = node!({"port" => 80});
+    /// let n = node!({"default" => base.clone(), "dev" => base, "ids" => node!([1]), "ls" => node!([1])});
+    /// let n = node!([n.clone(), n.get_checked("ids").unwrap().clone()]);
+    /// let opt = DumpOptions::new()
+    ///     .auto_anchor(true)
+    ///     .anchor_namer(|key, _| key.to_string());
+    /// let doc = dump_with_options(&[n], &[], opt);
+    /// let ans = "\
+    /// - default: &default
+    ///     port: 80
+    ///   dev: *default
+    ///   ids: &ids
+    ///     - 1
+    ///   ls:
+    ///     - 1
+    /// - *ids
+    /// ";
+    /// assert_eq!(doc, ans.replace('\n', NL));
+    /// ```
+    pub fn anchor_namer(self, namer: AnchorNamer) -> Self {
+        Self { anchor_namer: Some(namer), ..self }
+    }
+
+    /// Set the newline symbol of the document, default to the symbol of the
+    /// compiled platform ([`NL`]).
+    ///
+    /// The strings with carriage return (`\r`) are always dumped as
+    /// double-quoted strings, so their newlines are kept.
+    ///
+    /// ```
+    /// use yaml_peg::{dumper::{dump_with_options, DumpOptions, Newline}, node};
+    ///
+    /// let n = node!({"a" => "b\nc", "d" => "e\r\nf"});
+    /// let opt = DumpOptions::new().newline(Newline::CrLf);
+    /// let doc = dump_with_options(&[n], &[], opt);
+    /// assert_eq!(doc, "a: |-\r\n  b\r\n  c\r\nd: \"e\\r\\nf\"\r\n");
+    /// ```
+    pub fn newline(self, newline: Newline) -> Self {
+        Self { newline, ..self }
+    }
+
+    /// Set the symbol of the null values, default to `null`. The canonical
+    /// form is not affected.
+    ///
+    /// ```
+    /// use yaml_peg::{dumper::{dump_with_options, DumpOptions, NullStyle, NL}, node};
+    ///
+    /// let n = node!({"a" => (), "b" => node!([()])});
+    /// let opt = DumpOptions::new().null_style(NullStyle::Tilde);
+    /// let doc = dump_with_options(&[n], &[], opt);
+    /// assert_eq!(doc, "a: ~\nb:\n  - ~\n".replace('\n', NL));
+    /// ```
+    pub fn null_style(self, null_style: NullStyle) -> Self {
+        Self { null_style, ..self }
+    }
+
+    /// Sort the map items by their dumped keys, otherwise the insertion order
+    /// is kept (default).
+    ///
+    /// ```
+    /// use yaml_peg::{dumper::{dump_with_options, DumpOptions, NL}, node};
+    ///
+    /// let n = node!({"b" => node!({"d" => 1, "c" => 2}), "a" => 3});
+    /// let opt = DumpOptions::new().sort_keys(true);
+    /// let doc = dump_with_options(&[n], &[], opt);
+    /// let ans = "\
+    /// a: 3
+    /// b:
+    ///   c: 2
+    ///   d: 1
+    /// ";
+    /// assert_eq!(doc, ans.replace('\n', NL));
+    /// ```
+    pub fn sort_keys(self, sort_keys: bool) -> Self {
+        let sort_keys = if sort_keys { Some(str::cmp as _) } else { None };
+        Self { sort_keys, ..self }
+    }
+
+    /// Same as [`DumpOptions::sort_keys`], but sort the dumped keys with the
+    /// comparator.
+    ///
+    /// ```
+    /// use yaml_peg::{dumper::{dump_with_options, DumpOptions, NL}, node};
+    ///
+    /// let n = node!({"b" => 1, "a" => 2, "c" => 3});
+    /// let opt = DumpOptions::new().sort_keys_by(|a, b| b.cmp(a));
+    /// let doc = dump_with_options(&[n], &[], opt);
+    /// assert_eq!(doc, "c: 3\nb: 1\na: 2\n".replace('\n', NL));
+    /// ```
+    pub fn sort_keys_by(self, cmp: fn(&str, &str) -> Ordering) -> Self {
+        Self { sort_keys: Some(cmp), ..self }
+    }
+
+    /// Dump in canonical form, which is useful for comparing or hashing the
+    /// documents regardless of their formatting.
+    ///
+    /// + The nodes without tags are given their core schema tags (`!!str`,
+    ///   `!!int`, `!!map`, ...).
+    /// + The scalars are double-quoted.
+    /// + The numbers are normalized, e.g., `0x10` is dumped as `"16"`.
+    ///
+    /// Combine with [`DumpOptions::sort_keys`] to ignore the order of the map
+    /// items.
+    ///
+    /// ```
+    /// use yaml_peg::{dumper::{dump_with_options, DumpOptions, NL}, node};
+    ///
+    /// let n = node!({"a" => node!([0x10, 1.50, ()]), "b" => true});
+    /// let opt = DumpOptions::new().canonical(true);
+    /// let doc = dump_with_options(&[n], &[], opt);
+    /// let ans = "\
+    /// !!map
+    /// !!str \"a\": !!seq
+    ///   - !!int \"16\"
+    ///   - !!float \"1.5\"
+    ///   - !!null \"\"
+    /// !!str \"b\": !!bool \"true\"
+    /// ";
+    /// assert_eq!(doc, ans.replace('\n', NL));
+    /// ```
+    pub fn canonical(self, canonical: bool) -> Self {
+        Self { canonical, ..self }
+    }
+
+    /// Dump the quoted strings with their original quotes, see
+    /// [`Node::style`]. The other strings are decided by the dumper.
+    ///
+    /// ```
+    /// use yaml_peg::{dumper::{dump_with_options, DumpOptions, NL}, parse, repr::RcRepr};
+    ///
+    /// let doc = "a: 'it''s'\nb: \"c\"\nd: e\n";
+    /// let root = parse::<RcRepr>(doc).unwrap();
+    /// let opt = DumpOptions::new().keep_style(true);
+    /// assert_eq!(dump_with_options(&root, &[], opt), doc.replace('\n', NL));
+    /// ```
+    pub fn keep_style(self, keep_style: bool) -> Self {
+        Self { keep_style, ..self }
+    }
+
+    /// Dump the collections in flow style (`[a, b]` and `{a: b}`) if their
+    /// flow form is not wider than the threshold, zero to disable (default).
+    ///
+    /// The collections with the nested properties (anchors and tags) are
+    /// still dumped in block style. This option is ignored in the canonical
+    /// form.
+    ///
+    /// ```
+    /// use yaml_peg::{dumper::{dump_with_options, DumpOptions, NL}, node};
+    ///
+    /// let n = node!({"a" => node!([node!([1, 0]), node!([0, 1])]), "b" => node!({"c" => "d, e"})});
+    /// let opt = DumpOptions::new().prefer_flow(20);
+    /// let doc = dump_with_options(&[n], &[], opt);
+    /// let ans = "\
+    /// a: [[1, 0], [0, 1]]
+    /// b: {c: 'd, e'}
+    /// ";
+    /// assert_eq!(doc, ans.replace('\n', NL));
+    /// ```
+    pub fn prefer_flow(self, threshold: usize) -> Self {
+        Self { flow: threshold, ..self }
+    }
+
+    /// Reject the non-finite floats, for the documents that will be converted
+    /// to JSON. Otherwise, they are dumped as `.nan`, `.inf` and `-.inf`.
+    ///
+    /// The error is returned by [`try_dump_with_options`] and
+    /// [`dump_to_writer_with_options`], [`dump_with_options`] will panic.
+    ///
+    /// ```
+    /// use yaml_peg::{dumper::{try_dump_with_options, DumpOptions, NL}, node, NodeErrorKind};
+    ///
+    /// let n = node!([1.5, f64::NAN, f64::NEG_INFINITY]);
+    /// let doc = try_dump_with_options(&[n.clone()], &[], DumpOptions::new()).unwrap();
+    /// assert_eq!(doc, "- 1.5\n- .nan\n- -.inf\n".replace('\n', NL));
+    /// let opt = DumpOptions::new().finite_only(true);
+    /// let e = try_dump_with_options(&[n], &[], opt).unwrap_err();
+    /// assert_eq!(e.kind, NodeErrorKind::OutOfRange);
+    /// ```
+    pub fn finite_only(self, finite_only: bool) -> Self {
+        Self { finite_only, ..self }
+    }
+
+    /// Mask or drop the nodes before dumping, such as the passwords and the
+    /// tokens in the logged configurations. The tree is not modified.
+    ///
+    /// The function is called with the paths of the sequence items and the
+    /// map values (the keys are not filtered), the descendants of the
+    /// redacted and skipped nodes are not visited.
+    ///
+    /// ```
+    /// use yaml_peg::{
+    ///     dumper::{dump_with_options, DumpOptions, Filter, NL},
+    ///     node, PathSegment,
+    /// };
+    ///
+    /// let n = node!({"user" => "admin", "password" => "1234", "tokens" => node!(["a", "b"]), "debug" => true});
+    /// let opt = DumpOptions::new().with_filter(|path, _| match path.segments().last() {
+    ///     Some(PathSegment::Key(k)) if k == "password" => Filter::Redact("***"),
+    ///     Some(PathSegment::Key(k)) if k == "tokens" => Filter::Skip,
+    ///     _ => Filter::Emit,
+    /// });
+    /// let doc = dump_with_options(&[n], &[], opt);
+    /// assert_eq!(doc, "user: admin\npassword: '***'\ndebug: true\n".replace('\n', NL));
+    /// ```
+    pub fn with_filter(self, filter: DumpFilter) -> Self {
+        Self { filter: Some(filter), ..self }
+    }
+}
+
+/// The anchor names of the anchors map, indexed by the data holders.
+type AnchorIndex<R> = BTreeMap<*const Yaml<R>, String>;
+
+/// The anchor name of the node, or the name of the anchors map which holds the
+/// same data if the node is not anchored and not resolved from an alias.
+fn anchor_of<'a, R: Repr>(node: &'a Node<R>, index: Option<&'a AnchorIndex<R>>) -> &'a str {
+    match (node.anchor(), index) {
+        ("", Some(index)) if node.anchor_name().is_none() => index
+            .get(&(node.yaml() as *const _))
+            .map_or("", String::as_str),
+        (a, _) => a,
+    }
+}
+
+/// The generated anchor names of the shared nodes.
+struct AutoAnchors<R: Repr> {
+    names: BTreeMap<*const Yaml<R>, String>,
+    emitted: RefCell<BTreeSet<*const Yaml<R>>>,
+}
+
+impl<R: Repr> AutoAnchors<R> {
+    fn new(
+        node: &Node<R>,
+        anchors: &Anchors<R>,
+        index: &AnchorIndex<R>,
+        namer: Option<AnchorNamer>,
+    ) -> Self {
+        let mut id = 0;
+        let mut names = BTreeMap::new();
+        let shared = shared_nodes(node);
+        // The anchored nodes keep their names, and the copies become aliases
+        let mut used = shared
+            .iter()
+            .map(|(n, _)| anchor_of(n, Some(index)))
+            .filter(|a| !a.is_empty())
+            .map(String::from)
+            .collect::<BTreeSet<_>>();
+        for (n, key) in shared {
+            let anchor = anchor_of(n, Some(index));
+            if !anchor.is_empty() {
+                names.insert(n.yaml() as *const _, anchor.to_string());
+                continue;
+            }
+            id += 1;
+            let name = match namer {
+                Some(namer) => namer(key, id)
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                    .collect(),
+                None => String::new(),
+            };
+            let name = match name.trim_start_matches('-') {
+                "" => format!("id{id:03}"),
+                s => s.to_string(),
+            };
+            let mut unique = name.clone();
+            let mut i = 1;
+            while anchors.contains_key(&unique) || used.contains(&unique) {
+                i += 1;
+                unique = format!("{name}-{i}");
+            }
+            used.insert(unique.clone());
+            names.insert(n.yaml() as *const _, unique);
+        }
+        Self { names, emitted: RefCell::new(BTreeSet::new()) }
+    }
+}
+
 /// Dumper for nodes.
 pub struct Dumper<'a, R: Repr> {
     node: &'a Node<R>,
     root: Root,
     level: usize,
     anchors: &'a Anchors<R>,
+    opt: DumpOptions,
+    index: Option<&'a AnchorIndex<R>>,
+    auto: Option<&'a AutoAnchors<R>>,
 }
 
 impl<'a, R: Repr> Dumper<'a, R> {
     /// Create the dumper.
     pub fn new(node: &'a Node<R>, anchors: &'a Anchors<R>) -> Self {
-        Self { node, root: Root::Scalar, level: 0, anchors }
+        Self {
+            node,
+            root: Root::Scalar,
+            level: 0,
+            anchors,
+            opt: DumpOptions::default(),
+            index: None,
+            auto: None,
+        }
+    }
+
+    /// Set the options of the dumper.
+    pub fn with_options(self, opt: DumpOptions) -> Self {
+        Self { opt, ..self }
     }
 
     fn part(&self, node: &'a Node<R>, root: Root, level: usize) -> String {
-        Self { node, root, level, anchors: self.anchors }.dump()
+        Self { node, root, level, ..*self }.dump_node()
+    }
+
+    /// Return true if the string can be dumped as a literal block, where the
+    /// indentation of the first line can be detected, and the trailing
+    /// whitespaces of the lines are not stripped.
+    fn literal(s: &str) -> bool {
+        if s.contains('\r')
+            || !s.contains('\n')
+            || !s.chars().all(|c| c == '\n' || single_quoted(c))
+            || s.split('\n')
+                .any(|line| line.ends_with(char::is_whitespace))
+        {
+            return false;
+        }
+        match s.split('\n').find(|line| !line.is_empty()) {
+            Some(line) => !line.starts_with(char::is_whitespace),
+            None => false,
+        }
+    }
+
+    /// Return true if the string can be dumped as a plain string, which is
+    /// parsed back into the same string by this parser, and by the parsers of
+    /// YAML 1.1 as well.
+    ///
+    /// The single letter booleans of YAML 1.1 (`y` and `n`) are not quoted,
+    /// they are rarely supported and common as the keys.
+    fn plain(s: &str, flow: bool) -> bool {
+        let Some(first) = s.chars().next() else {
+            return false;
+        };
+        let indicator = match first {
+            // The indicators followed by the boundaries of the parser
+            '-' | '?' | ':' => {
+                s.len() == 1 || s[1..].starts_with([' ', '\t', '[', ']', '{', '}', ',', ':'])
+            }
+            c => "[]{}#&*!|>'\"%@`,".contains(c),
+        };
+        !indicator
+            && !s.starts_with(char::is_whitespace)
+            && !s.ends_with(char::is_whitespace)
+            && !s.ends_with(':')
+            && !s.contains(": ")
+            && !s.contains(" #")
+            && !(flow && s.contains([',', '[', ']', '{', '}']))
+            && s.chars().all(|c| c != '\t' && single_quoted(c))
+            && !yaml11_special(s)
+            && (matches!(s, "y" | "Y" | "n" | "N")
+                || matches!(
+                    parser::Loader::<R>::resolve_plain(s.into(), parser::Schema::Yaml11),
+                    Yaml::Str(_)
+                ))
+            && matches!(parser::Parser::new(s.as_bytes()).string_plain(0, flow), Ok(v) if v == s)
+    }
+
+    /// Dump the single line string, quote it if required.
+    ///
+    /// The single quotes are preferred, and the double quotes are used for
+    /// the strings with escaped characters.
+    fn string(&self, node: &Node<R>, s: &str, flow: bool) -> String {
+        let single = s.chars().all(single_quoted);
+        match node.style() {
+            ScalarStyle::SingleQuoted if self.opt.keep_style && single => {
+                format!("'{}'", s.replace('\'', "''"))
+            }
+            ScalarStyle::DoubleQuoted if self.opt.keep_style => double_quoted(s),
+            _ if Self::plain(s, flow) => s.to_string(),
+            _ if single => format!("'{}'", s.replace('\'', "''")),
+            _ => double_quoted(s),
+        }
+    }
+
+    /// Dump the comment lines of the node, followed by the indentation of the
+    /// next line.
+    fn comment(&self, node: &Node<R>, ind: &str) -> String {
+        let nl = self.opt.newline.as_str();
+        let mut doc = String::new();
+        if node.comment().is_empty() {
+            return doc;
+        }
+        for line in node.comment().lines() {
+            let line = line.trim_end();
+            let sep = if line.is_empty() { "" } else { " " };
+            write!(doc, "#{sep}{line}{nl}{ind}").unwrap();
+        }
+        doc
+    }
+
+    /// Dump the node in flow style if it is not wider than the threshold.
+    ///
+    /// The properties of the root node are dumped by the caller, and the
+    /// nested properties are not supported.
+    fn flow(&self, node: &'a Node<R>, root: bool) -> Option<String> {
+        if !root
+            && (!node.raw_tag().is_empty()
+                || !node.comment().is_empty()
+                || !anchor_of(node, self.index).is_empty()
+                || self
+                    .auto
+                    .is_some_and(|auto| auto.names.contains_key(&(node.yaml() as *const _))))
+        {
+            return None;
+        }
+        let s = match node.yaml() {
+            Yaml::Seq(v) => {
+                let v = v
+                    .iter()
+                    .map(|n| self.flow(n, false))
+                    .collect::<Option<Vec<_>>>()?;
+                format!("[{}]", v.join(", "))
+            }
+            Yaml::Map(m) => {
+                let mut items = m
+                    .iter()
+                    .map(|(k, v)| match k.yaml() {
+                        Yaml::Seq(_) | Yaml::Map(_) => None,
+                        _ if is_set(node) => Some((self.flow(k, false)?, String::new())),
+                        _ => Some((self.flow(k, false)?, self.flow(v, false)?)),
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                if let Some(cmp) = self.opt.sort_keys {
+                    items.sort_by(|(a, _), (b, _)| cmp(a, b));
+                }
+                let items = items
+                    .into_iter()
+                    .map(|(k, v)| if v.is_empty() { k } else { format!("{k}: {v}") })
+                    .collect::<Vec<_>>();
+                format!("{{{}}}", items.join(", "))
+            }
+            Yaml::Str(s) => self.string(node, s, true),
+            _ => Self { node, ..*self }.dump_node(),
+        };
+        (s.chars().count() <= self.opt.flow).then_some(s)
     }
 
     /// Dump into string.
     pub fn dump(&self) -> String {
         let mut doc = String::new();
-        if let Some(a) = self
-            .anchors
-            .iter()
-            .find_map(|(k, v)| if v == self.node { Some(k) } else { None })
-        {
-            write!(doc, "&{a} ").unwrap();
+        self.dump_to(&mut doc).unwrap();
+        doc
+    }
+
+    /// Dump into the writer, the items of the root collection are written one
+    /// by one.
+    fn dump_to(&self, w: &mut dyn Write) -> fmt::Result {
+        if let Some(f) = self.opt.filter {
+            let mut path = NodePath::new();
+            let node = match f(&path, self.node.as_value_checked().ok()) {
+                Filter::Emit => filter_tree(self.node, f, &mut path),
+                Filter::Redact(s) => Some(redact(self.node, s)),
+                Filter::Skip => Some(Node::new(Yaml::Null, self.node.pos(), "")),
+            };
+            let node = node.as_ref().unwrap_or(self.node);
+            let opt = DumpOptions { filter: None, ..self.opt };
+            return Dumper { node, opt, ..*self }.dump_to(w);
         }
-        let tag = self.node.tag();
-        if !tag.is_empty() && !tag.starts_with(parser::tag_prefix!()) {
-            if tag.starts_with(parser::tag_prefix!()) {
+        let Some(index) = self.index else {
+            // Build the index once instead of searching the anchors map
+            let index = self
+                .anchors
+                .iter()
+                .map(|(k, v)| (v.yaml() as *const _, k.clone()))
+                .collect();
+            return Dumper { index: Some(&index), ..*self }.dump_to(w);
+        };
+        if self.opt.auto_anchor && self.auto.is_none() {
+            let auto = AutoAnchors::new(self.node, self.anchors, index, self.opt.anchor_namer);
+            Dumper { auto: Some(&auto), ..*self }.write_node(w)
+        } else {
+            self.write_node(w)
+        }
+    }
+
+    fn dump_node(&self) -> String {
+        let mut doc = String::new();
+        self.write_node(&mut doc).unwrap();
+        doc
+    }
+
+    fn write_node(&self, w: &mut dyn Write) -> fmt::Result {
+        let nl = self.opt.newline.as_str();
+        let mut doc = String::new();
+        if let Some(name) = self.auto.and_then(|auto| {
+            let ptr = self.node.yaml() as *const _;
+            let name = auto.names.get(&ptr)?;
+            Some((name, auto.emitted.borrow_mut().insert(ptr)))
+        }) {
+            match name {
+                (name, true) => write!(doc, "&{name} ").unwrap(),
+                (name, false) => return write!(w, "*{name}"),
+            }
+        } else {
+            let anchor = anchor_of(self.node, self.index);
+            if !anchor.is_empty() {
+                write!(doc, "&{anchor} ").unwrap();
+            }
+        }
+        let tag = self.node.raw_tag();
+        if !tag.is_empty() {
+            if let Some(tag) = tag.strip_prefix(parser::tag_prefix!()) {
                 write!(doc, "!!{tag} ").unwrap();
-            } else if parser::Parser::new(tag.as_bytes()).identifier().is_ok() {
+            } else if tag
+                .bytes()
+                .all(|c| parser::Parser::is_uri_char(b"!,[]{}%")(&c))
+            {
                 write!(doc, "!{tag} ").unwrap();
             } else {
-                write!(doc, "!<{tag}> ").unwrap();
+                doc.push_str("!<");
+                for c in tag.bytes() {
+                    if c != b'%' && parser::Parser::is_uri_char(b"")(&c) {
+                        doc.push(c as char);
+                    } else {
+                        write!(doc, "%{c:02X}").unwrap();
+                    }
+                }
+                doc.push_str("> ");
+            }
+        } else if self.opt.canonical {
+            let tag = match self.node.yaml() {
+                Yaml::Null => "null",
+                Yaml::Bool(_) => "bool",
+                Yaml::Int(_) => "int",
+                Yaml::Float(_) => "float",
+                Yaml::Str(_) => "str",
+                Yaml::Seq(_) => "seq",
+                Yaml::Map(_) => "map",
+                Yaml::Alias(_) => "",
+            };
+            if !tag.is_empty() {
+                write!(doc, "!!{tag} ").unwrap();
+            }
+        }
+        if self.opt.flow > 0
+            && !self.opt.canonical
+            && matches!(self.node.yaml(), Yaml::Seq(_) | Yaml::Map(_))
+        {
+            if let Some(s) = self.flow(self.node, true) {
+                doc += &s;
+                return w.write_str(&doc);
             }
         }
         let ind = "  ".repeat(self.level);
         match &self.node.yaml() {
-            Yaml::Null => doc += "null",
+            Yaml::Null if self.opt.canonical => doc += "\"\"",
+            Yaml::Bool(b) if self.opt.canonical => write!(doc, "\"{b}\"").unwrap(),
+            Yaml::Int(n) if self.opt.canonical => match (to_i64(n), to_u64(n)) {
+                (Ok(n), _) => write!(doc, "\"{n}\"").unwrap(),
+                (_, Ok(n)) => write!(doc, "\"{n}\"").unwrap(),
+                _ => doc += &double_quoted(n.as_str()),
+            },
+            Yaml::Float(n) if self.opt.canonical => match to_f64(n) {
+                Ok(f) if f.is_nan() => doc += "\".nan\"",
+                Ok(f) if f.is_infinite() => {
+                    let sign = if f < 0. { "-" } else { "" };
+                    write!(doc, "\"{sign}.inf\"").unwrap();
+                }
+                Ok(f) => write!(doc, "\"{f:?}\"").unwrap(),
+                Err(_) => doc += &double_quoted(n.as_str()),
+            },
+            Yaml::Str(s) if self.opt.canonical => doc += &double_quoted(s),
+            Yaml::Seq(v) if v.is_empty() => doc += "[]",
+            Yaml::Map(m) if m.is_empty() => doc += "{}",
+            Yaml::Null => doc += self.opt.null_style.as_str(),
             Yaml::Bool(b) => write!(doc, "{b}").unwrap(),
-            Yaml::Int(n) | Yaml::Float(n) => doc += n,
+            Yaml::Int(n) => doc += n.as_str(),
+            Yaml::Float(n) => match to_f64(n) {
+                // The lexemes of Rust (`NaN` and `inf`) are not valid in YAML
+                Ok(f) if f.is_nan() => doc += ".nan",
+                Ok(f) if f.is_infinite() => doc += if f < 0. { "-.inf" } else { ".inf" },
+                _ => doc += n.as_str(),
+            },
             Yaml::Str(s) => {
-                if s.lines().nth(1).is_some() {
-                    // Multiline string
-                    let s = s
-                        .lines()
-                        .map(|s| {
-                            if s.is_empty() {
-                                String::new()
-                            } else {
-                                ind.to_string() + s.trim_end()
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                        .join(NL);
-                    write!(doc, "|{NL}{ind}{}", s.trim()).unwrap();
-                } else if parser::Parser::new(s.as_bytes())
-                    .string_plain(0, false)
-                    .is_err()
-                {
-                    // Literal string, not plain string
-                    write!(doc, "{s:?}").unwrap();
+                let quoted = self.opt.keep_style && self.node.style() == ScalarStyle::DoubleQuoted;
+                if Self::literal(s) && !quoted && self.root != Root::Key {
+                    // Multiline string, the trailing newlines are decided by
+                    // the chomping indicator, the keys are always quoted
+                    let body = s.trim_end_matches('\n');
+                    let chomp = match s.len() - body.len() {
+                        0 => "-",
+                        1 => "",
+                        _ => "+",
+                    };
+                    // The root string is still indented
+                    let ind = if self.level == 0 { "  " } else { &ind };
+                    write!(doc, "|{chomp}").unwrap();
+                    for line in body.split('\n') {
+                        doc += nl;
+                        if !line.is_empty() {
+                            write!(doc, "{ind}{line}").unwrap();
+                        }
+                    }
+                    for _ in 1..s.len() - body.len() {
+                        doc += nl;
+                    }
                 } else {
                     // Single line string
-                    doc += s;
+                    doc += &self.string(self.node, s, false);
                 }
             }
             Yaml::Seq(v) => {
-                let mut buf = NL.to_string();
+                // Properties are placed before the line
+                doc.truncate(doc.trim_end().len());
+                write!(w, "{doc}{nl}")?;
                 for (i, node) in v.iter().enumerate() {
+                    let mut buf = if i == 0 {
+                        String::new()
+                    } else {
+                        nl.to_string()
+                    };
                     if i != 0 || self.level != 0 {
                         buf += &ind;
                     }
+                    buf += &self.comment(node, &ind);
                     let s = self.part(node, Root::Array, self.level + 1);
-                    write!(buf, "- {s}{NL}").unwrap();
+                    write!(buf, "- {s}")?;
+                    w.write_str(&buf)?;
                 }
-                buf.truncate(buf.len() - NL.len());
-                doc += &buf;
+                return Ok(());
             }
             Yaml::Map(m) => {
-                let mut buf = match self.root {
-                    Root::Map => NL.to_string(),
-                    _ => String::new(),
-                };
-                for (i, (k, v)) in m.iter().enumerate() {
-                    if i != 0 || self.root == Root::Map {
+                // Properties are placed before the line
+                let wrap = matches!(self.root, Root::Map | Root::Key) || !doc.is_empty();
+                doc.truncate(doc.trim_end().len());
+                w.write_str(&doc)?;
+                if wrap {
+                    w.write_str(nl)?;
+                }
+                let mut items = m.iter().collect::<Vec<_>>();
+                if let Some(cmp) = self.opt.sort_keys {
+                    // Dump the keys without emitting the auto anchors
+                    let dumper = Self { auto: None, ..*self };
+                    let mut keys = items
+                        .into_iter()
+                        .map(|(k, v)| (dumper.part(k, Root::Key, self.level + 1), k, v))
+                        .collect::<Vec<_>>();
+                    keys.sort_by(|(a, ..), (b, ..)| cmp(a, b));
+                    items = keys.into_iter().map(|(_, k, v)| (k, v)).collect();
+                }
+                for (i, (k, v)) in items.into_iter().enumerate() {
+                    let mut buf = if i == 0 {
+                        String::new()
+                    } else {
+                        nl.to_string()
+                    };
+                    if i != 0 || wrap {
                         buf += &ind;
                     }
-                    let s = self.part(k, Root::Map, self.level + 1);
+                    buf += &self.comment(k, &ind);
+                    buf += &self.comment(v, &ind);
+                    let s = self.part(k, Root::Key, self.level + 1);
+                    if is_set(self.node) {
+                        // The set items are written as the keys without value
+                        let sep = if s.starts_with(nl) { "" } else { " " };
+                        write!(buf, "?{sep}{s}")?;
+                        w.write_str(&buf)?;
+                        continue;
+                    }
                     if matches!(k.yaml(), Yaml::Map(_) | Yaml::Seq(_)) {
-                        let pre_ind = "  ".repeat(self.level + 1);
-                        write!(buf, "?{pre_ind}{NL}{s}{NL}{ind}").unwrap();
+                        // The properties are placed behind the indicator
+                        let sep = if s.starts_with(nl) { "" } else { " " };
+                        write!(buf, "?{sep}{s}{nl}{ind}")?;
                     } else {
                         buf += &s;
                     };
                     buf += ":";
-                    buf += &match v.yaml() {
-                        Yaml::Map(_) => self.part(v, Root::Map, self.level + 1),
+                    let s = match v.yaml() {
                         Yaml::Seq(_) if self.root == Root::Array && i == 0 => {
                             self.part(v, Root::Map, self.level)
                         }
-                        Yaml::Seq(_) => self.part(v, Root::Map, self.level + 1),
-                        _ => format!(" {}", self.part(v, Root::Map, self.level + 1)),
+                        _ => self.part(v, Root::Map, self.level + 1),
                     };
-                    buf += NL;
+                    if !s.starts_with(nl) {
+                        buf += " ";
+                    }
+                    buf += &s;
+                    w.write_str(&buf)?;
                 }
-                buf.truncate(buf.len() - NL.len());
-                doc += &buf;
+                return Ok(());
             }
             Yaml::Alias(a) => write!(doc, "*{a}").unwrap(),
         };
-        doc
+        w.write_str(&doc)
     }
 }
 
+/// Return true if the node is a `!!set` map, and its values are the plain
+/// nulls, so the values can be omitted.
+fn is_set<R: Repr>(node: &Node<R>) -> bool {
+    node.is_tag("!!set")
+        && node
+            .entries()
+            .all(|(_, v)| v.is_null() && v.raw_tag().is_empty() && v.comment().is_empty())
+}
+
+/// Return true if the character is printable in YAML, except the line breaks.
+/// These characters can be written in the single-quoted strings.
+fn single_quoted(c: char) -> bool {
+    matches!(c, '\t' | ' '..='~' | '\u{A0}'..='\u{2027}' | '\u{202A}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..)
+        && c != '\u{FEFF}'
+}
+
+/// Return true if the string might be resolved as other types by the
+/// parsers of YAML 1.1, e.g., sexagesimal numbers (`12:30`), the numbers with
+/// underscores (`1_000`), binary numbers (`0b10`), dates (`2001-12-14`) and
+/// the merge key (`<<`).
+fn yaml11_special(s: &str) -> bool {
+    if matches!(s, "<<" | "=") {
+        return true;
+    }
+    let b = s.as_bytes();
+    if b.len() >= 8
+        && b[..4].iter().all(u8::is_ascii_digit)
+        && b[4] == b'-'
+        && b[5].is_ascii_digit()
+    {
+        return true;
+    }
+    let n = s.strip_prefix(['+', '-']).unwrap_or(s);
+    if let Some(n) = n.strip_prefix("0b") {
+        return !n.is_empty() && n.chars().all(|c| matches!(c, '0' | '1' | '_'));
+    }
+    n.starts_with(|c: char| c.is_ascii_digit())
+        && n.contains(['_', ':'])
+        && n.chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '_' | ':' | '.'))
+}
+
+/// Dump the string in double quotes with the YAML escape sequences.
+fn double_quoted(s: &str) -> String {
+    format!("\"{}\"", escape_for_double_quotes(s))
+}
+
+/// Escape the string as the content of the double-quoted string, the inverse
+/// of [`Parser::unescape`](crate::parser::Parser::unescape).
+///
+/// The quotes, the backslashes, the line breaks and the characters outside
+/// the printable set of YAML (e.g., the control characters and the byte order
+/// mark) are escaped by the YAML escape sequences, the other Unicode
+/// characters are kept.
+///
+/// ```
+/// use yaml_peg::dumper::escape_for_double_quotes;
+///
+/// let s = "say \"hi\"\n\t😀\u{1}\u{9F}\u{FEFF}\u{FFFF}";
+/// let ans = r#"say \"hi\"\n\t😀\x01\x9F\uFEFF\uFFFF"#;
+/// assert_eq!(escape_for_double_quotes(s), ans);
+/// ```
+pub fn escape_for_double_quotes(s: &str) -> String {
+    let mut doc = String::new();
+    for c in s.chars() {
+        match c {
+            '"' => doc += "\\\"",
+            '\\' => doc += "\\\\",
+            '\0' => doc += "\\0",
+            '\x07' => doc += "\\a",
+            '\x08' => doc += "\\b",
+            '\t' => doc += "\\t",
+            '\n' => doc += "\\n",
+            '\x0B' => doc += "\\v",
+            '\x0C' => doc += "\\f",
+            '\r' => doc += "\\r",
+            '\x1B' => doc += "\\e",
+            '\u{85}' => doc += "\\N",
+            '\u{A0}' => doc += "\\_",
+            '\u{2028}' => doc += "\\L",
+            '\u{2029}' => doc += "\\P",
+            c if single_quoted(c) => doc.push(c),
+            c if (c as u32) <= 0xFF => write!(doc, "\\x{:02X}", c as u32).unwrap(),
+            c if (c as u32) <= 0xFFFF => write!(doc, "\\u{:04X}", c as u32).unwrap(),
+            c => write!(doc, "\\U{:08X}", c as u32).unwrap(),
+        }
+    }
+    doc
+}
+
 /// Dump the YAML data in to block format.
 ///
 /// Dumper will use plain string when the string is none-wrapped,
-/// otherwise it use literal string and trim the last white spaces.
+/// otherwise it use literal string with the chomping indicator (`|`, `|-` or
+/// `|+`) decided by its trailing newlines. The strings which might be resolved
+/// as other types (`yes`, `12:30`, `0x1F`, ...) or started with the
+/// indicators are quoted, in single quotes unless the escape sequences are
+/// required. The map items keep their insertion
+/// order, see [`DumpOptions::sort_keys`] for the sorted output.
 ///
 /// ```
 /// use yaml_peg::{dump, node, dumper::NL};
@@ -163,23 +977,221 @@ impl<'a, R: Repr> Dumper<'a, R> {
 /// When calling [`parse`] function then [`dump`] the string, the string can be
 /// reformatted.
 ///
-/// Anchors can pass with the result of the [`Loader`](crate::parser::Loader).
+/// The anchors of the nodes are emitted as `&name`, see [`Node::anchor`].
+/// The anchors maps, such as the result of the
+/// [`Loader`](crate::parser::Loader), can be passed as well, then the nodes
+/// which share the data holders with the anchored nodes are emitted with
+/// their names, and the names generated by [`DumpOptions::auto_anchor`] will
+/// not conflict with them.
 pub fn dump<R: Repr>(nodes: &[Node<R>], anchors: &[Anchors<R>]) -> String {
+    dump_with_options(nodes, anchors, DumpOptions::default())
+}
+
+/// Same as [`dump`], but with the options. See [`DumpOptions`].
+///
+/// # Panics
+///
+/// Panics if a float is rejected by [`DumpOptions::finite_only`], use
+/// [`try_dump_with_options`] instead.
+pub fn dump_with_options<R: Repr>(
+    nodes: &[Node<R>],
+    anchors: &[Anchors<R>],
+    opt: DumpOptions,
+) -> String {
+    try_dump_with_options(nodes, anchors, opt).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Same as [`dump_with_options`], but returns the error of the rejected
+/// float instead of panicking, see [`DumpOptions::finite_only`].
+pub fn try_dump_with_options<R: Repr>(
+    nodes: &[Node<R>],
+    anchors: &[Anchors<R>],
+    opt: DumpOptions,
+) -> Result<String, NodeError> {
+    check_finite(nodes, opt)?;
+    let mut doc = String::new();
+    dump_docs(&mut doc, nodes, anchors, opt).unwrap();
+    Ok(doc)
+}
+
+/// Same as [`dump`], but writes the documents into the writer.
+///
+/// The output is not built in the memory, the documents and the items of
+/// their root collections are written one by one. The writes are small, so
+/// the unbuffered writers such as [`File`](std::fs::File) should be wrapped
+/// in a [`BufWriter`](std::io::BufWriter).
+///
+/// ```
+/// use yaml_peg::{dumper::{dump_to_writer, NL}, node};
+///
+/// let mut doc = Vec::new();
+/// dump_to_writer(&mut doc, &[node!([1, 2]), node!({"a" => "b"})], &[]).unwrap();
+/// let ans = "\
+/// - 1
+/// - 2
+/// ---
+/// a: b
+/// ";
+/// assert_eq!(String::from_utf8(doc).unwrap(), ans.replace('\n', NL));
+/// ```
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+pub fn dump_to_writer<R: Repr>(
+    writer: impl std::io::Write,
+    nodes: &[Node<R>],
+    anchors: &[Anchors<R>],
+) -> std::io::Result<()> {
+    dump_to_writer_with_options(writer, nodes, anchors, DumpOptions::default())
+}
+
+/// Same as [`dump_to_writer`], but with the options. See [`DumpOptions`].
+///
+/// The float rejected by [`DumpOptions::finite_only`] is returned as an error
+/// of [`InvalidData`](std::io::ErrorKind::InvalidData) kind, and nothing is
+/// written.
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+pub fn dump_to_writer_with_options<R: Repr>(
+    writer: impl std::io::Write,
+    nodes: &[Node<R>],
+    anchors: &[Anchors<R>],
+    opt: DumpOptions,
+) -> std::io::Result<()> {
+    check_finite(nodes, opt)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let mut w = IoWriter { writer, err: None };
+    dump_docs(&mut w, nodes, anchors, opt).map_err(|_| {
+        w.err
+            .unwrap_or_else(|| std::io::Error::other("formatter error"))
+    })
+}
+
+/// Apply the filter to the descendants of the node, returns `None` if they are
+/// not changed, so the unchanged data holders are still shared.
+fn filter_tree<R: Repr>(node: &Node<R>, f: DumpFilter, path: &mut NodePath) -> Option<Node<R>> {
+    let mut changed = false;
+    let mut item = |n: &Node<R>, path: &mut NodePath| match f(path, n.as_value_checked().ok()) {
+        Filter::Emit => Some(
+            filter_tree(n, f, path)
+                .inspect(|_| changed = true)
+                .unwrap_or_else(|| n.clone()),
+        ),
+        Filter::Redact(s) => {
+            changed = true;
+            Some(redact(n, s))
+        }
+        Filter::Skip => {
+            changed = true;
+            None
+        }
+    };
+    let yaml = match node.yaml() {
+        Yaml::Seq(v) => Yaml::Seq(
+            v.iter()
+                .enumerate()
+                .filter_map(|(i, n)| {
+                    path.push(PathSegment::Ind(i));
+                    let n = item(n, path);
+                    path.pop();
+                    n
+                })
+                .collect(),
+        ),
+        Yaml::Map(m) => Yaml::Map(
+            m.iter()
+                .filter_map(|(k, v)| {
+                    let key = k.as_value_checked().unwrap_or_default();
+                    path.push(PathSegment::Key(key.to_string()));
+                    let v = item(v, path);
+                    path.pop();
+                    Some((k.clone(), v?))
+                })
+                .collect(),
+        ),
+        _ => return None,
+    };
+    if !changed {
+        return None;
+    }
+    let mut n = node.clone();
+    n.set_yaml(yaml);
+    Some(n)
+}
+
+/// Replace the node with the plain string, the position is kept.
+fn redact<R: Repr>(node: &Node<R>, s: &str) -> Node<R> {
+    Node::new(Yaml::Str(s.into()), node.pos(), "")
+}
+
+/// Find the non-finite floats if they are rejected by the options.
+fn check_finite<R: Repr>(nodes: &[Node<R>], opt: DumpOptions) -> Result<(), NodeError> {
+    fn find<R: Repr>(node: &Node<R>) -> Option<u64> {
+        match node.yaml() {
+            Yaml::Float(n) => to_f64(n).is_ok_and(|f| !f.is_finite()).then(|| node.pos()),
+            Yaml::Seq(v) => v.iter().find_map(find),
+            Yaml::Map(m) => m.iter().find_map(|(k, v)| find(k).or_else(|| find(v))),
+            _ => None,
+        }
+    }
+    if !opt.finite_only {
+        return Ok(());
+    }
+    match nodes.iter().find_map(find) {
+        Some(pos) => Err(NodeError::out_of_range(pos)),
+        None => Ok(()),
+    }
+}
+
+fn dump_docs<R: Repr>(
+    w: &mut dyn Write,
+    nodes: &[Node<R>],
+    anchors: &[Anchors<R>],
+    opt: DumpOptions,
+) -> fmt::Result {
     let anchors_empty = Anchors::new();
-    nodes
-        .iter()
-        .enumerate()
-        .map(|(i, node)| {
-            let anchors = if i < anchors.len() {
-                &anchors[i]
-            } else {
-                &anchors_empty
-            };
-            let doc = Dumper::new(node, anchors).dump() + NL;
-            match i {
-                0 => doc,
-                _ => format!("---{NL}{}", doc.trim_start()),
-            }
+    let nl = opt.newline.as_str();
+    for (i, node) in nodes.iter().enumerate() {
+        let anchors = anchors.get(i).unwrap_or(&anchors_empty);
+        if i != 0 {
+            write!(w, "---{nl}")?;
+        }
+        let dumper = Dumper::new(node, anchors).with_options(opt);
+        w.write_str(&dumper.comment(node, ""))?;
+        // The leading newline of the root collection is skipped
+        let mut w = TrimStart { w, start: true };
+        dumper.dump_to(&mut w)?;
+        w.write_str(nl)?;
+    }
+    Ok(())
+}
+
+/// The writer skips the leading whitespaces.
+struct TrimStart<'a> {
+    w: &'a mut dyn Write,
+    start: bool,
+}
+
+impl Write for TrimStart<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let s = if self.start { s.trim_start() } else { s };
+        self.start &= s.is_empty();
+        self.w.write_str(s)
+    }
+}
+
+/// The adapter of the IO writer, the IO error is kept for the caller.
+#[cfg(feature = "std")]
+struct IoWriter<W> {
+    writer: W,
+    err: Option<std::io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.writer.write_all(s.as_bytes()).map_err(|e| {
+            self.err = Some(e);
+            fmt::Error
         })
-        .collect()
+    }
 }
@@ -0,0 +1,156 @@
+use crate::{repr::Repr, *};
+use alloc::string::{String, ToString};
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+/// The reason of the [`NodeError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeErrorKind {
+    /// The node is not the expected type, the types are named as the core
+    /// schema tags, such as `int` and `map`.
+    WrongType {
+        /// The expected type.
+        expected: &'static str,
+        /// The type of the node.
+        found: &'static str,
+    },
+    /// The key or the anchor is not found.
+    MissingKey {
+        /// The missing key, the keys which are not scalars are recorded as
+        /// empty strings.
+        key: String,
+    },
+    /// The index is out of the sequence, or the number is out of the range
+    /// of the type.
+    OutOfRange,
+    /// The path of [`Node::get_path_checked`] is invalid.
+    InvalidPath,
+    /// The alias refers to the anchored node which contains itself.
+    CyclicAlias {
+        /// The anchor name of the alias.
+        anchor: String,
+    },
+}
+
+/// The error of the node accessors, such as [`Node::get_checked`] and
+/// [`Node::as_int_checked`].
+///
+/// The error can be converted into its position, so the functions that
+/// return `Result<T, u64>` can still use the `?` operator.
+///
+/// The path refers to the missing item from the node of the method, it is
+/// prefixed with the parent path by [`Node::get_path_checked`] and
+/// [`Node::get_at`], or by [`NodeError::prefixed`] for the custom queries.
+///
+/// ```
+/// use yaml_peg::{node, NodeError, NodeErrorKind};
+///
+/// let n = node!({"a" => "b"});
+/// let e = n.get_checked("c").unwrap_err();
+/// assert_eq!(e.kind, NodeErrorKind::MissingKey { key: "c".to_string() });
+/// let e = n["a"].as_int_checked().unwrap_err();
+/// assert_eq!(e.kind, NodeErrorKind::WrongType { expected: "int", found: "str" });
+/// assert_eq!(e.to_string(), "expected int, found str at 0");
+/// assert_eq!(u64::from(e), 0);
+/// let n = node!({"a" => node!({"list" => node!([1, 2])})});
+/// let e = n.get_path_checked("a.list[3]").unwrap_err();
+/// assert_eq!(e.to_string(), "out of range at a.list[3] (pos 0)");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeError {
+    /// The position of the node.
+    pub pos: u64,
+    /// The reason of the error.
+    pub kind: NodeErrorKind,
+    /// The path of the missing item, empty if the node itself is invalid.
+    pub path: NodePath,
+}
+
+impl NodeError {
+    pub(crate) fn new(pos: u64, kind: NodeErrorKind) -> Self {
+        Self { pos, kind, path: NodePath::new() }
+    }
+
+    /// Prepend the path of the parent node, for the errors of the nested
+    /// queries.
+    ///
+    /// ```
+    /// use yaml_peg::{node, NodePath};
+    ///
+    /// let n = node!({"a" => node!({"b" => "x"})});
+    /// let path = NodePath::new().key("a").key("b");
+    /// let e = n.get_at(&path).unwrap().as_int_checked().map_err(|e| e.prefixed(&path)).unwrap_err();
+    /// assert_eq!(e.path, path);
+    /// ```
+    pub fn prefixed(mut self, parent: &NodePath) -> Self {
+        let mut path = parent.clone();
+        path.extend(self.path.segments().iter().cloned());
+        self.path = path;
+        self
+    }
+
+    pub(crate) fn wrong_type<R: Repr>(node: &Node<R>, expected: &'static str) -> Self {
+        let found = match node.yaml() {
+            Yaml::Null => "null",
+            Yaml::Bool(_) => "bool",
+            Yaml::Int(_) => "int",
+            Yaml::Float(_) => "float",
+            Yaml::Str(_) => "str",
+            Yaml::Seq(_) => "seq",
+            Yaml::Map(_) => "map",
+            Yaml::Alias(_) => "alias",
+        };
+        Self::new(node.pos(), NodeErrorKind::WrongType { expected, found })
+    }
+
+    pub(crate) fn missing_key(pos: u64, key: impl ToString) -> Self {
+        let key = key.to_string();
+        let path = NodePath::new().key(&key);
+        Self { pos, kind: NodeErrorKind::MissingKey { key }, path }
+    }
+
+    pub(crate) fn out_of_range(pos: u64) -> Self {
+        Self::new(pos, NodeErrorKind::OutOfRange)
+    }
+
+    pub(crate) fn cyclic_alias(pos: u64, anchor: &str) -> Self {
+        Self::new(
+            pos,
+            NodeErrorKind::CyclicAlias { anchor: anchor.to_string() },
+        )
+    }
+
+    pub(crate) fn invalid_path(pos: u64) -> Self {
+        Self::new(pos, NodeErrorKind::InvalidPath)
+    }
+}
+
+impl From<NodeError> for u64 {
+    fn from(e: NodeError) -> Self {
+        e.pos
+    }
+}
+
+impl Display for NodeErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::WrongType { expected, found } => write!(f, "expected {expected}, found {found}"),
+            Self::MissingKey { key } => write!(f, "missing key {key:?}"),
+            Self::OutOfRange => f.write_str("out of range"),
+            Self::InvalidPath => f.write_str("invalid path"),
+            Self::CyclicAlias { anchor } => write!(f, "cyclic alias {anchor:?}"),
+        }
+    }
+}
+
+impl Display for NodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if self.path.is_empty() {
+            write!(f, "{} at {}", self.kind, self.pos)
+        } else {
+            write!(f, "{} at {} (pos {})", self.kind, self.path, self.pos)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NodeError {}
@@ -20,18 +20,23 @@ use alloc::{format, string::String};
 ///
 /// This may be what you need if you went to indicate an error on the invalid
 /// data.
-pub fn indicated_msg(doc: &[u8], mut pos: u64) -> String {
+pub fn indicated_msg(doc: &[u8], pos: u64) -> String {
+    let (line, column, str_line) = line_of(doc, pos);
+    format!(
+        "{}:{}\n{}\n{}^",
+        line + 1,
+        column + 1,
+        String::from_utf8_lossy(str_line),
+        " ".repeat(column as usize)
+    )
+}
+
+/// Return the line number, the column number and the line of the position.
+fn line_of(doc: &[u8], mut pos: u64) -> (usize, u64, &[u8]) {
     for (line, str_line) in doc.split(|c| *c == b'\n').enumerate() {
         let full_line = str_line.len() as u64 + 1;
         if full_line > pos {
-            let column = pos;
-            return format!(
-                "{}:{}\n{}\n{}^",
-                line + 1,
-                column + 1,
-                String::from_utf8_lossy(str_line),
-                " ".repeat(column as usize)
-            );
+            return (line, pos, str_line);
         } else {
             pos -= full_line;
         }
@@ -39,6 +44,41 @@ pub fn indicated_msg(doc: &[u8], mut pos: u64) -> String {
     unreachable!()
 }
 
+/// Same as [`indicated_msg`], but indicate the parent position as well.
+///
+/// The parent position is usually the key of the nearest enclosing map,
+/// which helps to find out which structure the error belongs to.
+///
+/// ```
+/// use yaml_peg::indicated_msg_parent;
+///
+/// let doc = b"resources:\n  cpu: [1, 2\n";
+/// let msg = indicated_msg_parent(doc, 20, 13);
+/// let ans = "\
+/// 2:10
+///   cpu: [1, 2
+///          ^
+/// within 'cpu:' started at line 2
+/// 2:3
+///   cpu: [1, 2
+///   ^";
+/// assert_eq!(msg, ans);
+/// ```
+pub fn indicated_msg_parent(doc: &[u8], pos: u64, parent: u64) -> String {
+    let (line, column, str_line) = line_of(doc, parent);
+    let label = String::from_utf8_lossy(&str_line[column as usize..]);
+    let label = match label.find(':') {
+        Some(i) => &label[..=i],
+        None => label.trim_end(),
+    };
+    format!(
+        "{}\nwithin '{label}' started at line {}\n{}",
+        indicated_msg(doc, pos),
+        line + 1,
+        indicated_msg(doc, parent)
+    )
+}
+
 /// Same as [`indicated_msg`], but join the path before message.
 ///
 /// ```
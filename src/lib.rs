@@ -27,27 +27,88 @@
 //! If you went to rise your own error message, [`indicated_msg`] might be a
 //! good choice.
 //!
+//! The parsing functions accept UTF-8 string only, use [`parser::decode`] to
+//! remove the byte order mark or transcode UTF-16 documents (`utf16` feature).
+//!
 //! ## Anchor Parsing
 //!
 //! + [`parse`]: The parser will replace the anchors during parsing.
+//! + [`parse_with_anchors`]: Same as [`parse`], but return the anchors as well
+//!   for looking up the nodes by name.
+//! + `parse_parallel`: Same as [`parse`], but the documents are parsed in
+//!   parallel. (`rayon` feature)
 //! + [`parse_cyclic`]: Cyclic data means that a parent alias is inserted at the
-//! child node.   Keep the alias to avoid having undefined anchors when parsing.
+//!   child node. Keep the alias to avoid having undefined anchors when parsing.
 //!
 //! # No Standard Library
 //!
 //! The `std` feature is a default feature, use `--no-default-features` to build
-//! in the no-std mode.
+//! in the no-std mode. The `ritelinked` map backend is disabled then, and the
+//! `btreemap` backend is used unless the `indexmap` feature is enabled.
+//!
+//! The `compact-errors` feature leaves the messages and the found text of the
+//! parser errors ([`parser::PError`]) empty, so the formatting functions are
+//! not linked into the binary. The positions and the names are still provided,
+//! and the messages can be rendered later by [`parser::PError::render`].
+//!
+//! The `wasm` feature provides the `wasm-bindgen` bindings for the browsers,
+//! see the `wasm` module for the size-optimized configuration.
+//!
+//! # Small Data Optimization
+//!
+//! + `sso` feature: The short strings held by the nodes, such as the tags and
+//!   the interned scalar keys of the loader, use the inline string of
+//!   `smartstring` crate. The public types are still [`String`].
+//! + `smallvec` feature: The sequences ([`Seq`]) are stored inline if they
+//!   are short.
+//!
+//! The plain [`String`] and [`Vec`] are used by default.
+//!
+//! The `arena` feature provides [`repr::ArenaRepr`], which allocates the nodes
+//! in the chunks of a thread-local arena for the very large trees.
+//!
+//! # Map Backend
+//!
+//! The maps ([`Map`]) keep the insertion order, and the order is significant
+//! in the comparison. The `ritelinked` feature (default) uses the linked hash
+//! map of `ritelinked` crate, the `indexmap` feature uses the map of
+//! `indexmap` crate, and the `btreemap` feature uses a vector indexed by
+//! [`alloc::collections::BTreeMap`] without dependency, which is also the
+//! fallback if no backend is enabled. The API of [`Map`] is the same for all
+//! backends, see the [`map`] module for more information.
+//!
+//! # Native Numbers
+//!
+//! The numbers are stored as their lexemes by default, and parsed in every
+//! comparison and conversion. Enable `native-num` feature to parse them once
+//! when creating the nodes, the values are cached in the nodes for the
+//! comparison and the number accessors, e.g., [`Node::as_int_checked`] and
+//! [`Node::as_float_checked`]. The data types are not changed, [`Yaml::Int`]
+//! and [`Yaml::Float`] still hold the lexemes, so the out-of-range numbers are
+//! not lost.
+//!
+//! The integers out of the 64-bit range can be read by
+//! [`Node::as_i128_checked`] and [`Node::as_u128_checked`], and the
+//! arbitrary-precision integers are provided by `Node::as_big_int` with
+//! `num-bigint` feature.
 //!
 //! # Serialization and Deserialization
 //!
 //! Enable `serde` feature to use `serde` crate,
 //! which provides a set of protocol traits to convert between custom Rust data.
 //! Please be aware that the additional fields will be discarded when convert to
-//! a fix-sized structure. For example, the structure fields can be turned into
-//! map keys as well.
+//! a fix-sized structure, unless they are captured by a flattened [`Node`]
+//! field. For example, the structure fields can be turned into map keys as
+//! well.
 //!
 //! On the other hand, the primitive types are still able to transform to YAML
 //! data without serialization, according to built-in `From` and `Into` traits.
+//!
+//! # Property Testing
+//!
+//! Enable `proptest` feature to generate the arbitrary [`Node`] trees by the
+//! `Arbitrary` trait of `proptest` crate, e.g., `any::<NodeRc>()`. The trees
+//! can be dumped and parsed back as the same value.
 #![cfg_attr(doc_cfg, feature(doc_cfg))]
 #![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
@@ -55,12 +116,20 @@ extern crate alloc;
 extern crate core;
 
 pub use crate::{
+    builder::{IntoNode, MapBuilder, SeqBuilder},
     dumper::dump,
+    error::{NodeError, NodeErrorKind},
     indicator::*,
+    map::Map,
     node::*,
-    parser::{parse, parse_cyclic},
+    parser::{anchor_resolve_checked, parse, parse_cyclic, parse_with_anchors},
+    path::{NodePath, PathSegment},
     yaml::*,
 };
+#[allow(deprecated)]
+pub use crate::parser::anchor_resolve;
+#[cfg(feature = "rayon")]
+pub use crate::parser::parse_parallel;
 
 /// Create [`Node`] items literally.
 ///
@@ -86,23 +155,38 @@ pub use crate::{
 /// let m = vec![(NodeRc::from(1), NodeRc::from(2))];
 /// assert_eq!(node!({1 => 2}), m.into_iter().collect());
 /// ```
+///
+/// The tag can be given before the data with `!` symbol, see
+/// [`Node::set_tag`] for the shorthands.
+///
+/// ```
+/// use yaml_peg::node;
+///
+/// let n = node!(!"!!set" {"a" => (), "b" => node!(!"point" [1, 2])});
+/// assert_eq!(n.tag(), "tag:yaml.org,2002:set");
+/// assert_eq!(n.get_checked("b").unwrap().tag(), "point");
+/// assert_eq!(node!(!true), node!(false));
+/// ```
 #[macro_export]
 macro_rules! node {
     (@[$v:expr; $n:expr]) => {{
         extern crate alloc;
         let v = alloc::vec![$crate::node!(@$v); $n];
-        $crate::node!(@$crate::Yaml::Seq(v))
+        $crate::node!(@$crate::Yaml::Seq(v.into_iter().collect()))
     }};
     (@[$($v:expr),* $(,)?]) => {{
         extern crate alloc;
         let v = alloc::vec![$($crate::node!(@$v)),*];
-        $crate::node!(@$crate::Yaml::Seq(v))
+        $crate::node!(@$crate::Yaml::Seq(v.into_iter().collect()))
     }};
     (@{$($k:expr => $v:expr),* $(,)?}) => {{
         extern crate alloc;
         let m = alloc::vec![$(($crate::node!(@$k), $crate::node!(@$v))),*];
         $crate::node!(@$crate::Yaml::Map(m.into_iter().collect()))
     }};
+    (@!$tag:literal $($tt:tt)+) => {
+        $crate::node!(@$($tt)+).with_tag($tag)
+    };
     (@*$anchor:expr) => {
         $crate::node!(@$crate::Yaml::Alias($anchor.into()))
     };
@@ -120,14 +204,58 @@ macro_rules! node {
     };
 }
 
+/// Create [`parser::Anchors`] items literally.
+///
+/// The names are converted by [`ToString::to_string`], and the nodes are
+/// converted as the items of the [`node!`] macro. The representation is
+/// [`repr::RcRepr`] by default, use `arc` prefix for [`repr::ArcRepr`].
+///
+/// ```
+/// use yaml_peg::{anchors, node, parser::Anchors, repr::ArcRepr};
+///
+/// let anchors = anchors!["a" => 20, "b" => node!([1, 2])];
+/// assert_eq!(&node!([1, 2]), &anchors["b"]);
+/// let anchors: Anchors<ArcRepr> = anchors!(arc "a" => 20);
+/// assert_eq!(1, anchors.len());
+/// ```
+#[macro_export]
+macro_rules! anchors {
+    (@$($k:expr => $v:expr),* $(,)?) => {{
+        extern crate alloc;
+        let m = alloc::vec![$((alloc::string::ToString::to_string(&$k), $crate::node!(@$v))),*];
+        m.into_iter().collect::<$crate::parser::Anchors<_>>()
+    }};
+    (arc $($tt:tt)*) => {{
+        let anchors: $crate::parser::Anchors<$crate::repr::ArcRepr> = $crate::anchors!(@$($tt)*);
+        anchors
+    }};
+    (rc $($tt:tt)*) => {{
+        let anchors: $crate::parser::Anchors<$crate::repr::RcRepr> = $crate::anchors!(@$($tt)*);
+        anchors
+    }};
+    ($($tt:tt)*) => {
+        $crate::anchors!(rc $($tt)*)
+    };
+}
+
+#[cfg(feature = "proptest")]
+mod arbitrary;
+mod builder;
+pub mod coerce;
 pub mod dumper;
+mod error;
 mod indicator;
+pub mod map;
 mod node;
 pub mod parser;
+mod path;
 pub mod repr;
 #[cfg(feature = "serde")]
 #[cfg_attr(doc_cfg, doc(cfg(feature = "serde")))]
 pub mod serde;
 #[cfg(test)]
 mod tests;
+#[cfg(feature = "wasm")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "wasm")))]
+pub mod wasm;
 mod yaml;
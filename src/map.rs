@@ -0,0 +1,597 @@
+//! The map data structure of YAML and its iterators.
+//!
+//! The [`Map`] keeps the insertion order, and the order is significant in the
+//! comparison. The type and its methods are the same for all the backends,
+//! which are chosen by the features:
+//!
+//! + `btreemap`: The entries are stored in a vector, and indexed by a
+//!   [`alloc::collections::BTreeMap`] of their hashes. No dependency is
+//!   required, so it is the fallback backend if no other backend is enabled.
+//! + `indexmap`: The map of `indexmap` crate.
+//! + `ritelinked` (default): The linked hash map of `ritelinked` crate.
+//!
+//! If multiple backends are enabled, the first one in the list is used. The
+//! chosen backend is shown as [`Map::BACKEND`].
+use crate::{repr::Repr, yaml::hash_str, Node};
+use core::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    hash::{Hash, Hasher},
+    iter::FromIterator,
+    ops::Index,
+};
+
+macro_rules! impl_iter {
+    ($(impl<$($lt:lifetime,)? R> $ty:ident => $item:ty, |$e:ident| $map:expr)+) => {
+        $(impl<$($lt,)? R: Repr> Iterator for $ty<$($lt,)? R> {
+            type Item = $item;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.0.next().map(|$e| $map)
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.0.size_hint()
+            }
+        }
+
+        impl<$($lt,)? R: Repr> DoubleEndedIterator for $ty<$($lt,)? R> {
+            fn next_back(&mut self) -> Option<Self::Item> {
+                self.0.next_back().map(|$e| $map)
+            }
+        }
+
+        impl<$($lt,)? R: Repr> ExactSizeIterator for $ty<$($lt,)? R> {})+
+    };
+}
+
+/// The map data structure of YAML, the insertion order is kept.
+///
+/// Please see [module level document](self) for the backends.
+///
+/// ```
+/// use yaml_peg::{node, Map, NodeRc};
+///
+/// let mut m = Map::<yaml_peg::repr::RcRepr>::new();
+/// m.insert(node!("b"), node!(1));
+/// m.insert(node!("a"), node!(2));
+/// assert_eq!(m.insert(node!("b"), node!(3)), Some(node!(1)));
+/// assert_eq!(m.keys().collect::<Vec<_>>(), [&node!("b"), &node!("a")]);
+/// assert_eq!(m[&node!("b")], node!(3));
+/// assert_eq!(m.remove(&node!("b")), Some(node!(3)));
+/// assert_eq!(NodeRc::from(m), node!({"a" => 2}));
+/// ```
+pub struct Map<R: Repr>(imp::Inner<R>);
+
+impl<R: Repr> Map<R> {
+    /// The name of the chosen backend, `"btreemap"`, `"indexmap"` or
+    /// `"ritelinked"`.
+    pub const BACKEND: &'static str = imp::BACKEND;
+
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self(imp::Inner::with_capacity(0))
+    }
+
+    /// Create an empty map with the capacity.
+    pub fn with_capacity(n: usize) -> Self {
+        Self(imp::Inner::with_capacity(n))
+    }
+
+    /// The number of the entries.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Return true if the map has no entry.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all entries.
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    /// Get the value of the key.
+    pub fn get(&self, key: &Node<R>) -> Option<&Node<R>> {
+        self.0.get(key)
+    }
+
+    /// Get the mutable value of the key.
+    pub fn get_mut(&mut self, key: &Node<R>) -> Option<&mut Node<R>> {
+        self.0.get_mut(key)
+    }
+
+    /// Return true if the key is in the map.
+    pub fn contains_key(&self, key: &Node<R>) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Insert the entry at the back, returns the old value if the key is
+    /// already in the map, and the entry keeps its position then.
+    pub fn insert(&mut self, key: Node<R>, value: Node<R>) -> Option<Node<R>> {
+        match self.get_mut(&key) {
+            Some(v) => Some(core::mem::replace(v, value)),
+            None => {
+                self.0.push(key, value);
+                None
+            }
+        }
+    }
+
+    /// Remove the entry of the key, returns its value. The order of the other
+    /// entries is kept.
+    pub fn remove(&mut self, key: &Node<R>) -> Option<Node<R>> {
+        self.0.remove(key)
+    }
+
+    /// Keep the entries which are matched by the function.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&Node<R>, &mut Node<R>) -> bool,
+    {
+        self.0.retain(f)
+    }
+
+    /// Iterate the entries in the insertion order.
+    pub fn iter(&self) -> Iter<'_, R> {
+        Iter(self.0.iter())
+    }
+
+    /// Iterate the entries with the mutable values in the insertion order.
+    pub fn iter_mut(&mut self) -> IterMut<'_, R> {
+        IterMut(self.0.iter_mut())
+    }
+
+    /// Iterate the keys in the insertion order.
+    pub fn keys(&self) -> Keys<'_, R> {
+        Keys(self.iter())
+    }
+
+    /// Iterate the values in the insertion order.
+    pub fn values(&self) -> Values<'_, R> {
+        Values(self.iter())
+    }
+
+    /// Iterate the mutable values in the insertion order.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, R> {
+        ValuesMut(self.iter_mut())
+    }
+
+    /// Get the value of the string key without building the key node.
+    pub(crate) fn get_str(&self, key: &str) -> Option<&Node<R>> {
+        self.0.get_str(key)
+    }
+}
+
+impl<R: Repr> Default for Map<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Repr> Clone for Map<R> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<R: Repr> Debug for Map<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<R: Repr> PartialEq for Map<R> {
+    fn eq(&self, rhs: &Self) -> bool {
+        // The order is significant for all backends
+        self.len() == rhs.len() && self.iter().eq(rhs)
+    }
+}
+
+impl<R: Repr> Eq for Map<R> {}
+
+impl<R: Repr> Hash for Map<R> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.iter().for_each(|e| e.hash(state))
+    }
+}
+
+impl<R: Repr> Index<&Node<R>> for Map<R> {
+    type Output = Node<R>;
+
+    fn index(&self, key: &Node<R>) -> &Self::Output {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<R: Repr> FromIterator<(Node<R>, Node<R>)> for Map<R> {
+    fn from_iter<T: IntoIterator<Item = (Node<R>, Node<R>)>>(iter: T) -> Self {
+        let mut m = Self::new();
+        m.extend(iter);
+        m
+    }
+}
+
+impl<R: Repr> Extend<(Node<R>, Node<R>)> for Map<R> {
+    fn extend<T: IntoIterator<Item = (Node<R>, Node<R>)>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|(k, v)| {
+            self.insert(k, v);
+        })
+    }
+}
+
+impl<R: Repr> IntoIterator for Map<R> {
+    type Item = (Node<R>, Node<R>);
+    type IntoIter = IntoIter<R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self.0.into_iter())
+    }
+}
+
+impl<'a, R: Repr> IntoIterator for &'a Map<R> {
+    type Item = (&'a Node<R>, &'a Node<R>);
+    type IntoIter = Iter<'a, R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, R: Repr> IntoIterator for &'a mut Map<R> {
+    type Item = (&'a Node<R>, &'a mut Node<R>);
+    type IntoIter = IterMut<'a, R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// The iterator of the map entries, see [`Map::iter`].
+pub struct Iter<'a, R: Repr>(imp::Iter<'a, R>);
+/// The iterator of the map entries with the mutable values, see
+/// [`Map::iter_mut`].
+pub struct IterMut<'a, R: Repr>(imp::IterMut<'a, R>);
+/// The owned iterator of the map entries.
+pub struct IntoIter<R: Repr>(imp::IntoIter<R>);
+/// The iterator of the map keys, see [`Map::keys`].
+pub struct Keys<'a, R: Repr>(Iter<'a, R>);
+/// The iterator of the map values, see [`Map::values`].
+pub struct Values<'a, R: Repr>(Iter<'a, R>);
+/// The iterator of the mutable map values, see [`Map::values_mut`].
+pub struct ValuesMut<'a, R: Repr>(IterMut<'a, R>);
+
+impl_iter! {
+    impl<'a, R> Iter => (&'a Node<R>, &'a Node<R>), |e| e
+    impl<'a, R> IterMut => (&'a Node<R>, &'a mut Node<R>), |e| e
+    impl<R> IntoIter => (Node<R>, Node<R>), |e| e
+    impl<'a, R> Keys => &'a Node<R>, |e| e.0
+    impl<'a, R> Values => &'a Node<R>, |e| e.1
+    impl<'a, R> ValuesMut => &'a mut Node<R>, |e| e.1
+}
+
+impl<R: Repr> Clone for Iter<'_, R> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// The entry vector indexed by the hashes, without dependency.
+#[cfg(any(
+    feature = "btreemap",
+    not(any(feature = "indexmap", feature = "ritelinked"))
+))]
+mod imp {
+    use super::*;
+    use alloc::{collections::BTreeMap, vec::Vec};
+    use core::slice;
+
+    pub(super) const BACKEND: &str = "btreemap";
+    pub(super) type Iter<'a, R> = core::iter::Map<
+        slice::Iter<'a, (Node<R>, Node<R>)>,
+        fn(&'a (Node<R>, Node<R>)) -> (&'a Node<R>, &'a Node<R>),
+    >;
+    pub(super) type IterMut<'a, R> = core::iter::Map<
+        slice::IterMut<'a, (Node<R>, Node<R>)>,
+        fn(&'a mut (Node<R>, Node<R>)) -> (&'a Node<R>, &'a mut Node<R>),
+    >;
+    pub(super) type IntoIter<R> = alloc::vec::IntoIter<(Node<R>, Node<R>)>;
+
+    /// The FNV-1a hasher, which is deterministic and has no dependency.
+    struct Fnv(u64);
+
+    impl Hasher for Fnv {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for b in bytes {
+                self.0 = (self.0 ^ *b as u64).wrapping_mul(0x100_0000_01b3);
+            }
+        }
+    }
+
+    fn hash_of(f: impl FnOnce(&mut Fnv)) -> u64 {
+        let mut state = Fnv(0xcbf2_9ce4_8422_2325);
+        f(&mut state);
+        state.finish()
+    }
+
+    pub(super) struct Inner<R: Repr> {
+        entries: Vec<(Node<R>, Node<R>)>,
+        index: BTreeMap<u64, Vec<usize>>,
+    }
+
+    impl<R: Repr> Inner<R> {
+        pub(super) fn with_capacity(n: usize) -> Self {
+            Self {
+                entries: Vec::with_capacity(n),
+                index: BTreeMap::new(),
+            }
+        }
+
+        pub(super) fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        pub(super) fn clear(&mut self) {
+            self.entries.clear();
+            self.index.clear();
+        }
+
+        fn find(&self, hash: u64, f: impl Fn(&Node<R>) -> bool) -> Option<usize> {
+            let ids = self.index.get(&hash)?;
+            ids.iter().copied().find(|i| f(&self.entries[*i].0))
+        }
+
+        fn position(&self, key: &Node<R>) -> Option<usize> {
+            self.find(hash_of(|s| key.hash(s)), |k| k == key)
+        }
+
+        pub(super) fn get(&self, key: &Node<R>) -> Option<&Node<R>> {
+            self.position(key).map(|i| &self.entries[i].1)
+        }
+
+        pub(super) fn get_mut(&mut self, key: &Node<R>) -> Option<&mut Node<R>> {
+            self.position(key).map(|i| &mut self.entries[i].1)
+        }
+
+        pub(super) fn get_str(&self, key: &str) -> Option<&Node<R>> {
+            let hash = hash_of(|s| hash_str(key, s));
+            self.find(
+                hash,
+                |k| matches!(k.yaml(), crate::Yaml::Str(s) if s.as_str() == key),
+            )
+            .map(|i| &self.entries[i].1)
+        }
+
+        pub(super) fn push(&mut self, key: Node<R>, value: Node<R>) {
+            let hash = hash_of(|s| key.hash(s));
+            self.index.entry(hash).or_default().push(self.entries.len());
+            self.entries.push((key, value));
+        }
+
+        pub(super) fn remove(&mut self, key: &Node<R>) -> Option<Node<R>> {
+            let i = self.position(key)?;
+            let (_, v) = self.entries.remove(i);
+            self.reindex();
+            Some(v)
+        }
+
+        pub(super) fn retain<F>(&mut self, mut f: F)
+        where
+            F: FnMut(&Node<R>, &mut Node<R>) -> bool,
+        {
+            let len = self.entries.len();
+            self.entries.retain_mut(|(k, v)| f(k, v));
+            if self.entries.len() != len {
+                self.reindex();
+            }
+        }
+
+        fn reindex(&mut self) {
+            self.index.clear();
+            for (i, (k, _)) in self.entries.iter().enumerate() {
+                self.index
+                    .entry(hash_of(|s| k.hash(s)))
+                    .or_default()
+                    .push(i);
+            }
+        }
+
+        pub(super) fn iter(&self) -> Iter<'_, R> {
+            self.entries.iter().map(|(k, v)| (k, v))
+        }
+
+        pub(super) fn iter_mut(&mut self) -> IterMut<'_, R> {
+            self.entries.iter_mut().map(|(k, v)| (&*k, v))
+        }
+
+        pub(super) fn into_iter(self) -> IntoIter<R> {
+            self.entries.into_iter()
+        }
+    }
+
+    impl<R: Repr> Clone for Inner<R> {
+        fn clone(&self) -> Self {
+            Self {
+                entries: self.entries.clone(),
+                index: self.index.clone(),
+            }
+        }
+    }
+}
+
+/// The map of `indexmap` crate.
+#[cfg(all(feature = "indexmap", not(feature = "btreemap")))]
+mod imp {
+    use super::*;
+    use core::hash::BuildHasher;
+    use indexmap::map::RawEntryApiV1;
+
+    pub(super) const BACKEND: &str = "indexmap";
+    pub(super) type Iter<'a, R> = indexmap::map::Iter<'a, Node<R>, Node<R>>;
+    pub(super) type IterMut<'a, R> = indexmap::map::IterMut<'a, Node<R>, Node<R>>;
+    pub(super) type IntoIter<R> = indexmap::map::IntoIter<Node<R>, Node<R>>;
+
+    pub(super) struct Inner<R: Repr>(
+        indexmap::IndexMap<Node<R>, Node<R>, foldhash::fast::RandomState>,
+    );
+
+    impl<R: Repr> Inner<R> {
+        pub(super) fn with_capacity(n: usize) -> Self {
+            Self(indexmap::IndexMap::with_capacity_and_hasher(
+                n,
+                Default::default(),
+            ))
+        }
+
+        pub(super) fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        pub(super) fn clear(&mut self) {
+            self.0.clear()
+        }
+
+        pub(super) fn get(&self, key: &Node<R>) -> Option<&Node<R>> {
+            self.0.get(key)
+        }
+
+        pub(super) fn get_mut(&mut self, key: &Node<R>) -> Option<&mut Node<R>> {
+            self.0.get_mut(key)
+        }
+
+        pub(super) fn get_str(&self, key: &str) -> Option<&Node<R>> {
+            let mut state = self.0.hasher().build_hasher();
+            hash_str(key, &mut state);
+            self.0
+                .raw_entry_v1()
+                .from_hash(
+                    state.finish(),
+                    |k| matches!(k.yaml(), crate::Yaml::Str(s) if s.as_str() == key),
+                )
+                .map(|(_, v)| v)
+        }
+
+        pub(super) fn push(&mut self, key: Node<R>, value: Node<R>) {
+            self.0.insert(key, value);
+        }
+
+        pub(super) fn remove(&mut self, key: &Node<R>) -> Option<Node<R>> {
+            self.0.shift_remove(key)
+        }
+
+        pub(super) fn retain<F>(&mut self, f: F)
+        where
+            F: FnMut(&Node<R>, &mut Node<R>) -> bool,
+        {
+            self.0.retain(f)
+        }
+
+        pub(super) fn iter(&self) -> Iter<'_, R> {
+            self.0.iter()
+        }
+
+        pub(super) fn iter_mut(&mut self) -> IterMut<'_, R> {
+            self.0.iter_mut()
+        }
+
+        pub(super) fn into_iter(self) -> IntoIter<R> {
+            self.0.into_iter()
+        }
+    }
+
+    impl<R: Repr> Clone for Inner<R> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+}
+
+/// The linked hash map of `ritelinked` crate.
+#[cfg(all(
+    feature = "ritelinked",
+    not(any(feature = "indexmap", feature = "btreemap"))
+))]
+mod imp {
+    use super::*;
+    use core::hash::BuildHasher;
+    use ritelinked::linked_hash_map;
+
+    pub(super) const BACKEND: &str = "ritelinked";
+    pub(super) type Iter<'a, R> = linked_hash_map::Iter<'a, Node<R>, Node<R>>;
+    pub(super) type IterMut<'a, R> = linked_hash_map::IterMut<'a, Node<R>, Node<R>>;
+    pub(super) type IntoIter<R> = linked_hash_map::IntoIter<Node<R>, Node<R>>;
+
+    pub(super) struct Inner<R: Repr>(ritelinked::LinkedHashMap<Node<R>, Node<R>>);
+
+    impl<R: Repr> Inner<R> {
+        pub(super) fn with_capacity(n: usize) -> Self {
+            Self(ritelinked::LinkedHashMap::with_capacity(n))
+        }
+
+        pub(super) fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        pub(super) fn clear(&mut self) {
+            self.0.clear()
+        }
+
+        pub(super) fn get(&self, key: &Node<R>) -> Option<&Node<R>> {
+            self.0.get(key)
+        }
+
+        pub(super) fn get_mut(&mut self, key: &Node<R>) -> Option<&mut Node<R>> {
+            self.0.get_mut(key)
+        }
+
+        pub(super) fn get_str(&self, key: &str) -> Option<&Node<R>> {
+            let mut state = self.0.hasher().build_hasher();
+            hash_str(key, &mut state);
+            self.0
+                .raw_entry()
+                .from_hash(
+                    state.finish(),
+                    |k| matches!(k.yaml(), crate::Yaml::Str(s) if s.as_str() == key),
+                )
+                .map(|(_, v)| v)
+        }
+
+        pub(super) fn push(&mut self, key: Node<R>, value: Node<R>) {
+            self.0.insert(key, value);
+        }
+
+        pub(super) fn remove(&mut self, key: &Node<R>) -> Option<Node<R>> {
+            self.0.remove(key)
+        }
+
+        pub(super) fn retain<F>(&mut self, f: F)
+        where
+            F: FnMut(&Node<R>, &mut Node<R>) -> bool,
+        {
+            self.0.retain(f)
+        }
+
+        pub(super) fn iter(&self) -> Iter<'_, R> {
+            self.0.iter()
+        }
+
+        pub(super) fn iter_mut(&mut self) -> IterMut<'_, R> {
+            self.0.iter_mut()
+        }
+
+        pub(super) fn into_iter(self) -> IntoIter<R> {
+            self.0.into_iter()
+        }
+    }
+
+    impl<R: Repr> Clone for Inner<R> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+}
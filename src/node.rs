@@ -1,28 +1,45 @@
 use crate::{parser::Anchors, repr::*, *};
-use alloc::string::{String, ToString};
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    collections::BTreeSet,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
 use core::{
     fmt::{Debug, Formatter},
     hash::{Hash, Hasher},
     iter::FromIterator,
     marker::PhantomData,
-    ops::Index,
+    ops::{Bound, Index, RangeBounds},
 };
 
 macro_rules! as_method {
-    {$($(#[$meta:meta])* fn $id:ident = $ty:ident$(($op:ident))?
+    {$($(#[$meta:meta])* fn $id:ident($name:literal) = $ty:ident$(($op:ident))?
         $(| ($default:expr)?)?
         $(| $ty2:ident)* -> $r:ty)+} => {$(
         $(#[$meta])*
-        pub fn $id(&self) -> Result<$r, u64> {
+        pub fn $id(&self) -> Result<$r, NodeError> {
             match self.yaml() {
                 Yaml::$ty(v) $(| Yaml::$ty2(v))* => Ok(v$(.$op())?),
                 $(Yaml::Null => Ok($default),)?
-                _ => Err(self.pos),
+                _ => Err(NodeError::wrong_type(self, $name)),
             }
         }
     )+};
 }
 
+macro_rules! pos_method {
+    {$(fn $id:ident$(<$lt:lifetime>)?($($arg:ident: $ty:ty),*) = $f:ident -> $r:ty)+} => {$(
+        #[doc = concat!("Same as [`Node::", stringify!($f), "`], but return the position only.")]
+        #[deprecated(note = "use the method which returns `NodeError` instead")]
+        pub fn $id$(<$lt>)?(&$($lt)? self, $($arg: $ty),*) -> Result<$r, u64> {
+            self.$f($($arg),*).map_err(u64::from)
+        }
+    )+};
+}
+
 macro_rules! impl_iter {
     ($(impl $item:ty)+) => {
         $(impl<R: Repr> FromIterator<$item> for Node<R> {
@@ -33,6 +50,22 @@ macro_rules! impl_iter {
     };
 }
 
+/// The style of the scalar written in the document, see [`Node::style`].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+pub enum ScalarStyle {
+    /// Plain scalar, also used by the collections and the created nodes.
+    #[default]
+    Plain,
+    /// Single-quoted scalar, `'a'`.
+    SingleQuoted,
+    /// Double-quoted scalar, `"a"`.
+    DoubleQuoted,
+    /// Literal block scalar, `|`.
+    Literal,
+    /// Folded block scalar, `>`.
+    Folded,
+}
+
 /// A node with [`alloc::rc::Rc`] holder.
 pub type NodeRc = Node<RcRepr>;
 /// A node with [`alloc::sync::Arc`] holder.
@@ -74,15 +107,16 @@ pub type NodeArc = Node<ArcRepr>;
 /// let n = &n["a"][Ind(0)]["b"];
 /// ```
 ///
-/// Same as containers, to prevent panic, the [`Node::get`] method is the best
-/// choice. The [`Node::get_default`] can provide missing key value when
-/// indexing.
+/// Same as containers, to prevent panic, the [`Node::get_checked`] method is
+/// the best choice. The [`Node::get_default`] can provide missing key value
+/// when indexing.
 ///
-/// There are `as_*` methods provide `Result<T, u64>` returns with node
-/// position, default options can be created by [`Result::unwrap_or`],
-/// additional error message can be attach by [`Result::map_err`],
-/// and the optional [`Option`] can be return by [`Result::ok`],
-/// which shown as following example:
+/// There are `as_*_checked` methods provide `Result<T, NodeError>` returns
+/// with node position and the reason, default options can be created by
+/// [`Result::unwrap_or`], additional error message can be attach by
+/// [`Result::map_err`], and the optional [`Option`] can be return by
+/// [`Result::ok`], which shown as following example. The original `as_*` and
+/// `get*` methods which return the position only are deprecated.
 ///
 /// ```
 /// use yaml_peg::node;
@@ -91,14 +125,14 @@ pub type NodeArc = Node<ArcRepr>;
 ///     let n = node!({
 ///         "title" => 12.
 ///     });
-///     let n = n.get("title").map_err(|p| ("missing \"title\"", p))?;
+///     let n = n.get_checked("title").map_err(|e| ("missing \"title\"", e.pos))?;
 ///     assert_eq!(
 ///         Err(("title", 0)),
-///         n.as_str().map_err(|p| ("title", p))
+///         n.as_str_checked().map_err(|e| ("title", e.pos))
 ///     );
 ///     assert_eq!(
 ///         Option::<&str>::None,
-///         n.as_str().ok()
+///         n.as_str_checked().ok()
 ///     );
 ///     Ok(())
 /// }
@@ -115,11 +149,23 @@ pub type NodeArc = Node<ArcRepr>;
 /// your custom type.
 pub struct Node<R: Repr> {
     pos: u64,
-    tag: String,
+    tag: SmallStr,
+    style: ScalarStyle,
+    meta: Option<Box<Meta>>,
     yaml: R::Rc,
+    #[cfg(feature = "native-num")]
+    num: Num,
     _marker: PhantomData<R>,
 }
 
+/// The rarely used metadata, boxed to keep the node small.
+#[derive(Clone, Default)]
+struct Meta {
+    comment: String,
+    anchor: String,
+    alias: String,
+}
+
 impl<R: Repr> Node<R> {
     /// Create node from YAML data.
     pub fn new(yaml: impl Into<Yaml<R>>, pos: u64, tag: impl ToString) -> Self {
@@ -127,11 +173,17 @@ impl<R: Repr> Node<R> {
     }
 
     /// Create from a representation.
+    // The tag is converted into the inline string with `sso` feature
+    #[allow(clippy::useless_conversion)]
     pub fn new_repr(yaml: R::Rc, pos: u64, tag: impl ToString) -> Self {
         Self {
-            yaml,
             pos,
-            tag: tag.to_string(),
+            tag: tag.to_string().into(),
+            style: ScalarStyle::default(),
+            meta: None,
+            #[cfg(feature = "native-num")]
+            num: Num::new(&yaml),
+            yaml,
             _marker: PhantomData,
         }
     }
@@ -143,6 +195,10 @@ impl<R: Repr> Node<R> {
 
     /// Set from existing YAML representation.
     pub fn set_repr(&mut self, yaml: R::Rc) {
+        #[cfg(feature = "native-num")]
+        {
+            self.num = Num::new(&yaml);
+        }
         self.yaml = yaml;
     }
 
@@ -151,6 +207,161 @@ impl<R: Repr> Node<R> {
         self.pos
     }
 
+    /// Set the document position, e.g., the synthetic positions of the
+    /// generated nodes.
+    pub fn set_pos(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+
+    /// The style of the scalar written in the document.
+    ///
+    /// This information is ignored by the comparison, but the dumper can keep
+    /// it, see [`DumpOptions::keep_style`](crate::dumper::DumpOptions::keep_style).
+    ///
+    /// ```
+    /// use yaml_peg::{parse, ScalarStyle};
+    ///
+    /// let root = parse::<yaml_peg::repr::RcRepr>("[true, 'true', \"true\"]").unwrap();
+    /// let styles = root[0].as_seq_checked().unwrap().iter().map(|n| n.style()).collect::<Vec<_>>();
+    /// let ans = [ScalarStyle::Plain, ScalarStyle::SingleQuoted, ScalarStyle::DoubleQuoted];
+    /// assert_eq!(styles, ans);
+    /// ```
+    pub fn style(&self) -> ScalarStyle {
+        self.style
+    }
+
+    /// Set the style of the scalar.
+    pub fn set_style(&mut self, style: ScalarStyle) {
+        self.style = style;
+    }
+
+    /// The comment attached to the node, empty if not attached.
+    ///
+    /// This information is ignored by the comparison, the dumper emits it
+    /// before the node. See [`Node::set_comment`].
+    pub fn comment(&self) -> &str {
+        self.meta.as_ref().map_or("", |m| &m.comment)
+    }
+
+    /// Attach a comment to the node, the dumper emits it before the map item
+    /// or the sequence item of the node, or at the top of the document if the
+    /// node is the root. Each line is emitted as a comment line.
+    ///
+    /// The comments are emitted in block style only, so the collections with
+    /// the commented items are not dumped in flow style.
+    ///
+    /// ```
+    /// use yaml_peg::{dump, node, dumper::NL};
+    ///
+    /// let mut port = node!("port");
+    /// port.set_comment("The port of the server,\nuse 0 to pick one.");
+    /// let mut hosts = node!(["a.com", "b.com"]);
+    /// hosts.set_comment("The allowed hosts");
+    /// let mut n = node!({port => 80, "hosts" => hosts});
+    /// n.set_comment("Server config");
+    /// let ans = "\
+    /// ## Server config
+    /// ## The port of the server,
+    /// ## use 0 to pick one.
+    /// port: 80
+    /// ## The allowed hosts
+    /// hosts:
+    ///   - a.com
+    ///   - b.com
+    /// ";
+    /// assert_eq!(dump(&[n], &[]), ans.replace('\n', NL));
+    /// ```
+    pub fn set_comment(&mut self, comment: impl ToString) {
+        self.set_meta(|m| m.comment = comment.to_string());
+    }
+
+    /// The anchor name of the node, empty if the node is not anchored.
+    ///
+    /// The anchors are recorded by the parser (both normal and cyclic modes)
+    /// and the builders, the dumper emits them as `&name`. The copies of the
+    /// anchored node resolved from the aliases are not anchored, see
+    /// [`Node::anchor_name`].
+    ///
+    /// This information is ignored by the comparison.
+    ///
+    /// ```
+    /// use yaml_peg::{dump, parse, repr::RcRepr, Ind, dumper::NL};
+    ///
+    /// let root = parse::<RcRepr>("- &a [1, 2]\n- *a\n").unwrap().remove(0);
+    /// assert_eq!(root[Ind(0)].anchor(), "a");
+    /// assert_eq!(root[Ind(1)].anchor(), "");
+    /// let mut n = root[Ind(1)].clone();
+    /// n.set_anchor("b");
+    /// let ans = "\
+    /// &b
+    /// - 1
+    /// - 2
+    /// ";
+    /// assert_eq!(dump(&[n], &[]), ans.replace('\n', NL));
+    /// ```
+    pub fn anchor(&self) -> &str {
+        self.meta.as_ref().map_or("", |m| &m.anchor)
+    }
+
+    /// Set the anchor name of the node, an empty name to unset. See
+    /// [`Node::anchor`].
+    pub fn set_anchor(&mut self, anchor: impl ToString) {
+        self.set_meta(|m| m.anchor = anchor.to_string());
+    }
+
+    /// The name of the alias which this node is resolved from, `None` if the
+    /// node is not resolved from an alias.
+    ///
+    /// When the alias `*a` is replaced by [`parse`] or
+    /// [`anchor_resolve_checked`], the copy shares the data, the tag and the
+    /// style of the node anchored by `&a`. The tag of the alias overrides the
+    /// tag of the anchored node, see
+    /// [`Loader::alias_props`](parser::Loader::alias_props).
+    ///
+    /// This information is ignored by the comparison.
+    ///
+    /// ```
+    /// use yaml_peg::{parse, repr::RcRepr, Ind};
+    ///
+    /// let root = parse::<RcRepr>("- &a !point [1, 2]\n- *a\n").unwrap().remove(0);
+    /// assert_eq!(root[Ind(1)].anchor_name(), Some("a"));
+    /// assert_eq!(root[Ind(1)].tag(), "point");
+    /// assert!(root[Ind(1)].ptr_eq(&root[Ind(0)]));
+    /// assert_eq!(root[Ind(0)].anchor_name(), None);
+    /// ```
+    pub fn anchor_name(&self) -> Option<&str> {
+        self.meta
+            .as_ref()
+            .map(|m| m.alias.as_str())
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Set the name of the alias which this node is resolved from, an empty
+    /// name to unset. See [`Node::anchor_name`].
+    pub fn set_anchor_name(&mut self, name: impl ToString) {
+        self.set_meta(|m| m.alias = name.to_string());
+    }
+
+    fn set_meta(&mut self, f: impl FnOnce(&mut Meta)) {
+        let mut meta = self.meta.take().unwrap_or_default();
+        f(&mut meta);
+        if !meta.comment.is_empty() || !meta.anchor.is_empty() || !meta.alias.is_empty() {
+            self.meta = Some(meta);
+        }
+    }
+
+    /// Copy the anchored node for the alias, the tag of the alias overrides
+    /// the tag of the anchored node if specified.
+    pub(crate) fn resolved_from(&self, alias: &Self, name: &str) -> Self {
+        let mut n = self.clone();
+        if !alias.tag.is_empty() {
+            n.tag = alias.tag.clone();
+        }
+        n.set_anchor(alias.anchor());
+        n.set_anchor_name(name);
+        n
+    }
+
     /// Tag. If the tag is not specified, returns a default tag from core
     /// schema.
     ///
@@ -171,6 +382,76 @@ impl<R: Repr> Node<R> {
         }
     }
 
+    /// The tag specified by the user, empty if not specified.
+    pub(crate) fn raw_tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Set the tag of the node, an empty tag to use the default tag.
+    ///
+    /// The shorthands are resolved as the parser: `!!str` is the core schema
+    /// tag `tag:yaml.org,2002:str`, `!<uri>` is the verbatim tag `uri`, and
+    /// `!foo` is the local tag `foo`. Other tags are kept as is.
+    ///
+    /// ```
+    /// use yaml_peg::{node, parse, repr::RcRepr};
+    ///
+    /// let root = parse::<RcRepr>("[!!str 1, !foo 2, !<tag:x.com,2000:y> 3]").unwrap().remove(0);
+    /// let mut n = node!(1);
+    /// for (item, tag) in root.iter().zip(["!!str", "!foo", "!<tag:x.com,2000:y>"]) {
+    ///     n.set_tag(tag);
+    ///     assert_eq!(n.tag(), item.tag());
+    /// }
+    /// n.set_tag("");
+    /// assert_eq!(n.tag(), "tag:yaml.org,2002:int");
+    /// ```
+    pub fn set_tag(&mut self, tag: impl ToString) {
+        self.tag = resolve_tag(&tag.to_string()).as_ref().into();
+    }
+
+    /// Same as [`Node::set_tag`], but return the node, for building the
+    /// nodes in an expression.
+    pub fn with_tag(mut self, tag: impl ToString) -> Self {
+        self.set_tag(tag);
+        self
+    }
+
+    /// Return true if the tag of the node is the tag, the shorthands are
+    /// supported like [`Node::set_tag`], and the default tags are matched as
+    /// well.
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// assert!(node!("a").is_tag("!!str"));
+    /// assert!(node!(1).with_tag("!foo").is_tag("foo"));
+    /// assert!(!node!(1).with_tag("!foo").is_tag("!!int"));
+    /// ```
+    pub fn is_tag(&self, tag: &str) -> bool {
+        self.tag() == resolve_tag(tag)
+    }
+
+    /// Strip the prefix from the tag of the node, `None` if the tag does not
+    /// start with the prefix. The `!!` prefix is the prefix of the core
+    /// schema tags.
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// assert_eq!(node!([1]).tag_suffix("!!"), Some("seq"));
+    /// let n = node!(1).with_tag("tag:example.com,2000:app/foo");
+    /// assert_eq!(n.tag_suffix("tag:example.com,2000:app/"), Some("foo"));
+    /// assert_eq!(n.tag_suffix("!!"), None);
+    /// ```
+    pub fn tag_suffix(&self, prefix: &str) -> Option<&str> {
+        let prefix = if prefix == "!!" {
+            parser::DEFAULT_PREFIX
+        } else {
+            prefix
+        };
+        self.tag().strip_prefix(prefix)
+    }
+
     /// YAML data.
     pub fn yaml(&self) -> &Yaml<R> {
         &self.yaml
@@ -207,31 +488,137 @@ impl<R: Repr> Node<R> {
         *self.yaml() == Yaml::Null
     }
 
+    /// The number lexeme with the value cached by `native-num` feature.
+    #[cfg(feature = "native-num")]
+    fn lexeme<'a>(&self, s: &'a str) -> Cached<'a> {
+        Cached(self.num, s)
+    }
+
+    /// The number lexeme, parsed in every conversion.
+    #[cfg(not(feature = "native-num"))]
+    fn lexeme<'a>(&self, s: &'a str) -> &'a str {
+        s
+    }
+
     /// Convert to integer.
     ///
     /// ```
     /// use yaml_peg::node;
     ///
-    /// assert_eq!(60, node!(60).as_int().unwrap());
+    /// assert_eq!(60, node!(60).as_int_checked().unwrap());
+    /// ```
+    pub fn as_int_checked(&self) -> Result<i64, NodeError> {
+        match self.yaml() {
+            Yaml::Int(s) => to_i64(&self.lexeme(s)).map_err(|_| NodeError::out_of_range(self.pos)),
+            _ => Err(NodeError::wrong_type(self, "int")),
+        }
+    }
+
+    /// Convert to unsigned integer.
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// assert_eq!(u64::MAX, node!(u64::MAX).as_u64_checked().unwrap());
+    /// assert!(node!(-1).as_u64_checked().is_err());
+    /// ```
+    pub fn as_u64_checked(&self) -> Result<u64, NodeError> {
+        match self.yaml() {
+            Yaml::Int(s) => to_u64(&self.lexeme(s)).map_err(|_| NodeError::out_of_range(self.pos)),
+            _ => Err(NodeError::wrong_type(self, "int")),
+        }
+    }
+
+    /// Convert to 128-bit integer, which covers both signed and unsigned
+    /// 64-bit integers.
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// assert_eq!(-1, node!(-1).as_i128_checked().unwrap());
+    /// assert_eq!(u64::MAX as i128, node!(u64::MAX).as_i128_checked().unwrap());
     /// ```
-    pub fn as_int(&self) -> Result<i64, u64> {
+    pub fn as_i128_checked(&self) -> Result<i128, NodeError> {
         match self.yaml() {
-            Yaml::Int(s) => to_i64(s).map_err(|_| self.pos),
-            _ => Err(self.pos),
+            Yaml::Int(s) => to_i128(&self.lexeme(s)).map_err(|_| NodeError::out_of_range(self.pos)),
+            _ => Err(NodeError::wrong_type(self, "int")),
         }
     }
 
+    /// Convert to 128-bit unsigned integer.
+    ///
+    /// ```
+    /// use yaml_peg::{parse, repr::RcRepr};
+    ///
+    /// let n = parse::<RcRepr>("340282366920938463463374607431768211455").unwrap().remove(0);
+    /// assert_eq!(u128::MAX, n.as_u128_checked().unwrap());
+    /// assert!(n.as_i128_checked().is_err());
+    /// ```
+    pub fn as_u128_checked(&self) -> Result<u128, NodeError> {
+        match self.yaml() {
+            Yaml::Int(s) => to_u128(&self.lexeme(s)).map_err(|_| NodeError::out_of_range(self.pos)),
+            _ => Err(NodeError::wrong_type(self, "int")),
+        }
+    }
+
+    /// Convert to the arbitrary-precision integer of `num-bigint` crate, so
+    /// the integers of any size are not lost. (`num-bigint` feature)
+    ///
+    /// ```
+    /// use yaml_peg::{parse, repr::RcRepr};
+    ///
+    /// let n = parse::<RcRepr>("-123456789012345678901234567890123456789012").unwrap().remove(0);
+    /// assert_eq!("-123456789012345678901234567890123456789012", n.as_big_int().unwrap().to_string());
+    /// ```
+    #[cfg(feature = "num-bigint")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "num-bigint")))]
+    pub fn as_big_int(&self) -> Result<num_bigint::BigInt, NodeError> {
+        let s = match self.yaml() {
+            Yaml::Int(s) => s.as_str(),
+            _ => return Err(NodeError::wrong_type(self, "int")),
+        };
+        let (neg, s) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (radix, s) = if let Some(s) = s.strip_prefix("0x") {
+            (16, s)
+        } else if let Some(s) = s.strip_prefix("0o") {
+            (8, s)
+        } else {
+            (10, s)
+        };
+        let n = num_bigint::BigInt::parse_bytes(s.as_bytes(), radix)
+            .ok_or_else(|| NodeError::out_of_range(self.pos))?;
+        Ok(if neg { -n } else { n })
+    }
+
+    /// Convert to size, such as the index or the length.
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// assert_eq!(20, node!(20).as_usize_checked().unwrap());
+    /// ```
+    pub fn as_usize_checked(&self) -> Result<usize, NodeError> {
+        self.as_u64_checked()
+            .and_then(|n| usize::try_from(n).map_err(|_| NodeError::out_of_range(self.pos)))
+    }
+
     /// Convert to float.
     ///
     /// ```
     /// use yaml_peg::node;
     ///
-    /// assert_eq!(20.06, node!(20.06).as_float().unwrap());
+    /// assert_eq!(20.06, node!(20.06).as_float_checked().unwrap());
     /// ```
-    pub fn as_float(&self) -> Result<f64, u64> {
+    pub fn as_float_checked(&self) -> Result<f64, NodeError> {
         match self.yaml() {
-            Yaml::Float(s) => to_f64(s).map_err(|_| self.pos),
-            _ => Err(self.pos),
+            Yaml::Float(s) => {
+                to_f64(&self.lexeme(s)).map_err(|_| NodeError::out_of_range(self.pos))
+            }
+            _ => Err(NodeError::wrong_type(self, "float")),
         }
     }
 
@@ -240,14 +627,18 @@ impl<R: Repr> Node<R> {
     /// ```
     /// use yaml_peg::node;
     ///
-    /// assert_eq!(60., node!(60).as_number().unwrap());
-    /// assert_eq!(20.06, node!(20.06).as_number().unwrap());
+    /// assert_eq!(60., node!(60).as_number_checked().unwrap());
+    /// assert_eq!(20.06, node!(20.06).as_number_checked().unwrap());
     /// ```
-    pub fn as_number(&self) -> Result<f64, u64> {
+    pub fn as_number_checked(&self) -> Result<f64, NodeError> {
         match self.yaml() {
-            Yaml::Int(s) => to_i64(s).map(|n| n as f64).map_err(|_| self.pos),
-            Yaml::Float(s) => to_f64(s).map_err(|_| self.pos),
-            _ => Err(self.pos),
+            Yaml::Int(s) => to_i64(&self.lexeme(s))
+                .map(|n| n as f64)
+                .map_err(|_| NodeError::out_of_range(self.pos)),
+            Yaml::Float(s) => {
+                to_f64(&self.lexeme(s)).map_err(|_| NodeError::out_of_range(self.pos))
+            }
+            _ => Err(NodeError::wrong_type(self, "number")),
         }
     }
 
@@ -257,9 +648,9 @@ impl<R: Repr> Node<R> {
         /// ```
         /// use yaml_peg::node;
         ///
-        /// assert!(node!(true).as_bool().unwrap());
+        /// assert!(node!(true).as_bool_checked().unwrap());
         /// ```
-        fn as_bool = Bool(clone) -> bool
+        fn as_bool_checked("bool") = Bool(clone) -> bool
 
         /// Convert to string pointer.
         ///
@@ -269,37 +660,137 @@ impl<R: Repr> Node<R> {
         /// ```
         /// use yaml_peg::node;
         ///
-        /// assert_eq!("abc", node!("abc").as_str().unwrap());
-        /// assert!(node!(()).as_str().unwrap().is_empty());
+        /// assert_eq!("abc", node!("abc").as_str_checked().unwrap());
+        /// assert!(node!(()).as_str_checked().unwrap().is_empty());
         /// ```
-        fn as_str = Str | ("")? -> &str
+        fn as_str_checked("str") = Str | ("")? -> &str
 
         /// Convert to sequence.
         ///
+        /// The sequence is cloned, use [`Node::iter`] to iterate the items
+        /// directly.
+        ///
         /// ```
         /// use yaml_peg::node;
         ///
         /// let n = node!(["55"]);
-        /// assert_eq!(node!("55"), n.as_seq().unwrap()[0]);
-        /// for n in n.as_seq().unwrap() {
+        /// assert_eq!(node!("55"), n.as_seq_checked().unwrap()[0]);
+        /// for n in n.as_seq_checked().unwrap() {
         ///     assert_eq!(node!("55"), n);
         /// }
         /// ```
-        fn as_seq = Seq(clone) -> Seq<R>
+        fn as_seq_checked("seq") = Seq(clone) -> Seq<R>
 
         /// Convert to map.
         ///
+        /// The map is cloned, use [`Node::entries`] to iterate the items
+        /// directly.
+        ///
         /// ```
         /// use yaml_peg::node;
         ///
         /// let n = node!({1 => 2});
-        /// assert_eq!(node!(2), n.as_map().unwrap()[&node!(1)]);
-        /// for (k, v) in n.as_map().unwrap() {
+        /// assert_eq!(node!(2), n.as_map_checked().unwrap()[&node!(1)]);
+        /// for (k, v) in n.as_map_checked().unwrap() {
         ///     assert_eq!(node!(1), k);
         ///     assert_eq!(node!(2), v);
         /// }
         /// ```
-        fn as_map = Map(clone) -> Map<R>
+        fn as_map_checked("map") = Map(clone) -> Map<R>
+    }
+
+    /// Resolve the string as a plain scalar with the core schema, for the
+    /// lenient conversions. Other types and the strings of other values are
+    /// returned as is.
+    ///
+    /// The quoted numbers and booleans (`"42"`) are common in the
+    /// configuration files, this method lets the typed accessors accept them.
+    /// The position and the tag are kept, but the tag is not checked.
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// assert_eq!(42, node!("42").lenient().as_u64_checked().unwrap());
+    /// assert_eq!(42, node!(42).lenient().as_u64_checked().unwrap());
+    /// assert!(node!("42").as_u64_checked().is_err());
+    /// assert!(node!("true").lenient().as_bool_checked().unwrap());
+    /// assert_eq!(0.5, node!("0.5").lenient().as_number_checked().unwrap());
+    /// assert!(node!("abc").lenient().as_int_checked().is_err());
+    /// ```
+    pub fn lenient(&self) -> Self {
+        let Yaml::Str(s) = self.yaml() else {
+            return self.clone();
+        };
+        let mut n = self.clone();
+        n.set_yaml(parser::Loader::<R>::resolve_plain(
+            s.as_str().into(),
+            parser::Schema::Core,
+        ));
+        n
+    }
+
+    /// Take the YAML data out, the data is cloned only if the data holder is
+    /// shared with other nodes.
+    pub fn into_yaml(self) -> Yaml<R> {
+        R::try_unwrap(self.yaml).unwrap_or_else(|rc| (*rc).clone())
+    }
+
+    /// Convert into sequence without cloning the items if the node holds the
+    /// last reference of the data, see [`Node::into_yaml`].
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// let n = node!([node!([1, 2]), node!([3])]);
+    /// let v = n.try_into_seq_checked().unwrap();
+    /// assert_eq!(node!(3), v[1].clone().try_into_seq_checked().unwrap()[0]);
+    /// assert_eq!(0, node!(1).try_into_seq_checked().unwrap_err().pos);
+    /// ```
+    pub fn try_into_seq_checked(self) -> Result<Seq<R>, NodeError> {
+        let e = NodeError::wrong_type(&self, "seq");
+        match self.into_yaml() {
+            Yaml::Seq(v) => Ok(v),
+            _ => Err(e),
+        }
+    }
+
+    /// Convert into map without cloning the items if the node holds the last
+    /// reference of the data, see [`Node::into_yaml`].
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// let n = node!({"a" => 1});
+    /// for (k, v) in n.try_into_map_checked().unwrap() {
+    ///     assert_eq!((node!("a"), node!(1)), (k, v));
+    /// }
+    /// ```
+    pub fn try_into_map_checked(self) -> Result<Map<R>, NodeError> {
+        let e = NodeError::wrong_type(&self, "map");
+        match self.into_yaml() {
+            Yaml::Map(m) => Ok(m),
+            _ => Err(e),
+        }
+    }
+
+    /// Convert into string without cloning if the node holds the last
+    /// reference of the data, see [`Node::into_yaml`].
+    ///
+    /// Same as [`Node::as_str_checked`], null is represented as empty string.
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// assert_eq!("abc", node!("abc").try_into_string_checked().unwrap());
+    /// assert!(node!(()).try_into_string_checked().unwrap().is_empty());
+    /// ```
+    pub fn try_into_string_checked(self) -> Result<String, NodeError> {
+        let e = NodeError::wrong_type(&self, "str");
+        match self.into_yaml() {
+            Yaml::Str(s) => Ok(s),
+            Yaml::Null => Ok(String::new()),
+            _ => Err(e),
+        }
     }
 
     /// Convert to string pointer for string, null, bool, int, and float type.
@@ -309,20 +800,20 @@ impl<R: Repr> Node<R> {
     /// ```
     /// use yaml_peg::node;
     ///
-    /// assert_eq!("abc", node!("abc").as_value().unwrap());
-    /// assert_eq!("123", node!(123).as_value().unwrap());
-    /// assert_eq!("12.04", node!(12.04).as_value().unwrap());
-    /// assert_eq!("true", node!(true).as_value().unwrap());
-    /// assert_eq!("false", node!(false).as_value().unwrap());
-    /// assert!(node!(()).as_value().unwrap().is_empty());
+    /// assert_eq!("abc", node!("abc").as_value_checked().unwrap());
+    /// assert_eq!("123", node!(123).as_value_checked().unwrap());
+    /// assert_eq!("12.04", node!(12.04).as_value_checked().unwrap());
+    /// assert_eq!("true", node!(true).as_value_checked().unwrap());
+    /// assert_eq!("false", node!(false).as_value_checked().unwrap());
+    /// assert!(node!(()).as_value_checked().unwrap().is_empty());
     /// ```
-    pub fn as_value(&self) -> Result<&str, u64> {
+    pub fn as_value_checked(&self) -> Result<&str, NodeError> {
         match self.yaml() {
             Yaml::Str(s) | Yaml::Int(s) | Yaml::Float(s) => Ok(s),
             Yaml::Bool(true) => Ok("true"),
             Yaml::Bool(false) => Ok("false"),
             Yaml::Null => Ok(""),
-            _ => Err(self.pos),
+            _ => Err(NodeError::wrong_type(self, "scalar")),
         }
     }
 
@@ -335,84 +826,234 @@ impl<R: Repr> Node<R> {
     /// anchors.insert("a".to_string(), node!(20));
     /// assert_eq!(
     ///     20,
-    ///     node!(*"a").as_anchor(&anchors).unwrap().as_int().unwrap()
+    ///     node!(*"a").as_anchor_checked(&anchors).unwrap().as_int_checked().unwrap()
     /// );
     /// ```
-    pub fn as_anchor<'a>(&'a self, anchors: &'a Anchors<R>) -> Result<&'a Self, u64> {
+    pub fn as_anchor_checked<'a>(&'a self, anchors: &'a Anchors<R>) -> Result<&'a Self, NodeError> {
         if let Yaml::Alias(a) = self.yaml() {
-            anchors.get(a).ok_or(self.pos)
+            anchors.get(a).ok_or_else(|| {
+                NodeError::new(self.pos, NodeErrorKind::MissingKey { key: a.to_string() })
+            })
         } else {
             Ok(self)
         }
     }
 
+    /// Feed the content of the node into the hasher, the aliases are resolved
+    /// from `anchors` and the tags are hashed if `tags` is true.
+    ///
+    /// The numbers are hashed by their values as [`Hash`] does, so the
+    /// equivalent documents with different formatting have the same digest.
+    /// The recursive aliases and the missing anchors are hashed by their
+    /// names.
+    ///
+    /// ```
+    /// use std::{collections::hash_map::DefaultHasher, hash::Hasher};
+    /// use yaml_peg::parse_cyclic;
+    ///
+    /// fn digest(doc: &str, tags: bool) -> u64 {
+    ///     let (root, anchors) = parse_cyclic::<yaml_peg::repr::RcRepr>(doc).unwrap();
+    ///     let mut state = DefaultHasher::new();
+    ///     root[0].digest(&anchors[0], tags, &mut state);
+    ///     state.finish()
+    /// }
+    ///
+    /// let doc = "a: &x [0x10, b]\nc: *x\n";
+    /// assert_eq!(digest(doc, true), digest("{a: [16, b], c: [16, \"b\"]}", true));
+    /// assert_ne!(digest(doc, true), digest("{a: [16, b], c: [16, !t b]}", true));
+    /// assert_eq!(digest(doc, false), digest("{a: [16, b], c: [16, !t b]}", false));
+    /// ```
+    pub fn digest<H: Hasher>(&self, anchors: &Anchors<R>, tags: bool, state: &mut H) {
+        fn visit<'a, R: Repr, H: Hasher>(
+            n: &'a Node<R>,
+            anchors: &'a Anchors<R>,
+            tags: bool,
+            stack: &mut Vec<&'a str>,
+            state: &mut H,
+        ) {
+            match n.yaml() {
+                Yaml::Seq(v) => {
+                    state.write_u8(6);
+                    state.write_usize(v.len());
+                    v.iter().for_each(|n| visit(n, anchors, tags, stack, state));
+                }
+                Yaml::Map(m) => {
+                    state.write_u8(7);
+                    state.write_usize(m.len());
+                    m.iter().for_each(|(k, v)| {
+                        visit(k, anchors, tags, stack, state);
+                        visit(v, anchors, tags, stack, state);
+                    });
+                }
+                Yaml::Alias(a) => match anchors.get(a) {
+                    Some(n) if !stack.contains(&a.as_str()) => {
+                        stack.push(a);
+                        visit(n, anchors, tags, stack, state);
+                        stack.pop();
+                        return;
+                    }
+                    _ => n.yaml().hash(state),
+                },
+                yaml => yaml.hash(state),
+            }
+            if tags {
+                n.tag().hash(state);
+            }
+        }
+
+        visit(self, anchors, tags, &mut Vec::new(), state);
+    }
+
     /// Convert to map and try to get the value by key.
     ///
-    /// If any key is missing, return `Err` with node position.
+    /// If the key is missing or this node is not a map, return [`NodeError`]
+    /// with this node position.
     ///
     /// ```
-    /// # fn main() -> Result<(), u64> {
+    /// # fn main() -> Result<(), yaml_peg::NodeError> {
     /// use yaml_peg::node;
     ///
     /// let n = node!({node!("a") => node!({node!("b") => node!(30.)})});
-    /// assert_eq!(&node!(30.), n.get("a")?.get("b")?);
-    /// # Ok::<(), u64>(()) }
+    /// assert_eq!(&node!(30.), n.get_checked("a")?.get_checked("b")?);
+    /// # Ok::<(), yaml_peg::NodeError>(()) }
     /// ```
-    pub fn get<Y: Into<Self>>(&self, key: Y) -> Result<&Self, u64> {
+    pub fn get_checked<Y: Into<Self>>(&self, key: Y) -> Result<&Self, NodeError> {
         if let Yaml::Map(m) = self.yaml() {
-            m.get(&key.into()).ok_or(self.pos)
+            let key = key.into();
+            m.get(&key).ok_or_else(|| {
+                NodeError::missing_key(self.pos, key.as_value_checked().unwrap_or_default())
+            })
         } else {
-            Err(self.pos)
+            Err(NodeError::wrong_type(self, "map"))
         }
     }
 
-    /// Same as [`Node::get`] but provide default value if the key is missing.
-    /// For this method, a transform method `as_*` is required.
+    /// Same as [`Node::get_checked`] but the key is a string, which is faster
+    /// since the temporary key node is not required.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), yaml_peg::NodeError> {
+    /// use yaml_peg::node;
+    ///
+    /// let n = node!({"a" => node!({"b" => 30.}), 1 => "c"});
+    /// assert_eq!(&node!(30.), n.get_str_checked("a")?.get_str_checked("b")?);
+    /// assert!(n.get_str_checked("1").is_err());
+    /// # Ok::<(), yaml_peg::NodeError>(()) }
+    /// ```
+    pub fn get_str_checked(&self, key: &str) -> Result<&Self, NodeError> {
+        let Yaml::Map(m) = self.yaml() else {
+            return Err(NodeError::wrong_type(self, "map"));
+        };
+        m.get_str(key)
+            .ok_or_else(|| NodeError::missing_key(self.pos, key))
+    }
+
+    /// Same as [`Node::get_str_checked`] but the key is case-insensitive,
+    /// returns the matched key as well, e.g., for warning the users about the
+    /// spelling.
+    ///
+    /// The exact key is preferred, otherwise the first matched key in the
+    /// insertion order is used. See [`Node::get_normalized`] for other rules.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), yaml_peg::NodeError> {
+    /// use yaml_peg::node;
+    ///
+    /// let n = node!({"Name" => "a", "name" => "b", "PORT" => 80});
+    /// assert_eq!(("PORT", &node!(80)), n.get_ci("port")?);
+    /// assert_eq!(("name", &node!("b")), n.get_ci("name")?);
+    /// assert_eq!(("Name", &node!("a")), n.get_ci("NAME")?);
+    /// assert!(n.get_ci("host").is_err());
+    /// # Ok::<(), yaml_peg::NodeError>(()) }
+    /// ```
+    pub fn get_ci(&self, key: &str) -> Result<(&str, &Self), NodeError> {
+        self.get_normalized(key, str::to_lowercase)
+    }
+
+    /// Same as [`Node::get_ci`] but the keys are matched after being
+    /// normalized by the function, such as [`normalize_key`].
+    ///
+    /// ```
+    /// # fn main() -> Result<(), yaml_peg::NodeError> {
+    /// use yaml_peg::{node, normalize_key};
+    ///
+    /// let n = node!({" Max-Retries " => 3});
+    /// assert_eq!((" Max-Retries ", &node!(3)), n.get_normalized("max_retries", normalize_key)?);
+    /// # Ok::<(), yaml_peg::NodeError>(()) }
+    /// ```
+    pub fn get_normalized<F>(&self, key: &str, normalize: F) -> Result<(&str, &Self), NodeError>
+    where
+        F: Fn(&str) -> String,
+    {
+        let Yaml::Map(m) = self.yaml() else {
+            return Err(NodeError::wrong_type(self, "map"));
+        };
+        let find = |f: &dyn Fn(&str) -> bool| {
+            m.iter().find_map(|(k, v)| match k.yaml() {
+                Yaml::Str(s) if f(s) => Some((s.as_str(), v)),
+                _ => None,
+            })
+        };
+        let norm = normalize(key);
+        find(&|s| s == key)
+            .or_else(|| find(&|s| normalize(s) == norm))
+            .ok_or_else(|| NodeError::missing_key(self.pos, key))
+    }
+
+    /// Same as [`Node::get_checked`] but provide default value if the key is
+    /// missing. For this method, a transform method `as_*` is required.
     ///
     /// + If the value exist, return the value.
     /// + If value is a wrong type, return `Err` with node position.
     /// + If the value is not exist, return the default value.
+    /// + If this node is not a map (including null), return `Err` with this
+    ///   node position. Use [`Node::get_default_lenient`] to fall back to the
+    ///   default value instead.
+    ///
+    /// The error type of the factory can be any type converted from
+    /// [`NodeError`], such as `u64` for the position only.
     ///
     /// ```
-    /// # fn main() -> Result<(), u64> {
+    /// # fn main() -> Result<(), yaml_peg::NodeError> {
     /// use yaml_peg::{node, Node};
     ///
     /// let a = node!({node!("a") => node!({node!("b") => node!("c")})});
     /// assert_eq!(
     ///     "c",
-    ///     a.get("a")?.get_default("b", "d", Node::as_str)?
+    ///     a.get_checked("a")?.get_default("b", "d", Node::as_str_checked)?
     /// );
     /// let b = node!({node!("a") => node!({})});
     /// assert_eq!(
     ///     "d",
-    ///     b.get("a")?.get_default("b", "d", Node::as_str)?
+    ///     b.get_checked("a")?.get_default("b", "d", Node::as_str_checked)?
     /// );
     /// let c = node!({node!("a") => node!({node!("b") => node!(20.)})});
     /// assert_eq!(
     ///     Err(0),
-    ///     c.get("a")?.get_default("b", "d", Node::as_str)
+    ///     c.get_checked("a")?.get_default("b", "d", Node::as_str_checked).map_err(|e| e.pos)
     /// );
-    /// # Ok::<(), u64>(()) }
+    /// # Ok::<(), yaml_peg::NodeError>(()) }
     /// ```
     ///
     /// ```
-    /// # fn main() -> Result<(), u64> {
-    /// use yaml_peg::{node, Node};
+    /// # fn main() -> Result<(), yaml_peg::NodeError> {
+    /// use yaml_peg::{node, Node, Seq};
     ///
     /// let n = node!({node!("a") => node!([node!(1), node!(2), node!(3)])});
-    /// let a = n.get_default("c", vec![], Node::as_seq)?;
-    /// assert_eq!(a, vec![]);
-    /// # Ok::<(), u64>(()) }
+    /// let a = n.get_default("c", Seq::new(), Node::as_seq_checked)?;
+    /// assert!(a.is_empty());
+    /// # Ok::<(), yaml_peg::NodeError>(()) }
     /// ```
-    pub fn get_default<'a, Y, Ret, F>(
+    pub fn get_default<'a, Y, Ret, F, E>(
         &'a self,
         key: Y,
         default: Ret,
         factory: F,
-    ) -> Result<Ret, u64>
+    ) -> Result<Ret, E>
     where
         Y: Into<Self>,
-        F: FnOnce(&'a Self) -> Result<Ret, u64>,
+        F: FnOnce(&'a Self) -> Result<Ret, E>,
+        E: From<NodeError>,
     {
         if let Yaml::Map(m) = self.yaml() {
             if let Some(n) = m.get(&key.into()) {
@@ -421,26 +1062,611 @@ impl<R: Repr> Node<R> {
                 Ok(default)
             }
         } else {
-            Err(self.pos)
+            Err(NodeError::wrong_type(self, "map").into())
+        }
+    }
+
+    /// Same as [`Node::get_default`] but return the default value if this node
+    /// is not a map.
+    ///
+    /// This is useful for probing the optional sections which might be null
+    /// or omitted. The wrong type of the value is still an error.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), yaml_peg::NodeError> {
+    /// use yaml_peg::{node, Node};
+    ///
+    /// let n = node!({node!("a") => node!(()), node!("b") => node!({node!("c") => 10})});
+    /// assert_eq!(10, n.get_checked("b")?.get_default_lenient("c", 20, Node::as_int_checked)?);
+    /// assert_eq!(20, n.get_checked("a")?.get_default_lenient("c", 20, Node::as_int_checked)?);
+    /// assert!(n.get_checked("a")?.get_default("c", 20, Node::as_int_checked).is_err());
+    /// let n = node!({node!("c") => "x"});
+    /// assert!(n.get_default_lenient("c", 20, Node::as_int_checked).is_err());
+    /// # Ok::<(), yaml_peg::NodeError>(()) }
+    /// ```
+    pub fn get_default_lenient<'a, Y, Ret, F, E>(
+        &'a self,
+        key: Y,
+        default: Ret,
+        factory: F,
+    ) -> Result<Ret, E>
+    where
+        Y: Into<Self>,
+        F: FnOnce(&'a Self) -> Result<Ret, E>,
+        E: From<NodeError>,
+    {
+        if let Yaml::Map(_) = self.yaml() {
+            self.get_default(key, default, factory)
+        } else {
+            Ok(default)
         }
     }
 
     /// Get node through index indicator. Only suitable for sequence.
     ///
     /// ```
-    /// # fn main() -> Result<(), u64> {
+    /// # fn main() -> Result<(), yaml_peg::NodeError> {
     /// use yaml_peg::{node, Ind};
     ///
     /// let n = node!([node!("a"), node!("b"), node!("c")]);
-    /// assert_eq!(&node!("b"), n.get_ind(Ind(1))?);
-    /// # Ok::<(), u64>(()) }
+    /// assert_eq!(&node!("b"), n.get_ind_checked(Ind(1))?);
+    /// assert!(n.get_ind_checked(Ind(3)).is_err());
+    /// # Ok::<(), yaml_peg::NodeError>(()) }
     /// ```
-    pub fn get_ind(&self, ind: Ind) -> Result<&Self, u64> {
-        if let Yaml::Seq(v) = self.yaml() {
-            v.get(ind.0).ok_or(self.pos)
-        } else {
-            Err(self.pos)
+    pub fn get_ind_checked(&self, ind: Ind) -> Result<&Self, NodeError> {
+        self.get_seq_item(|_| Some(ind.0))
+    }
+
+    /// Get node through the index counting from the end, see [`RevInd`]. Only
+    /// suitable for sequence.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), yaml_peg::NodeError> {
+    /// use yaml_peg::{node, RevInd};
+    ///
+    /// let n = node!([node!("a"), node!("b"), node!("c")]);
+    /// assert_eq!(&node!("c"), n.get_rev_ind_checked(RevInd(1))?);
+    /// assert_eq!(&node!("a"), n.get_rev_ind_checked(RevInd(3))?);
+    /// assert!(n.get_rev_ind_checked(RevInd(4)).is_err());
+    /// # Ok::<(), yaml_peg::NodeError>(()) }
+    /// ```
+    pub fn get_rev_ind_checked(&self, ind: RevInd) -> Result<&Self, NodeError> {
+        self.get_seq_item(|len| ind.resolve(len))
+    }
+
+    fn get_seq_item(&self, ind: impl FnOnce(usize) -> Option<usize>) -> Result<&Self, NodeError> {
+        let Yaml::Seq(v) = self.yaml() else {
+            return Err(NodeError::wrong_type(self, "seq"));
+        };
+        let i = ind(v.len());
+        i.and_then(|i| v.get(i)).ok_or_else(|| {
+            let mut e = NodeError::out_of_range(self.pos);
+            // The reversed indices which are out of range are not recorded
+            if let Some(i) = i {
+                e.path.push(PathSegment::Ind(i));
+            }
+            e
+        })
+    }
+
+    /// Get the sub-sequence through the range indicator, returns a new node
+    /// which shares the items. Only suitable for sequence.
+    ///
+    /// The negative bounds count from the end as [`RevInd`] does, e.g., `-1`
+    /// is the last item. The position and the tag of this node are kept.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), yaml_peg::NodeError> {
+    /// use yaml_peg::{node, IndRange};
+    ///
+    /// let n = node!(["a", "b", "c", "d"]);
+    /// assert_eq!(node!(["b", "c"]), n.get_range(IndRange(1..3))?);
+    /// assert_eq!(node!(["c", "d"]), n.get_range(IndRange(-2..))?);
+    /// assert_eq!(node!(["a", "b", "c"]), n.get_range(IndRange(..=-2))?);
+    /// assert!(n.get_range(IndRange(3..5)).is_err());
+    /// # Ok::<(), yaml_peg::NodeError>(()) }
+    /// ```
+    pub fn get_range<T>(&self, range: IndRange<T>) -> Result<Self, NodeError>
+    where
+        T: RangeBounds<isize>,
+    {
+        let Yaml::Seq(v) = self.yaml() else {
+            return Err(NodeError::wrong_type(self, "seq"));
+        };
+        let len = v.len();
+        let start = match range.0.start_bound() {
+            Bound::Included(i) => resolve_ind(*i, len),
+            Bound::Excluded(i) => resolve_ind(*i, len).map(|i| i + 1),
+            Bound::Unbounded => Some(0),
+        };
+        let end = match range.0.end_bound() {
+            Bound::Included(i) => resolve_ind(*i, len).map(|i| i + 1),
+            Bound::Excluded(i) => resolve_ind(*i, len),
+            Bound::Unbounded => Some(len),
+        };
+        let items = start
+            .zip(end)
+            .and_then(|(start, end)| v.get(start..end))
+            .ok_or_else(|| NodeError::out_of_range(self.pos))?;
+        let mut n = self.clone();
+        n.set_yaml(Yaml::Seq(Seq::from(items)));
+        Ok(n)
+    }
+
+    /// Get the node through the path of string keys and indices, such as
+    /// `spec.containers[0].name`.
+    ///
+    /// The keys are separated by dots and the indices are wrapped by brackets,
+    /// the keys cannot contain these symbols. The negative indices count from
+    /// the end as [`Node::get_rev_ind_checked`] does, e.g., `[-1]` is the last
+    /// item. An empty path returns this node.
+    ///
+    /// If any key or index is missing, or the path is invalid, return `Err`
+    /// with the position of the last found node.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), yaml_peg::NodeError> {
+    /// use yaml_peg::node;
+    ///
+    /// let n = node!({"a" => node!({"b" => node!([node!({"c" => 10}), node!([20])])})});
+    /// assert_eq!(&node!(10), n.get_path_checked("a.b[0].c")?);
+    /// assert_eq!(&node!(20), n.get_path_checked("a.b[1][0]")?);
+    /// assert_eq!(&node!(20), n.get_path_checked("a.b[-1][-1]")?);
+    /// assert_eq!(&n, n.get_path_checked("")?);
+    /// assert!(n.get_path_checked("a.c").is_err());
+    /// assert!(n.get_path_checked("a.b[x]").is_err());
+    /// # Ok::<(), yaml_peg::NodeError>(()) }
+    /// ```
+    pub fn get_path_checked(&self, path: &str) -> Result<&Self, NodeError> {
+        // The found path is parsed again only for the error
+        let found = |end: usize| crate::path::parse_path(&path[..end]);
+        let mut node = self;
+        let mut start = 0;
+        for piece in path.split('.') {
+            let (key, mut inds) = piece.split_once('[').unwrap_or((piece, ""));
+            let end = |rest: &str| start + piece.len() - rest.len();
+            let parent = start.saturating_sub(1);
+            if !key.is_empty() {
+                node = node
+                    .get_str_checked(key)
+                    .map_err(|e| e.prefixed(&found(parent)))?;
+            } else if inds.is_empty() && !path.is_empty() {
+                return Err(NodeError::invalid_path(node.pos).prefixed(&found(parent)));
+            }
+            while !inds.is_empty() {
+                let parent = end(inds) - 1;
+                let invalid = || NodeError::invalid_path(node.pos).prefixed(&found(parent));
+                let (ind, rest) = inds.split_once(']').ok_or_else(invalid)?;
+                node = match ind.strip_prefix('-') {
+                    Some(ind) => {
+                        node.get_rev_ind_checked(RevInd(ind.parse().map_err(|_| invalid())?))
+                    }
+                    None => node.get_ind_checked(Ind(ind.parse().map_err(|_| invalid())?)),
+                }
+                .map_err(|e| e.prefixed(&found(parent)))?;
+                inds = rest.strip_prefix('[').unwrap_or(rest);
+                if inds.len() == rest.len() && !rest.is_empty() {
+                    return Err(NodeError::invalid_path(node.pos).prefixed(&found(end(rest))));
+                }
+            }
+            start += piece.len() + 1;
+        }
+        Ok(node)
+    }
+
+    /// Get the node through the [`NodePath`], the keys are matched as the
+    /// strings like [`Node::get_str_checked`].
+    ///
+    /// The error contains the path from this node to the missing item.
+    ///
+    /// ```
+    /// use yaml_peg::{node, NodePath};
+    ///
+    /// let n = node!({"a" => node!({"list" => node!([1, 2])})});
+    /// assert_eq!(&node!(2), n.get_at(&NodePath::new().key("a").key("list").ind(1)).unwrap());
+    /// let path = NodePath::new().key("a").key("list").ind(3).key("b");
+    /// let e = n.get_at(&path).unwrap_err();
+    /// assert_eq!(e.path.to_string(), "a.list[3]");
+    /// ```
+    pub fn get_at(&self, path: &NodePath) -> Result<&Self, NodeError> {
+        let mut node = self;
+        for (i, segment) in path.segments().iter().enumerate() {
+            let parent = || path.segments()[..i].iter().cloned().collect();
+            node = match segment {
+                PathSegment::Key(key) => node.get_str_checked(key),
+                PathSegment::Ind(ind) => node.get_ind_checked(Ind(*ind)),
+            }
+            .map_err(|e| e.prefixed(&parent()))?;
+        }
+        Ok(node)
+    }
+
+    pos_method! {
+        fn get_str(key: &str) = get_str_checked -> &Self
+        fn get_ind(ind: Ind) = get_ind_checked -> &Self
+        fn get_path(path: &str) = get_path_checked -> &Self
+        fn as_int() = as_int_checked -> i64
+        fn as_u64() = as_u64_checked -> u64
+        fn as_i128() = as_i128_checked -> i128
+        fn as_usize() = as_usize_checked -> usize
+        fn as_float() = as_float_checked -> f64
+        fn as_number() = as_number_checked -> f64
+        fn as_bool() = as_bool_checked -> bool
+        fn as_str() = as_str_checked -> &str
+        fn as_seq() = as_seq_checked -> Seq<R>
+        fn as_map() = as_map_checked -> Map<R>
+        fn as_value() = as_value_checked -> &str
+        fn as_anchor<'a>(anchors: &'a Anchors<R>) = as_anchor_checked -> &'a Self
+        fn try_normalize() = try_normalize_checked -> Self
+    }
+
+    /// Same as [`Node::get_checked`], but return the position only.
+    #[deprecated(note = "use the method which returns `NodeError` instead")]
+    pub fn get<Y: Into<Self>>(&self, key: Y) -> Result<&Self, u64> {
+        self.get_checked(key).map_err(u64::from)
+    }
+
+    /// Same as [`Node::try_into_seq_checked`], but return the position only.
+    #[deprecated(note = "use the method which returns `NodeError` instead")]
+    pub fn try_into_seq(self) -> Result<Seq<R>, u64> {
+        self.try_into_seq_checked().map_err(u64::from)
+    }
+
+    /// Same as [`Node::try_into_map_checked`], but return the position only.
+    #[deprecated(note = "use the method which returns `NodeError` instead")]
+    pub fn try_into_map(self) -> Result<Map<R>, u64> {
+        self.try_into_map_checked().map_err(u64::from)
+    }
+
+    /// Same as [`Node::try_into_string_checked`], but return the position
+    /// only.
+    #[deprecated(note = "use the method which returns `NodeError` instead")]
+    pub fn try_into_string(self) -> Result<String, u64> {
+        self.try_into_string_checked().map_err(u64::from)
+    }
+
+    /// Return true if the nodes share the same data holder, e.g., the node
+    /// is cloned from the other one, or the aliases of the same anchor are
+    /// replaced by [`parse`].
+    ///
+    /// The equivalent nodes which are created separately are not matched,
+    /// use `==` operator to compare their data.
+    ///
+    /// ```
+    /// use yaml_peg::{node, parse, repr::RcRepr};
+    ///
+    /// let root = parse::<RcRepr>("a: &x [1]\nb: *x\nc: [1]").unwrap().remove(0);
+    /// let a = root.get_checked("a").unwrap();
+    /// assert!(a.ptr_eq(root.get_checked("b").unwrap()));
+    /// assert!(!a.ptr_eq(root.get_checked("c").unwrap()));
+    /// assert_eq!(a, root.get_checked("c").unwrap());
+    /// ```
+    pub fn ptr_eq(&self, rhs: &Self) -> bool {
+        core::ptr::eq(self.yaml(), rhs.yaml())
+    }
+
+    /// Return true if the node or any of its descendants (map keys and
+    /// values included) is equal to the other node.
+    ///
+    /// The shared data holders are visited once, so the trees with many
+    /// replaced aliases are searched in linear time.
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// let n = node!({"a" => node!([1, node!({"b" => 2})])});
+    /// assert!(n.contains(&node!({"b" => 2})));
+    /// assert!(n.contains(&node!("a")));
+    /// assert!(n.contains(&n));
+    /// assert!(!n.contains(&node!(3)));
+    /// ```
+    pub fn contains(&self, node: &Self) -> bool {
+        fn visit<R: Repr>(
+            n: &Node<R>,
+            node: &Node<R>,
+            seen: &mut BTreeSet<*const Yaml<R>>,
+        ) -> bool {
+            if !seen.insert(n.yaml()) {
+                return false;
+            }
+            if n == node {
+                return true;
+            }
+            match n.yaml() {
+                Yaml::Seq(v) => v.iter().any(|n| visit(n, node, seen)),
+                Yaml::Map(m) => m
+                    .iter()
+                    .any(|(k, v)| visit(k, node, seen) || visit(v, node, seen)),
+                _ => false,
+            }
+        }
+
+        visit(self, node, &mut BTreeSet::new())
+    }
+
+    /// Find the paths of the nodes which match the predicate, in the
+    /// visiting order. The node itself is matched by the empty path.
+    ///
+    /// The map values are searched, but the keys are not. The shared data
+    /// holders are visited in every place, so their paths are all returned.
+    ///
+    /// ```
+    /// use yaml_peg::{node, Yaml};
+    ///
+    /// let n = node!({"a" => node!([1, "x", node!({"b" => 2})]), "c" => 3});
+    /// let paths = n.find_all(|n| matches!(n.yaml(), Yaml::Int(_)));
+    /// let paths = paths.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+    /// assert_eq!(paths, ["a[0]", "a[2].b", "c"]);
+    /// ```
+    pub fn find_all<F>(&self, f: F) -> Vec<NodePath>
+    where
+        F: Fn(&Self) -> bool,
+    {
+        let mut found = Vec::new();
+        path::find_all(self, &f, &mut NodePath::new(), &mut found);
+        found
+    }
+
+    /// Iterate over the items of the sequence without cloning the vector.
+    ///
+    /// Other types are treated as empty sequences. The reference of the node
+    /// can be iterated as well.
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// let n = node!([1, 2, 3]);
+    /// assert_eq!(6, n.iter().map(|n| n.as_int_checked().unwrap()).sum::<i64>());
+    /// for (i, n) in (&n).into_iter().enumerate() {
+    ///     assert_eq!(node!(i as i64 + 1), *n);
+    /// }
+    /// assert_eq!(0, node!("a").iter().count());
+    /// ```
+    pub fn iter(&self) -> core::slice::Iter<'_, Self> {
+        match self.yaml() {
+            Yaml::Seq(v) => v.iter(),
+            _ => [].iter(),
+        }
+    }
+
+    /// Iterate over the key-value pairs of the map without cloning the map.
+    ///
+    /// Other types are treated as empty maps.
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// let n = node!({"a" => 1, "b" => 2});
+    /// let keys = n.entries().map(|(k, _)| k.as_str_checked().unwrap()).collect::<Vec<_>>();
+    /// assert_eq!(vec!["a", "b"], keys);
+    /// ```
+    pub fn entries(&self) -> impl Iterator<Item = (&Self, &Self)> {
+        match self.yaml() {
+            Yaml::Map(m) => Some(m),
+            _ => None,
+        }
+        .into_iter()
+        .flatten()
+    }
+
+    /// Convert the YAML 1.1 set `!!set` into its items, the keys of the map.
+    ///
+    /// The tag is not checked, any map whose values are all null is a set.
+    /// Return `Err` with the position of the node if it is not a map, or the
+    /// position of the first value which is not null.
+    ///
+    /// ```
+    /// use yaml_peg::{node, parse, repr::RcRepr};
+    ///
+    /// let root = parse::<RcRepr>("!!set\n? a\n? b\n").unwrap();
+    /// assert!(root[0].is_tag("!!set"));
+    /// assert_eq!(root[0].as_set().unwrap(), [&node!("a"), &node!("b")]);
+    /// assert!(node!([1]).as_set().is_err());
+    /// ```
+    pub fn as_set(&self) -> Result<Vec<&Self>, NodeError> {
+        match self.yaml() {
+            Yaml::Map(m) => m
+                .iter()
+                .map(|(k, v)| match v.yaml() {
+                    Yaml::Null => Ok(k),
+                    _ => Err(NodeError::wrong_type(v, "null")),
+                })
+                .collect(),
+            _ => Err(NodeError::wrong_type(self, "map")),
+        }
+    }
+
+    /// Convert the YAML 1.1 ordered map `!!omap` or pairs `!!pairs` into the
+    /// key-value pairs, a sequence of the single-entry maps.
+    ///
+    /// The tag is not checked, and the duplicated keys are allowed. Return
+    /// `Err` with the position of the node if it is not a sequence, or the
+    /// position of the first item which is not a single-entry map.
+    ///
+    /// ```
+    /// use yaml_peg::{node, parse, repr::RcRepr};
+    ///
+    /// let root = parse::<RcRepr>("!!pairs\n- a: 1\n- b: 2\n- a: 3\n").unwrap();
+    /// let pairs = root[0].as_pairs().unwrap();
+    /// assert_eq!(pairs[2], (&node!("a"), &node!(3)));
+    /// assert!(node!([node!({1 => 2, 3 => 4})]).as_pairs().is_err());
+    /// ```
+    pub fn as_pairs(&self) -> Result<Vec<(&Self, &Self)>, NodeError> {
+        match self.yaml() {
+            Yaml::Seq(v) => v
+                .iter()
+                .map(|n| match n.yaml() {
+                    Yaml::Map(m) if m.len() == 1 => Ok(m.iter().next().unwrap()),
+                    Yaml::Map(_) => Err(NodeError::out_of_range(n.pos)),
+                    _ => Err(NodeError::wrong_type(n, "map")),
+                })
+                .collect(),
+            _ => Err(NodeError::wrong_type(self, "seq")),
+        }
+    }
+
+    /// The number of the items of the sequence or the map, zero for other
+    /// types.
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// assert_eq!(3, node!([1, 2, 3]).len());
+    /// assert_eq!(1, node!({1 => 2}).len());
+    /// assert!(node!("abc").is_empty());
+    /// ```
+    pub fn len(&self) -> usize {
+        match self.yaml() {
+            Yaml::Seq(v) => v.len(),
+            Yaml::Map(m) => m.len(),
+            _ => 0,
+        }
+    }
+
+    /// Return true if the sequence or the map has no item, or the node is
+    /// other types.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check the number lexemes recursively, and rewrite them into the
+    /// decimal form.
+    ///
+    /// The nodes created by the parser are always valid, but the nodes
+    /// constructed manually might not. Return [`NodeError`] with the position
+    /// of the invalid number.
+    ///
+    /// ```
+    /// use yaml_peg::{node, Yaml};
+    ///
+    /// let n = node!([node!(Yaml::Int("0x10".into())), node!(Yaml::Float("1e3".into()))]);
+    /// let n = n.try_normalize_checked().unwrap();
+    /// assert_eq!(Ok("16"), n[yaml_peg::Ind(0)].as_value_checked());
+    /// assert_eq!(Ok("1000"), n[yaml_peg::Ind(1)].as_value_checked());
+    /// let n = node!({"a" => node!(Yaml::Int("abc".into()))});
+    /// assert_eq!(0, n.try_normalize_checked().unwrap_err().pos);
+    /// ```
+    pub fn try_normalize_checked(&self) -> Result<Self, NodeError> {
+        let yaml = match self.yaml() {
+            Yaml::Int(s) => match to_i128(&self.lexeme(s)) {
+                Ok(n) => Yaml::from(n),
+                // Out of range of signed integer
+                Err(_) => Yaml::from(to_u128(s).map_err(|_| NodeError::out_of_range(self.pos))?),
+            },
+            Yaml::Float(s) => {
+                Yaml::from(to_f64(&self.lexeme(s)).map_err(|_| NodeError::out_of_range(self.pos))?)
+            }
+            Yaml::Seq(v) => Yaml::Seq(
+                v.iter()
+                    .map(Self::try_normalize_checked)
+                    .collect::<Result<_, NodeError>>()?,
+            ),
+            Yaml::Map(m) => Yaml::Map(
+                m.iter()
+                    .map(|(k, v)| Ok((k.try_normalize_checked()?, v.try_normalize_checked()?)))
+                    .collect::<Result<_, NodeError>>()?,
+            ),
+            _ => return Ok(self.clone()),
+        };
+        let mut node = self.clone();
+        node.set_yaml(yaml);
+        Ok(node)
+    }
+
+    /// Sort the items of the sequence with the comparator, returns a new node.
+    ///
+    /// The sort is stable. The node is cloned without rebuilding if it is
+    /// already sorted, so the data holder is still shared. Other types are
+    /// returned as is.
+    ///
+    /// ```
+    /// use yaml_peg::node;
+    ///
+    /// let n = node!(["c", "a", "b"]);
+    /// let sorted = n.sort_seq_by(|a, b| a.as_value_checked().unwrap().cmp(b.as_value_checked().unwrap()));
+    /// assert_eq!(node!(["a", "b", "c"]), sorted);
+    /// assert_eq!(node!(["c", "a", "b"]), n);
+    /// let again = sorted.sort_seq_by(|a, b| a.as_value_checked().unwrap().cmp(b.as_value_checked().unwrap()));
+    /// assert!(again.ptr_eq(&sorted));
+    /// ```
+    pub fn sort_seq_by<F>(&self, mut cmp: F) -> Self
+    where
+        F: FnMut(&Self, &Self) -> core::cmp::Ordering,
+    {
+        let Yaml::Seq(v) = self.yaml() else {
+            return self.clone();
+        };
+        if v.windows(2).all(|w| cmp(&w[0], &w[1]).is_le()) {
+            return self.clone();
+        }
+        let mut v = v.clone();
+        v.sort_by(cmp);
+        let mut n = self.clone();
+        n.set_yaml(Yaml::Seq(v));
+        n
+    }
+
+    /// Remove the repeated items of the sequence, the first ones are kept,
+    /// returns a new node.
+    ///
+    /// The items are compared as [`PartialEq`] does, so the numbers are
+    /// compared by their values. The node is cloned without rebuilding if
+    /// there are no repeated items. Other types are returned as is.
+    ///
+    /// ```
+    /// use yaml_peg::{node, Yaml};
+    ///
+    /// let n = node!([1, 2, node!(Yaml::Int("0x1".into())), 3, 2]);
+    /// assert_eq!(node!([1, 2, 3]), n.dedup_seq());
+    /// let n = node!([1, 2]);
+    /// assert!(n.dedup_seq().ptr_eq(&n));
+    /// ```
+    pub fn dedup_seq(&self) -> Self {
+        let Yaml::Seq(v) = self.yaml() else {
+            return self.clone();
+        };
+        let mut seen = Map::default();
+        let v = v
+            .iter()
+            .filter(|n| seen.insert((*n).clone(), Self::from(())).is_none())
+            .cloned()
+            .collect::<Seq<R>>();
+        if v.len() == self.len() {
+            return self.clone();
         }
+        let mut n = self.clone();
+        n.set_yaml(Yaml::Seq(v));
+        n
+    }
+
+    /// Copy the tree into another representation, such as from [`NodeRc`] to
+    /// [`NodeArc`].
+    ///
+    /// The positions, tags, styles and comments are kept, but the shared data holders
+    /// are copied separately.
+    ///
+    /// ```
+    /// use yaml_peg::{node, repr::ArcRepr, NodeArc};
+    ///
+    /// let n = node!({"a" => node!([1, 2])});
+    /// let n: NodeArc = n.to_repr::<ArcRepr>();
+    /// assert_eq!(node!(arc {"a" => node!(arc [1, 2])}), n);
+    /// ```
+    pub fn to_repr<R2: Repr>(&self) -> Node<R2> {
+        let yaml = match self.yaml() {
+            Yaml::Null => Yaml::Null,
+            Yaml::Bool(b) => Yaml::Bool(*b),
+            Yaml::Int(s) => Yaml::Int(s.clone()),
+            Yaml::Float(s) => Yaml::Float(s.clone()),
+            Yaml::Str(s) => Yaml::Str(s.clone()),
+            Yaml::Seq(v) => Yaml::Seq(v.iter().map(Self::to_repr).collect()),
+            Yaml::Map(m) => Yaml::Map(m.iter().map(|(k, v)| (k.to_repr(), v.to_repr())).collect()),
+            Yaml::Alias(a) => Yaml::Alias(a.clone()),
+        };
+        let mut n = Node::new(yaml, self.pos, &self.tag);
+        n.style = self.style;
+        n.meta = self.meta.clone();
+        n
     }
 }
 
@@ -454,6 +1680,7 @@ impl<R: Repr> Clone for Node<R> {
     fn clone(&self) -> Self {
         Self {
             tag: self.tag.clone(),
+            meta: self.meta.clone(),
             yaml: self.clone_yaml(),
             ..*self
         }
@@ -462,12 +1689,28 @@ impl<R: Repr> Clone for Node<R> {
 
 impl<R: Repr> Hash for Node<R> {
     fn hash<H: Hasher>(&self, state: &mut H) {
+        #[cfg(feature = "native-num")]
+        match self.num {
+            Num::Int(n) => return hash_int(n.into(), state),
+            Num::Float(f) => return hash_float(f, state),
+            Num::None => {}
+        }
         self.yaml.hash(state)
     }
 }
 
 impl<R: Repr> PartialEq for Node<R> {
     fn eq(&self, rhs: &Self) -> bool {
+        // The shared data holders are always equal
+        if self.ptr_eq(rhs) {
+            return true;
+        }
+        #[cfg(feature = "native-num")]
+        match (self.num, rhs.num) {
+            (Num::Int(n1), Num::Int(n2)) => return n1 == n2,
+            (Num::Float(f1), Num::Float(f2)) => return float_eq(f1, f2),
+            _ => {}
+        }
         self.yaml.eq(&rhs.yaml)
     }
 }
@@ -477,6 +1720,61 @@ impl<R: Repr> Eq for Node<R> {}
 /// Indicator of the node use to index the sequence position.
 pub struct Ind(pub usize);
 
+/// Indicator of the sequence position counting from the end, `RevInd(1)` is
+/// the last item, as the index `-1` of [`Node::get_path_checked`].
+///
+/// ```
+/// use yaml_peg::{node, RevInd};
+///
+/// let n = node!(["a", "b", "c"]);
+/// assert_eq!(node!("c"), n[RevInd(1)]);
+/// assert_eq!(node!("b"), n[RevInd(2)]);
+/// ```
+pub struct RevInd(pub usize);
+
+impl RevInd {
+    /// Resolve the index with the length of the sequence, returns `None` if
+    /// the index is out of range.
+    fn resolve(&self, len: usize) -> Option<usize> {
+        len.checked_sub(self.0).filter(|_| self.0 > 0)
+    }
+}
+
+/// Resolve the signed index of [`IndRange`] with the length of the sequence.
+fn resolve_ind(i: isize, len: usize) -> Option<usize> {
+    if i < 0 {
+        RevInd(i.unsigned_abs()).resolve(len)
+    } else {
+        Some(i as usize)
+    }
+}
+
+/// Indicator of the range of the sequence items, see [`Node::get_range`].
+///
+/// The bounds can be negative as [`RevInd`] does, e.g., `IndRange(-2..)` is
+/// the last two items.
+pub struct IndRange<T: RangeBounds<isize>>(pub T);
+
+/// Normalize the key for the tolerant lookup of [`Node::get_normalized`].
+///
+/// The whitespaces around the key are trimmed, the letters are lowercased,
+/// and the dashes and the spaces are replaced with the underscores, so the
+/// keys in the kebab case and the snake case are matched.
+///
+/// ```
+/// use yaml_peg::normalize_key;
+///
+/// assert_eq!("max_retries", normalize_key(" Max-Retries "));
+/// assert_eq!("max_retries", normalize_key("max retries"));
+/// ```
+pub fn normalize_key(key: &str) -> String {
+    key.trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| if c == '-' || c == ' ' { '_' } else { c })
+        .collect()
+}
+
 impl<R: Repr> Index<Ind> for Node<R> {
     type Output = Self;
 
@@ -489,6 +1787,18 @@ impl<R: Repr> Index<Ind> for Node<R> {
     }
 }
 
+impl<R: Repr> Index<RevInd> for Node<R> {
+    type Output = Self;
+
+    fn index(&self, index: RevInd) -> &Self::Output {
+        if let Yaml::Seq(v) = self.yaml() {
+            v.index(index.resolve(v.len()).expect("out of bound!"))
+        } else {
+            panic!("out of bound!")
+        }
+    }
+}
+
 impl<R, I> Index<I> for Node<R>
 where
     R: Repr,
@@ -506,6 +1816,17 @@ where
     }
 }
 
+/// Resolve the shorthand of the tag, see [`Node::set_tag`].
+fn resolve_tag(tag: &str) -> Cow<'_, str> {
+    if let Some(tag) = tag.strip_prefix("!!") {
+        Cow::Owned(format!("{}{tag}", parser::DEFAULT_PREFIX))
+    } else if let Some(tag) = tag.strip_prefix("!<").and_then(|t| t.strip_suffix('>')) {
+        Cow::Borrowed(tag)
+    } else {
+        Cow::Borrowed(tag.strip_prefix('!').unwrap_or(tag))
+    }
+}
+
 impl<R, Y> From<Y> for Node<R>
 where
     R: Repr,
@@ -516,6 +1837,15 @@ where
     }
 }
 
+impl<'a, R: Repr> IntoIterator for &'a Node<R> {
+    type Item = &'a Node<R>;
+    type IntoIter = core::slice::Iter<'a, Node<R>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 impl_iter! {
     impl Self
     impl (Self, Self)
@@ -0,0 +1,318 @@
+use crate::{repr::Repr, *};
+use alloc::{
+    collections::{btree_map, BTreeMap},
+    format,
+    string::String,
+    vec::Vec,
+};
+use core::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    ops::{Deref, DerefMut},
+};
+
+/// The handling of the anchors which are defined again in the same document,
+/// see [`Loader::anchor_policy`](super::Loader::anchor_policy).
+///
+/// The aliases always refer to the nearest definition before them, except
+/// the aliases of the cyclic mode, which are resolved after parsing.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+pub enum AnchorPolicy {
+    /// Raise the "duplicated anchor definition" error.
+    #[default]
+    Error,
+    /// The later definition replaces the earlier one, as the YAML spec.
+    LastWins,
+    /// The later definition takes the name, and the earlier ones are kept
+    /// with the suffixed names `-2`, `-3`, ... in the defining order.
+    Rename,
+}
+
+/// The anchor recorder of the [`Loader`](super::Loader), a map from the anchor
+/// names to the anchored nodes.
+///
+/// The map operations are available through [`Deref`]. The literal can be
+/// created by the [`anchors!`](crate::anchors) macro.
+///
+/// ```
+/// use yaml_peg::{anchors, node};
+///
+/// let anchors = anchors!["a" => 20, "b" => node!(*"a")];
+/// assert_eq!(&node!(20), &anchors["a"]);
+/// assert_eq!(node!(20), anchors.resolve_checked(&anchors["b"], true).unwrap());
+/// ```
+pub struct Anchors<R: Repr>(BTreeMap<String, Node<R>>);
+
+impl<R: Repr> Anchors<R> {
+    /// Create an empty anchor map.
+    pub fn new() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    /// Collect the shared data holders of the node tree and give them the
+    /// names `id001`, `id002`, ... in the visiting order.
+    ///
+    /// The shared data holders are the nodes that referenced more than once
+    /// by [`Node::clone`], such as the result of [`parse_cyclic`] or the
+    /// nodes built by hand.
+    ///
+    /// ```
+    /// use yaml_peg::{node, parser::Anchors};
+    ///
+    /// let a = node!([1, 2]);
+    /// let root = node!({"x" => a.clone(), "y" => a.clone()});
+    /// let anchors = Anchors::from_node(&root);
+    /// assert_eq!(Some("id001"), anchors.name_of(&a));
+    /// assert_eq!(None, anchors.name_of(&root));
+    /// ```
+    pub fn from_node(node: &Node<R>) -> Self {
+        shared_nodes(node)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (n, _))| (format!("id{:03}", i + 1), n.clone()))
+            .collect()
+    }
+
+    /// Find the anchor name of the node by the identity of the data holder.
+    ///
+    /// The equivalent nodes which are not the same holder are not matched.
+    pub fn name_of(&self, node: &Node<R>) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(_, n)| core::ptr::eq(n.yaml(), node.yaml()))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Replace the aliases of the node with the anchored nodes.
+    ///
+    /// If `deep` is false, only the alias of the node itself is replaced,
+    /// otherwise the aliases of the entire tree are replaced, including the
+    /// aliases inside the anchored nodes, see [`anchor_resolve_checked`].
+    ///
+    /// Returns [`NodeError`] with the position of the alias if the anchor is
+    /// missing or the alias refers to itself.
+    ///
+    /// ```
+    /// use yaml_peg::{anchors, node};
+    ///
+    /// let anchors = anchors!["a" => node!([1, 2]), "b" => node!([node!(*"a")])];
+    /// let n = &node!(*"b");
+    /// assert_eq!(node!([node!(*"a")]), anchors.resolve_checked(n, false).unwrap());
+    /// assert_eq!(node!([node!([1, 2])]), anchors.resolve_checked(n, true).unwrap());
+    /// ```
+    pub fn resolve_checked(&self, node: &Node<R>, deep: bool) -> Result<Node<R>, NodeError> {
+        if deep {
+            anchor_resolve_checked(node, self, usize::MAX)
+        } else {
+            node.as_anchor_checked(self).cloned()
+        }
+    }
+
+    /// Same as [`Anchors::resolve_checked`], but return the position only.
+    #[deprecated(note = "use the method which returns `NodeError` instead")]
+    pub fn resolve(&self, node: &Node<R>, deep: bool) -> Result<Node<R>, u64> {
+        self.resolve_checked(node, deep).map_err(u64::from)
+    }
+}
+
+/// Replace the aliases of the node tree with the anchored nodes recursively.
+///
+/// The `depth` is the maximum number of the nested substitutions, the aliases
+/// inside the anchored nodes are replaced until the depth is exhausted, and
+/// the remaining aliases are kept. Use [`usize::MAX`] to replace all of them.
+///
+/// Returns [`NodeError`] with the position of the alias if the anchor is
+/// missing or the alias refers to itself, such as the cyclic data from
+/// [`parse_cyclic`].
+///
+/// ```
+/// use yaml_peg::{anchor_resolve_checked, anchors, node, NodeErrorKind};
+///
+/// let anchors = anchors!["a" => 1, "b" => node!([node!(*"a")])];
+/// let n = node!({"x" => node!(*"b")});
+/// assert_eq!(n, anchor_resolve_checked(&n, &anchors, 0).unwrap());
+/// let ans = node!({"x" => node!([node!(*"a")])});
+/// assert_eq!(ans, anchor_resolve_checked(&n, &anchors, 1).unwrap());
+/// let ans = node!({"x" => node!([1])});
+/// assert_eq!(ans, anchor_resolve_checked(&n, &anchors, 2).unwrap());
+///
+/// let anchors = anchors!["a" => node!([node!(*"a")])];
+/// let e = anchor_resolve_checked(&node!(*"a"), &anchors, usize::MAX).unwrap_err();
+/// assert_eq!(e.kind, NodeErrorKind::CyclicAlias { anchor: "a".to_string() });
+/// ```
+pub fn anchor_resolve_checked<R: Repr>(
+    node: &Node<R>,
+    anchors: &Anchors<R>,
+    depth: usize,
+) -> Result<Node<R>, NodeError> {
+    fn visit<'a, R: Repr>(
+        node: &'a Node<R>,
+        anchors: &'a Anchors<R>,
+        depth: usize,
+        stack: &mut Vec<&'a str>,
+    ) -> Result<Node<R>, NodeError> {
+        match node.yaml() {
+            Yaml::Alias(_) if depth == 0 => Ok(node.clone()),
+            Yaml::Alias(a) => {
+                if stack.contains(&a.as_str()) {
+                    return Err(NodeError::cyclic_alias(node.pos(), a));
+                }
+                let n = anchors
+                    .get(a)
+                    .ok_or_else(|| NodeError::missing_key(node.pos(), a))?;
+                stack.push(a);
+                let n = visit(n, anchors, depth - 1, stack)?;
+                stack.pop();
+                Ok(n.resolved_from(node, a))
+            }
+            Yaml::Seq(v) => {
+                let v = v
+                    .iter()
+                    .map(|n| visit(n, anchors, depth, stack))
+                    .collect::<Result<Seq<R>, _>>()?;
+                let mut n = node.clone();
+                n.set_yaml(v);
+                Ok(n)
+            }
+            Yaml::Map(m) => {
+                let m = m
+                    .iter()
+                    .map(|(k, v)| {
+                        Ok::<_, NodeError>((
+                            visit(k, anchors, depth, stack)?,
+                            visit(v, anchors, depth, stack)?,
+                        ))
+                    })
+                    .collect::<Result<Map<R>, _>>()?;
+                let mut n = node.clone();
+                n.set_yaml(m);
+                Ok(n)
+            }
+            _ => Ok(node.clone()),
+        }
+    }
+
+    visit(node, anchors, depth, &mut Vec::new())
+}
+
+/// Same as [`anchor_resolve_checked`], but return the position only.
+#[deprecated(note = "use the function which returns `NodeError` instead")]
+pub fn anchor_resolve<R: Repr>(
+    node: &Node<R>,
+    anchors: &Anchors<R>,
+    depth: usize,
+) -> Result<Node<R>, u64> {
+    anchor_resolve_checked(node, anchors, depth).map_err(u64::from)
+}
+
+/// Collect the nodes which data holders are visited more than once, in the
+/// visiting order. The aliases are excluded.
+///
+/// The key of the first visited map value is returned together, or empty if
+/// the node is not a map value or the key is not a scalar.
+pub(crate) fn shared_nodes<R: Repr>(node: &Node<R>) -> Vec<(&Node<R>, &str)> {
+    fn visit<'a, R: Repr>(
+        node: &'a Node<R>,
+        key: &'a str,
+        count: &mut BTreeMap<*const Yaml<R>, usize>,
+        order: &mut Vec<(&'a Node<R>, &'a str)>,
+    ) {
+        let c = count.entry(node.yaml()).or_default();
+        *c += 1;
+        if *c > 1 {
+            return;
+        }
+        order.push((node, key));
+        match node.yaml() {
+            Yaml::Seq(v) => v.iter().for_each(|n| visit(n, "", count, order)),
+            Yaml::Map(m) => m.iter().for_each(|(k, v)| {
+                visit(k, "", count, order);
+                visit(v, k.as_value_checked().unwrap_or_default(), count, order);
+            }),
+            _ => (),
+        }
+    }
+
+    let mut count = BTreeMap::new();
+    let mut order = Vec::new();
+    visit(node, "", &mut count, &mut order);
+    order
+        .into_iter()
+        .filter(|(n, _)| count[&(n.yaml() as *const _)] > 1 && !matches!(n.yaml(), Yaml::Alias(_)))
+        .collect()
+}
+
+impl<R: Repr> Default for Anchors<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Repr> Clone for Anchors<R> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<R: Repr> Debug for Anchors<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.0.fmt(f)
+    }
+}
+
+impl<R: Repr> PartialEq for Anchors<R> {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.0 == rhs.0
+    }
+}
+
+impl<R: Repr> Eq for Anchors<R> {}
+
+impl<R: Repr> Deref for Anchors<R> {
+    type Target = BTreeMap<String, Node<R>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<R: Repr> DerefMut for Anchors<R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<R: Repr> From<BTreeMap<String, Node<R>>> for Anchors<R> {
+    fn from(m: BTreeMap<String, Node<R>>) -> Self {
+        Self(m)
+    }
+}
+
+impl<R: Repr> From<Anchors<R>> for BTreeMap<String, Node<R>> {
+    fn from(anchors: Anchors<R>) -> Self {
+        anchors.0
+    }
+}
+
+impl<R: Repr> FromIterator<(String, Node<R>)> for Anchors<R> {
+    fn from_iter<T: IntoIterator<Item = (String, Node<R>)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<R: Repr> IntoIterator for Anchors<R> {
+    type Item = (String, Node<R>);
+    type IntoIter = btree_map::IntoIter<String, Node<R>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, R: Repr> IntoIterator for &'a Anchors<R> {
+    type Item = (&'a String, &'a Node<R>);
+    type IntoIter = btree_map::Iter<'a, String, Node<R>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
@@ -12,7 +12,7 @@ impl Parser<'_> {
                 p.directive_tag()
             } else {
                 // Unknown - ignore
-                p.take_while(Self::not_in(b"\n\r"), TakeOpt::More(0))
+                p.take_until_nl()
             }
         })?;
         self.gap(true).map(|_| ())
@@ -20,14 +20,22 @@ impl Parser<'_> {
 
     fn directive_yaml(&mut self) -> PResult<()> {
         self.ws(TakeOpt::More(1))?;
-        if self.version_checked {
-            self.err("checked version")
-        } else if !self.context(|p| p.sym_seq(b"1.1").is_ok() || p.sym_seq(b"1.2").is_ok()) {
-            self.err("invalid version")
-        } else {
-            self.version_checked = true;
-            Ok(())
+        if self.version.is_some() {
+            return self.err("checked version");
+        }
+        self.version = self.context(|p| {
+            if p.sym_seq(b"1.1").is_ok() {
+                Some(Version::V1_1)
+            } else if p.sym_seq(b"1.2").is_ok() {
+                Some(Version::V1_2)
+            } else {
+                None
+            }
+        });
+        if self.version.is_none() {
+            return self.err_expected("invalid version", &["1.1", "1.2"]);
         }
+        Ok(())
     }
 
     fn directive_tag(&mut self) -> PResult<()> {
@@ -46,9 +54,10 @@ impl Parser<'_> {
             p.ws(TakeOpt::More(1))?;
             let doc = p.context(|p| {
                 p.take_while(Self::not_in(b" \n\r"), TakeOpt::More(1))?;
-                Ok(p.text())
+                p.uri_text()
             })?;
-            p.tag.insert(tag, doc);
+            p.tag.insert(tag.clone(), doc.clone());
+            p.tag_directives.insert(tag, doc);
             Ok(())
         })
     }
@@ -1,14 +1,16 @@
 use super::*;
 use alloc::format;
-use core::cmp::Ordering;
 
 /// The low level grammar implementation for YAML.
 ///
 /// These sub-parser returns `Result<R, PError>`, and calling
 /// [`Parser::backward`] if mismatched.
-impl Parser<'_> {
+impl<'a> Parser<'a> {
     /// Match invisible boundaries and keep the gaps. (must matched once)
     pub fn bound(&mut self) -> PResult<()> {
+        if self.food().is_empty() {
+            return Ok(());
+        }
         self.sym_set(b":{}[] ,\n\r")?;
         self.back(1);
         self.ws(TakeOpt::More(0))
@@ -48,7 +50,7 @@ impl Parser<'_> {
     }
 
     fn ascii_digit(i: u8) -> impl Fn(&u8) -> bool + 'static {
-        move |c| c.is_ascii_digit() || (*c > b'a' && *c < b'a' + i) || (*c > b'A' && *c < b'A' + i)
+        move |c| char::from(*c).is_digit(u32::from(i))
     }
 
     /// Match float.
@@ -64,9 +66,14 @@ impl Parser<'_> {
         Ok(s.trim_end_matches(".0").to_string())
     }
 
-    /// Match float with scientific notation.
+    /// Match float with scientific notation, the fraction is optional.
     pub fn sci_float(&mut self) -> PResult<String> {
         self.num_prefix()?;
+        self.context(|p| {
+            p.sym(b'.')?;
+            p.take_while(u8::is_ascii_digit, TakeOpt::More(0))
+        })
+        .unwrap_or_default();
         self.sym_set(b"eE")?;
         self.take_while(Self::is_in(b"+-"), TakeOpt::Range(0, 1))?;
         self.take_while(u8::is_ascii_digit, TakeOpt::More(1))?;
@@ -78,46 +85,93 @@ impl Parser<'_> {
         Ok(s)
     }
 
+    /// Match the number of JSON, e.g., `-1`, `0.5` and `1e-3`.
+    pub fn json_number(&mut self) -> PResult<String> {
+        self.sym(b'-').unwrap_or_default();
+        if self.context(|p| p.sym(b'0')).is_err() {
+            self.take_while(|c| matches!(c, b'1'..=b'9'), TakeOpt::One)?;
+            self.take_while(u8::is_ascii_digit, TakeOpt::More(0))?;
+        }
+        self.context(|p| {
+            p.sym(b'.')?;
+            p.take_while(u8::is_ascii_digit, TakeOpt::More(1))
+        })
+        .unwrap_or_default();
+        self.context(|p| {
+            p.sym_set(b"eE")?;
+            p.take_while(Self::is_in(b"+-"), TakeOpt::Range(0, 1))?;
+            p.take_while(u8::is_ascii_digit, TakeOpt::More(1))
+        })
+        .unwrap_or_default();
+        Ok(self.text())
+    }
+
     /// Match quoted string.
-    pub fn string_quoted(&mut self, sym: u8, ignore: &[u8]) -> PResult<String> {
+    ///
+    /// The escape sequences are only decoded in the double-quoted string
+    /// (`sym` is `"`), see [`Parser::escape_char`]. The string is borrowed
+    /// from the document if it has no escape sequences or line breaks.
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    /// use yaml_peg::parser::Parser;
+    ///
+    /// let mut p = Parser::new(b"'a b'");
+    /// assert!(matches!(p.string_quoted(b'\'', b"''").unwrap(), Cow::Borrowed("a b")));
+    /// let mut p = Parser::new(b"'a''b'");
+    /// assert!(matches!(p.string_quoted(b'\'', b"''").unwrap(), Cow::Owned(s) if s == "a'b"));
+    /// ```
+    pub fn string_quoted(&mut self, sym: u8, ignore: &[u8]) -> PResult<Cow<'a, str>> {
         self.context(|p| {
             p.sym(sym)?;
             p.forward();
-            let mut v = String::new();
+            let start = p.pos;
+            let mut v = Cow::Borrowed("");
+            // The escaped characters are not trimmed
+            let mut keep = 0;
             p.ws(TakeOpt::More(0))?;
-            v.push_str(&p.text());
+            p.push_text(&mut v, start);
             loop {
                 p.forward();
                 p.take_while(Self::not_in(&[b'\n', b'\r', b'\\', sym]), TakeOpt::More(0))?;
-                v.push_str(&p.text());
+                p.push_text(&mut v, start);
                 p.forward();
                 if p.sym_seq(ignore).is_ok() {
-                    v.push(char::from(sym));
-                } else if let Ok(mut t) = p.gap(false) {
-                    if v.ends_with('\\') {
-                        t -= 1;
-                    }
-                    match t.cmp(&1) {
-                        Ordering::Less => {}
-                        Ordering::Equal => {
-                            v.truncate(v.trim_end().len());
-                            // Manual wrapping
-                            if !v.ends_with("\\n") {
-                                v.push(' ');
-                            }
+                    v.to_mut().push(char::from(sym));
+                } else if let Ok(t) = p.gap(false) {
+                    let v = v.to_mut();
+                    v.truncate(v.trim_end().len().max(keep));
+                    if t == 1 {
+                        // Manual wrapping
+                        if !(v.len() == keep && v.ends_with('\n')) {
+                            v.push(' ');
                         }
-                        Ordering::Greater => {
-                            for _ in 0..t - 1 {
-                                v.push('\n');
-                            }
+                    } else {
+                        for _ in 0..t - 1 {
+                            v.push('\n');
                         }
                     }
                     // Remove leading space
                     p.ws(TakeOpt::More(0))?;
                 } else if p.sym(b'\\').is_ok() {
-                    v.push('\\');
+                    let v = v.to_mut();
+                    if sym != b'"' {
+                        v.push('\\');
+                    } else if let Ok(t) = p.gap(false) {
+                        // Escaped line break
+                        for _ in 0..t - 1 {
+                            v.push('\n');
+                        }
+                        p.ws(TakeOpt::More(0))?;
+                    } else {
+                        v.push(p.escape_char()?);
+                        keep = v.len();
+                    }
                 } else if p.sym(sym).is_ok() {
                     break;
+                } else {
+                    let expected: &[_] = if sym == b'"' { &["\""] } else { &["'"] };
+                    return p.err_expected("quoted string", expected);
                 }
             }
             Ok(v)
@@ -125,55 +179,74 @@ impl Parser<'_> {
     }
 
     /// Match plain string.
-    pub fn string_plain(&mut self, level: usize, inner: bool) -> PResult<String> {
-        let mut patt = b"[]{}: \n\r".to_vec();
-        if inner {
-            patt.push(b',');
-        }
+    ///
+    /// The string is ended by the following patterns, and the other
+    /// characters are the part of the string. The string is borrowed from the
+    /// document if it is not folded.
+    ///
+    /// + `: ` and `:` at the line end (`:` before the flow indicators in flow
+    ///   context).
+    /// + ` #` and `\t#` (comments).
+    /// + `,`, `[`, `]`, `{` and `}` in flow context.
+    /// + Less indented lines and document markers.
+    ///
+    /// ```
+    /// use yaml_peg::parser::Parser;
+    ///
+    /// let mut p = Parser::new(b"http://a.b:1/c#d # comment");
+    /// assert_eq!("http://a.b:1/c#d", p.string_plain(0, false).unwrap());
+    /// let mut p = Parser::new(b"a:b, c");
+    /// assert_eq!("a:b", p.string_plain(0, true).unwrap());
+    /// ```
+    pub fn string_plain(&mut self, level: usize, inner: bool) -> PResult<Cow<'a, str>> {
         self.context(|p| {
-            let mut v = String::new();
-            let mut is_leading = false;
+            let start = p.pos;
+            let mut v = Cow::Borrowed("");
             loop {
                 p.forward();
-                p.take_while(Self::not_in(&patt), TakeOpt::More(0))?;
-                v.push_str(&p.text());
-                p.forward();
-                if p.food().is_empty()
-                    || p.sym_seq(b": ").is_ok()
-                    || (p.sym(b':').is_ok() && p.nl().is_ok())
-                    || p.sym_seq(b" #").is_ok()
-                {
-                    p.backward();
-                    break;
-                }
+                p.take_while(Self::not_in(b"[]{}:#, \t\n\r"), TakeOpt::More(0))?;
+                p.push_text(&mut v, start);
                 p.forward();
-                if p.sym_set(b": ").is_ok() {
-                    // Remove leading space
-                    if is_leading && p.text() == " " {
+                let next = p.food().get(1).copied();
+                match p.food().first() {
+                    None => break,
+                    Some(b':') => match next {
+                        None | Some(b' ' | b'\t' | b'\n' | b'\r') => break,
+                        Some(b',' | b'[' | b']' | b'{' | b'}') if inner => break,
+                        _ => p.push_char(&mut v, ':'),
+                    },
+                    Some(b'#') if v.is_empty() || v.ends_with([' ', '\t']) => break,
+                    Some(b',') if inner => break,
+                    Some(b'[' | b']' | b'{' | b'}') if inner || v.is_empty() => break,
+                    Some(b'\n' | b'\r') => {
+                        // Line folding
+                        let Ok(t) = p.gap(true) else { break };
+                        if p.ind(level).is_err() || level == 0 && p.doc_marker() {
+                            break;
+                        }
+                        let v = v.to_mut();
                         v.truncate(v.trim_end().len());
-                    }
-                    v.push_str(&p.text());
-                } else if !inner && !v.is_empty() && p.sym_set(b"{}[]").is_ok() {
-                    v.push_str(&p.text());
-                    is_leading = false;
-                } else if p.ind(level).is_err() {
-                    if let Ok(t) = p.gap(true) {
                         if t == 1 {
                             v.push(' ');
                         }
                         for _ in 0..t - 1 {
                             v.push('\n');
                         }
-                        if p.ind(level).is_err() {
-                            break;
-                        }
-                    } else {
-                        break;
+                        // Remove leading space
+                        p.ws(TakeOpt::More(0))?;
+                        continue;
                     }
-                    is_leading = true;
+                    Some(c) => p.push_char(&mut v, char::from(*c)),
                 }
+                p.pos += 1;
             }
-            v.truncate(v.trim_end().len());
+            let v = match v {
+                Cow::Borrowed(s) => Cow::Borrowed(s.trim_end()),
+                Cow::Owned(mut s) => {
+                    s.truncate(s.trim_end().len());
+                    Cow::Owned(s)
+                }
+            };
             if v.is_empty() {
                 Err(PError::Mismatch)
             } else {
@@ -182,6 +255,13 @@ impl Parser<'_> {
         })
     }
 
+    /// Return true if the document marker `---` or `...` is at the cursor.
+    fn doc_marker(&self) -> bool {
+        let food = self.food();
+        (food.starts_with(b"---") || food.starts_with(b"..."))
+            && matches!(food.get(3), None | Some(b' ' | b'\t' | b'\n' | b'\r'))
+    }
+
     /// Match literal string.
     pub fn string_literal(&mut self, level: usize) -> PResult<String> {
         self.sym(b'|')?;
@@ -214,65 +294,192 @@ impl Parser<'_> {
     }
 
     /// Match wrapped string.
+    ///
+    /// The base indentation is detected by the first non-empty line, the
+    /// further indentation is kept. If `leading` is true, the lines are
+    /// joined by `sep` literally; otherwise, the lines are folded by `sep`,
+    /// except the empty lines and the more-indented lines, which keep their
+    /// newlines.
     pub fn string_wrapped(&mut self, level: usize, sep: u8, leading: bool) -> PResult<String> {
         self.context(|p| {
-            let mut v = String::new();
-            loop {
-                p.nl()?;
+            let mut lines = Vec::new();
+            let mut base = None;
+            p.nl()?;
+            let wrap = loop {
+                // The end of the stream is not an empty line
+                if p.food().is_empty() {
+                    break false;
+                }
+                let mut start = p.pos;
                 p.forward();
                 if p.ind(level).is_err() {
                     if let Ok(t) = p.gap(false) {
                         for _ in 0..t {
-                            v.push('\n');
+                            lines.push(String::new());
                         }
+                        start = p.pos;
                         if p.ind(level).is_err() {
-                            break;
+                            break true;
                         }
                     } else {
-                        break;
+                        break true;
                     }
                 }
                 p.forward();
-                p.take_while(Self::not_in(b"\n\r"), TakeOpt::More(0))?;
+                p.take_until_nl()?;
                 let s = p.text();
-                if leading {
-                    if !v.is_empty() {
-                        v.push(char::from(sep));
+                let ind = s.len() - s.trim_start_matches(' ').len();
+                if ind < s.len() {
+                    match base {
+                        // Less indented line is not a part of the string
+                        Some(base) if ind < base => {
+                            p.pos = start;
+                            break true;
+                        }
+                        Some(_) => (),
+                        None => base = Some(ind),
                     }
-                    v.push_str(&s);
-                } else {
-                    let s = s.trim_start();
-                    if !v.is_empty() && !v.ends_with(char::is_whitespace) {
-                        v.push(char::from(sep));
+                }
+                lines.push(s[ind.min(base.unwrap_or(ind))..].to_string());
+                if p.nl().is_err() {
+                    break false;
+                }
+            };
+            // Keep the last wrap
+            if wrap {
+                p.back(1);
+            }
+            let sep = char::from(sep);
+            if leading {
+                let mut v = String::new();
+                for (i, s) in lines.iter().enumerate() {
+                    if i != 0 {
+                        v.push(sep);
                     }
                     v.push_str(s);
                 }
+                return Ok(v + "\n");
             }
-            // Keep the last wrap
-            p.back(1);
+            let folded = |s: &str| !s.starts_with([' ', '\t']);
+            let mut v = String::new();
+            let mut last: Option<&str> = None;
+            let mut empty = 0;
+            for s in &lines {
+                if s.is_empty() {
+                    empty += 1;
+                    continue;
+                }
+                match last {
+                    Some(last) if folded(last) && folded(s) => match empty {
+                        0 => v.push(sep),
+                        _ => v.push_str(&"\n".repeat(empty)),
+                    },
+                    Some(_) => v.push_str(&"\n".repeat(empty + 1)),
+                    None => v.push_str(&"\n".repeat(empty)),
+                }
+                v.push_str(s);
+                last = Some(s);
+                empty = 0;
+            }
+            v.push_str(&"\n".repeat(empty));
             Ok(v + "\n")
         })
     }
 
-    /// Match an escaped string, return unescaped string.
+    /// Match the escape sequence behind the backslash, return the escaped
+    /// character.
+    ///
+    /// Raise "escape sequence" error if the sequence is invalid.
+    ///
+    /// ```
+    /// use yaml_peg::parser::Parser;
+    ///
+    /// assert_eq!('\x1B', Parser::new(b"e").escape_char().unwrap());
+    /// assert_eq!('中', Parser::new(b"u4e2d").escape_char().unwrap());
+    /// assert_eq!('🀄', Parser::new(b"U0001F004").escape_char().unwrap());
+    /// assert!(Parser::new(b"xZZ").escape_char().is_err());
+    /// assert!(Parser::new(b"q").escape_char().is_err());
+    /// ```
+    pub fn escape_char(&mut self) -> PResult<char> {
+        let n = match self.food().first() {
+            Some(b'x') => 2,
+            Some(b'u') => 4,
+            Some(b'U') => 8,
+            Some(c) => {
+                let c = match c {
+                    b'0' => '\0',
+                    b'a' => '\x07',
+                    b'b' => '\x08',
+                    b't' | b'\t' => '\t',
+                    b'n' => '\n',
+                    b'v' => '\x0B',
+                    b'f' => '\x0C',
+                    b'r' => '\r',
+                    b'e' => '\x1B',
+                    b' ' => ' ',
+                    b'"' => '"',
+                    b'/' => '/',
+                    b'\\' => '\\',
+                    b'N' => '\u{85}',
+                    b'_' => '\u{A0}',
+                    b'L' => '\u{2028}',
+                    b'P' => '\u{2029}',
+                    _ => return self.err("escape sequence"),
+                };
+                self.pos += 1;
+                return Ok(c);
+            }
+            None => return self.err("escape sequence"),
+        };
+        self.pos += 1;
+        self.context(|p| {
+            p.take_while(u8::is_ascii_hexdigit, TakeOpt::Range(n, n))
+                .or_else(|_| p.err_expected("escape sequence", &["hex digits"]))?;
+            match u32::from_str_radix(&p.text(), 16)
+                .ok()
+                .and_then(char::from_u32)
+            {
+                Some(c) if p.text().len() == n => Ok(c),
+                _ => p.err("escape sequence"),
+            }
+        })
+    }
+
+    /// Unescape the string, the invalid escape sequences are kept.
+    #[deprecated(note = "renamed to `Parser::unescape`")]
     pub fn escape(doc: &str) -> String {
+        Self::unescape(doc)
+    }
+
+    /// Unescape the content of the double-quoted string, the invalid escape
+    /// sequences are kept.
+    ///
+    /// See [`Parser::escape_char`] for the supported escape sequences, and
+    /// [`escape_for_double_quotes`](crate::dumper::escape_for_double_quotes)
+    /// for the inverse.
+    ///
+    /// ```
+    /// use yaml_peg::{dumper::escape_for_double_quotes, parser::Parser};
+    ///
+    /// assert_eq!(Parser::unescape(r"a\tb\qc\U0001F600"), "a\tb\\qc😀");
+    /// let s = "\"😀\"\0\u{2028}\u{FEFF}";
+    /// assert_eq!(Parser::unescape(&escape_for_double_quotes(s)), s);
+    /// ```
+    pub fn unescape(doc: &str) -> String {
+        let mut p = Parser::new(doc.as_bytes());
         let mut s = String::new();
-        let mut b = false;
-        for c in doc.chars() {
-            if c == '\\' && !b {
-                b = true;
-                continue;
+        loop {
+            p.forward();
+            p.take_while(Self::not_in(b"\\"), TakeOpt::More(0))
+                .unwrap_or_default();
+            s.push_str(&p.text());
+            if p.sym(b'\\').is_err() {
+                break;
+            }
+            match p.escape_char() {
+                Ok(c) => s.push(c),
+                Err(_) => s.push('\\'),
             }
-            s.push(match c {
-                '\\' if b => '\\',
-                'n' if b => '\n',
-                'r' if b => '\r',
-                't' if b => '\t',
-                'b' if b => '\x08',
-                'f' if b => '\x0C',
-                c => c,
-            });
-            b = false;
         }
         s
     }
@@ -286,43 +493,102 @@ impl Parser<'_> {
         )
     }
 
+    /// Match the byte order mark of UTF-8. (always matched)
+    ///
+    /// The documents in other encodings should be decoded first, see
+    /// [`decode`].
+    pub fn bom(&mut self) -> PResult<()> {
+        if self.food().starts_with(b"\xFE\xFF") || self.food().starts_with(b"\xFF\xFE") {
+            return self.err_expected("encoding", &["UTF-8"]);
+        }
+        self.context(|p| p.sym_seq(encoding::BOM))
+            .unwrap_or_default();
+        self.forward();
+        Ok(())
+    }
+
     /// Match tags.
+    ///
+    /// The named handles such as `!e!` must be declared by the `%TAG`
+    /// directives, the undeclared handles are kept as written unless
+    /// [`Parser::strict_tags`] is enabled. The URI escapes such as `%20` are
+    /// decoded.
     pub fn tag(&mut self) -> PResult<String> {
         self.sym(b'!')?;
         self.context(|p| {
-            p.identifier().unwrap_or_default();
-            let tag = p.text();
-            let prefix = if !tag.is_empty() {
-                if p.sym(b'!').is_ok() {
-                    // Tag prefix variable
-                    p.tag[&tag].clone()
-                } else {
-                    String::new()
-                }
-            } else if p.sym(b'<').is_ok() {
-                // Full tag
+            if p.sym(b'<').is_ok() {
+                // Verbatim tag
                 let tag = p.context(|p| {
-                    p.take_while(Self::not_in(b" <>\n\r"), TakeOpt::More(1))?;
-                    Ok(p.text())
+                    p.take_while(Self::is_uri_char(b""), TakeOpt::More(1))?;
+                    p.uri_text()
                 })?;
                 p.sym(b'>')?;
-                tag
-            } else if p.sym(b'!').is_ok() {
-                p.tag["!!"].clone()
+                return Ok(tag);
+            }
+            p.identifier().unwrap_or_default();
+            let prefix = if p.pos > p.eaten && p.food().first() == Some(&b'!') {
+                // Named handle
+                let handle = p.text();
+                match p.tag.get(&handle) {
+                    Some(prefix) => prefix.clone(),
+                    None if p.strict_tags => return p.err("tag handle"),
+                    None => format!("!{handle}!"),
+                }
             } else {
-                p.tag["!"].clone()
-            };
-            let doc = p.context(|p| {
-                if p.identifier().is_ok() {
-                    p.text()
+                p.backward();
+                if p.food().first() == Some(&b'!') {
+                    p.tag["!!"].clone()
                 } else {
-                    String::new()
+                    p.tag["!"].clone()
                 }
-            });
-            Ok(format!("{prefix}{doc}"))
+            };
+            p.sym(b'!').unwrap_or_default();
+            let suffix = p.context(|p| {
+                p.take_while(Self::is_uri_char(b"!,[]{}"), TakeOpt::More(0))?;
+                p.uri_text()
+            })?;
+            Ok(format!("{prefix}{suffix}"))
         })
     }
 
+    /// A detector of the URI characters, excludes the characters `s`.
+    pub(crate) fn is_uri_char(s: &[u8]) -> impl Fn(&u8) -> bool + '_ {
+        move |c| {
+            (c.is_ascii_alphanumeric() || b"-#;/?:@&=+$,_.!~*'()[]{}%".contains(c))
+                && !s.contains(c)
+        }
+    }
+
+    /// Get the text like [`Parser::text`], and decode the URI escapes such as
+    /// `%20`.
+    pub(crate) fn uri_text(&mut self) -> PResult<String> {
+        let text = &self.doc[self.eaten..self.pos];
+        let mut v = Vec::with_capacity(text.len());
+        let mut i = 0;
+        while i < text.len() {
+            if text[i] != b'%' {
+                v.push(text[i]);
+                i += 1;
+                continue;
+            }
+            match text.get(i + 1..i + 3) {
+                Some(s) if s.iter().all(u8::is_ascii_hexdigit) => {
+                    let s = core::str::from_utf8(s).unwrap();
+                    v.push(u8::from_str_radix(s, 16).unwrap());
+                }
+                _ => {
+                    self.pos = self.eaten + i;
+                    return self.err("tag escape");
+                }
+            }
+            i += 3;
+        }
+        match String::from_utf8(v) {
+            Ok(s) => Ok(s),
+            Err(_) => self.err("tag escape"),
+        }
+    }
+
     /// Match anchor definition.
     pub fn anchor(&mut self) -> PResult<String> {
         self.sym(b'&')?;
@@ -380,6 +646,26 @@ impl Parser<'_> {
         Ok(())
     }
 
+    /// Record the indent size of a matched block collection, and check it in
+    /// the strict mode. The error is indicated at the `start` position.
+    pub(crate) fn record_indent(&mut self, level: usize, start: usize) -> PResult<()> {
+        let ind = self.indent[level];
+        if self.detected_indent.len() <= level {
+            self.detected_indent.resize(level + 1, None);
+        }
+        self.detected_indent[level].get_or_insert(ind);
+        if self.strict_indent && level > 0 && ind > 0 {
+            let step = *self
+                .indent_step
+                .get_or_insert(self.indent_hint.unwrap_or(ind));
+            if ind != step {
+                self.pos = start;
+                return self.err("inconsistent indentation");
+            }
+        }
+        Ok(())
+    }
+
     /// Match any optional invisible characters between two lines.
     ///
     /// Set `cmt` to `true` to ignore comments at the line end.
@@ -406,10 +692,43 @@ impl Parser<'_> {
         })
     }
 
+    /// Return true if the cursor is behind the explicit key indicator `?` or
+    /// the value indicator `:` at the beginning of the line, where the block
+    /// collections are started in the same line. The collections in the next
+    /// line are indented as usual, e.g., the zero-indented sequences.
+    pub(crate) fn explicit_node(&self) -> bool {
+        let line = &self.doc[..self.pos];
+        let start = line
+            .iter()
+            .rposition(|c| matches!(c, b'\n' | b'\r'))
+            .map_or(0, |i| i + 1);
+        let line = line[start..].trim_ascii();
+        let rest = self.food();
+        let end = rest.iter().position(|c| matches!(c, b'\n' | b'\r'));
+        let rest = rest[..end.unwrap_or(rest.len())].trim_ascii_start();
+        matches!(line.last(), Some(b'?' | b':'))
+            && line.iter().all(|c| matches!(c, b'?' | b':' | b'-' | b' '))
+            && !rest.is_empty()
+            && !rest.starts_with(b"#")
+    }
+
+    /// Match any invisible characters and comments between the flow items.
+    pub fn gap_flow(&mut self) -> PResult<()> {
+        loop {
+            self.inv(TakeOpt::More(0))?;
+            if self.context(Self::comment).is_err() {
+                return Ok(());
+            }
+        }
+    }
+
     /// Match comment.
     pub fn comment(&mut self) -> PResult<()> {
         self.ws(TakeOpt::More(0))?;
+        let start = self.pos;
         self.sym(b'#')?;
-        self.take_while(Self::not_in(b"\n\r"), TakeOpt::More(0))
+        self.take_until_nl()?;
+        self.push_comment(start);
+        Ok(())
     }
 }
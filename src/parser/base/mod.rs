@@ -1,5 +1,6 @@
 use super::*;
-use alloc::borrow::Cow;
+use alloc::{borrow::Cow, format};
+use core::ops::Range;
 
 mod directive;
 mod grammar;
@@ -16,15 +17,36 @@ pub enum TakeOpt {
     More(usize),
 }
 
+/// A comment of the document, see [`Parser::comments`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommentToken {
+    /// The byte range from the indicator `#` to the end of the line.
+    pub range: Range<u64>,
+    /// The text behind the indicator `#`.
+    pub text: String,
+}
+
 /// Basic greedy parser with YAML syntax.
 ///
-/// Its methods are actually the sub-parser of the syntax.
+/// Its methods are actually the sub-parser of the syntax. The scalar
+/// sub-parsers can be replaced for the [`Loader`] by a [`Grammar`], which
+/// builds the custom rules from the public sub-parsers.
 pub struct Parser<'a> {
     doc: &'a [u8],
-    indent: Vec<usize>,
+    filename: Option<String>,
+    pub(crate) indent: Vec<usize>,
+    indent_hint: Option<usize>,
+    indent_step: Option<usize>,
+    strict_indent: bool,
+    strict_tags: bool,
+    detected_indent: Vec<Option<usize>>,
+    comments: Vec<CommentToken>,
     consumed: u64,
-    pub(crate) version_checked: bool,
+    pub(crate) parents: Vec<u64>,
+    pub(crate) scopes: Vec<&'static str>,
+    pub(crate) version: Option<Version>,
     pub(crate) tag: BTreeMap<String, String>,
+    pub(crate) tag_directives: BTreeMap<String, String>,
     /// Current position.
     pub pos: usize,
     /// Read position.
@@ -38,10 +60,20 @@ impl Default for Parser<'_> {
         tag.insert("!!".to_string(), tag_prefix!().to_string());
         Self {
             doc: b"",
+            filename: None,
             indent: vec![0],
+            indent_hint: None,
+            indent_step: None,
+            strict_indent: false,
+            strict_tags: false,
+            detected_indent: Vec::new(),
+            comments: Vec::new(),
             consumed: 0,
-            version_checked: false,
+            parents: Vec::new(),
+            scopes: Vec::new(),
+            version: None,
             tag,
+            tag_directives: BTreeMap::new(),
             pos: 0,
             eaten: 0,
         }
@@ -60,13 +92,100 @@ impl<'a> Parser<'a> {
         Self { doc, ..self }
     }
 
+    /// Set the source name of the document, it will be shown in the error
+    /// messages. See [`indicated_msg_file`].
+    pub fn with_filename(self, filename: impl ToString) -> Self {
+        Self { filename: Some(filename.to_string()), ..self }
+    }
+
+    /// Set the indentation of the levels which cannot be detected, such as the
+    /// compact collections behind the sequence indicator `- `, defaults to 2.
+    ///
+    /// In the strict mode, the nested block collections must be indented by
+    /// this width, see [`Parser::strict_indent`].
+    pub fn with_indent_hint(self, indent: usize) -> Self {
+        Self { indent_hint: Some(indent), ..self }
+    }
+
+    /// Raise "inconsistent indentation" error if the nested block collections
+    /// are indented by the different widths. The width is the indent hint if
+    /// provided, otherwise the first detected width. The sequences in the
+    /// maps can still be not indented.
+    pub fn strict_indent(self, strict_indent: bool) -> Self {
+        Self { strict_indent, ..self }
+    }
+
+    /// Raise "tag handle" error if the named tag handles such as `!e!` are
+    /// not declared by the `%TAG` directives. Otherwise, the undeclared
+    /// handles are kept in the tags as written.
+    pub fn strict_tags(self, strict_tags: bool) -> Self {
+        Self { strict_tags, ..self }
+    }
+
+    /// The relative indentation of the block collections at each nesting
+    /// level, which is detected first. The levels without the block
+    /// collections are `None`.
+    ///
+    /// ```
+    /// use yaml_peg::{parser::Loader, repr::RcRepr};
+    ///
+    /// let doc = b"a:\n    b:\n      c: 1\n    d:\n    - e\n";
+    /// let mut loader = Loader::<RcRepr>::new(doc);
+    /// loader.parse().unwrap();
+    /// assert_eq!(loader.detected_indent(), [Some(0), Some(4), Some(2)]);
+    /// ```
+    pub fn detected_indent(&self) -> &[Option<usize>] {
+        &self.detected_indent
+    }
+
+    /// The comments of the parsed documents in order, includes the comments
+    /// that are not around any node, such as the file headers.
+    ///
+    /// ```
+    /// use yaml_peg::{parser::Loader, repr::RcRepr};
+    ///
+    /// let doc = b"# header\na: 1 # one\nb: '# not' # two\n";
+    /// let mut loader = Loader::<RcRepr>::new(doc);
+    /// loader.parse().unwrap();
+    /// let comments = loader.comments();
+    /// assert_eq!(comments.len(), 3);
+    /// assert_eq!(comments[1].range, 14..19);
+    /// assert_eq!(comments[2].text, " two");
+    /// ```
+    pub fn comments(&self) -> &[CommentToken] {
+        &self.comments
+    }
+
+    /// Record the comment from the position to the cursor, the comments that
+    /// are matched again by the backtracking are skipped.
+    pub(crate) fn push_comment(&mut self, start: usize) {
+        let range = self.consumed + start as u64..self.indicator();
+        let i = self
+            .comments
+            .partition_point(|c| c.range.start < range.start);
+        if self
+            .comments
+            .get(i)
+            .is_some_and(|c| c.range.start == range.start)
+        {
+            return;
+        }
+        let text = String::from_utf8_lossy(&self.doc[start + 1..self.pos]).into();
+        self.comments.insert(i, CommentToken { range, text });
+    }
+
+    /// Get the source name of the document if provided.
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
     /// Show the right hand side string after the current cursor.
     pub fn food(&self) -> &'a [u8] {
         &self.doc[self.pos..]
     }
 
     /// Encoded version of the left characters.
-    pub fn food_str(&self) -> Cow<str> {
+    pub fn food_str(&self) -> Cow<'_, str> {
         String::from_utf8_lossy(&self.doc[self.pos..])
     }
 
@@ -78,6 +197,23 @@ impl<'a> Parser<'a> {
             String::new()
         }
     }
+
+    /// Append the text from the eaten cursor to the current position to the
+    /// scalar `v`, which is borrowed from `start` if it is still contiguous.
+    pub(crate) fn push_text(&mut self, v: &mut Cow<'a, str>, start: usize) {
+        match v {
+            Cow::Borrowed(_) => *v = String::from_utf8_lossy(&self.doc[start..self.pos]),
+            Cow::Owned(v) => v.push_str(&self.text()),
+        }
+    }
+
+    /// Append the character at the current position to the scalar `v`, the
+    /// borrowed scalar is extended by [`Parser::push_text`] later.
+    pub(crate) fn push_char(&self, v: &mut Cow<'a, str>, c: char) {
+        if let Cow::Owned(v) = v {
+            v.push(c);
+        }
+    }
 }
 
 /// The low level grammar implementation.
@@ -96,11 +232,65 @@ impl Parser<'_> {
     }
 
     /// A short function to raise error.
+    ///
+    /// If the parser is in a map value, the nearest key will be indicated as
+    /// well. See [`indicated_msg_parent`].
     pub fn err<R>(&self, name: &'static str) -> PResult<R> {
-        Err(PError::Terminate {
-            name,
-            msg: indicated_msg(self.doc, self.indicator()),
-        })
+        self.err_expected(name, &[])
+    }
+
+    /// Same as [`Parser::err`], but provide the expected grammar elements.
+    ///
+    /// ```
+    /// use yaml_peg::parser::{PError, Parser};
+    ///
+    /// let e = Parser::new(b"@ab\ncd").err_expected::<()>("a", &["b"]).unwrap_err();
+    /// let PError::Terminate { pos, expected, found, .. } = e else { unreachable!() };
+    /// # #[cfg(not(feature = "compact-errors"))]
+    /// assert_eq!((0, ["b"].as_slice(), "@ab"), (pos, expected, found.as_str()));
+    /// ```
+    pub fn err_expected<R>(
+        &self,
+        name: &'static str,
+        expected: &'static [&'static str],
+    ) -> PResult<R> {
+        let (pos, found, msg) = (self.indicator(), self.found(), self.err_msg());
+        let context = self.scopes.as_slice().into();
+        Err(PError::Terminate { name, pos, expected, found, context, msg })
+    }
+
+    /// A short function to raise [`PError::LimitExceeded`] error.
+    pub fn limit_err<R>(&self, name: &'static str) -> PResult<R> {
+        let (pos, msg) = (self.indicator(), self.err_msg());
+        Err(PError::LimitExceeded { name, pos, msg })
+    }
+
+    /// The text at the current position until the end of line, at most 16
+    /// characters.
+    fn found(&self) -> String {
+        if cfg!(feature = "compact-errors") {
+            return String::new();
+        }
+        let line = self
+            .food()
+            .split(|c| matches!(c, b'\n' | b'\r'))
+            .next()
+            .unwrap_or_default();
+        String::from_utf8_lossy(line).chars().take(16).collect()
+    }
+
+    fn err_msg(&self) -> String {
+        if cfg!(feature = "compact-errors") {
+            return String::new();
+        }
+        let mut msg = match self.parents.last() {
+            Some(parent) => indicated_msg_parent(self.doc, self.indicator(), *parent),
+            None => indicated_msg(self.doc, self.indicator()),
+        };
+        if let Some(filename) = &self.filename {
+            msg.insert_str(0, &format!("{filename}:"));
+        }
+        msg
     }
 
     /// Consume and move the pointer.
@@ -144,6 +334,36 @@ impl Parser<'_> {
         Ok(())
     }
 
+    /// Match until the symbol sequence, the sequence itself is not matched.
+    ///
+    /// Mismatched if the sequence is not found in the rest of the document.
+    ///
+    /// ```
+    /// use yaml_peg::parser::Parser;
+    ///
+    /// let mut p = Parser::new(b"abc # comment");
+    /// assert!(p.take_until_seq(b" #").is_ok());
+    /// assert_eq!(p.text(), "abc");
+    /// assert!(p.take_until_seq(b"//").is_err());
+    /// ```
+    pub fn take_until_seq(&mut self, s: &[u8]) -> PResult<()> {
+        match memchr::memmem::find(self.food(), s) {
+            Some(n) => {
+                self.pos += n;
+                Ok(())
+            }
+            None => Err(PError::Mismatch),
+        }
+    }
+
+    /// Match until the newline characters or the end of the document.
+    /// (always matched)
+    pub fn take_until_nl(&mut self) -> PResult<()> {
+        let food = self.food();
+        self.pos += memchr::memchr2(b'\n', b'\r', food).unwrap_or(food.len());
+        Ok(())
+    }
+
     /// Match until the condition failed.
     ///
     /// The argument `opt` matches different terminate requirement.
@@ -230,7 +450,7 @@ impl Parser<'_> {
     pub fn ind(&mut self, level: usize) -> PResult<()> {
         if level >= self.indent.len() {
             for _ in 0..level - self.indent.len() + 1 {
-                self.indent.push(2);
+                self.indent.push(self.indent_hint.unwrap_or(2));
             }
         } else {
             // Clear the old indent settings
@@ -0,0 +1,62 @@
+use alloc::{collections::BTreeMap, string::String};
+
+/// The YAML version of the document.
+///
+/// The version is declared by the `%YAML` directive, or given by
+/// [`Loader::default_version`](super::Loader::default_version).
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Version {
+    /// YAML 1.1
+    V1_1,
+    /// YAML 1.2
+    #[default]
+    V1_2,
+}
+
+/// The schema to resolve the types of the plain scalars,
+/// see [`Loader::schema`](super::Loader::schema).
+///
+/// The quoted scalars are always strings.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+pub enum Schema {
+    /// JSON schema, only `null`, `true`, `false` and the JSON numbers are
+    /// resolved.
+    Json,
+    /// Core schema of YAML 1.2, includes the `~` null, the hexadecimal and
+    /// octal integers, and `.inf` / `.nan`.
+    #[default]
+    Core,
+    /// YAML 1.1 types, includes the core schema, and `y` / `n`, `yes` / `no`,
+    /// `on` / `off` are booleans.
+    Yaml11,
+}
+
+/// The information of a parsed document,
+/// returned by [`Loader::doc_info`](super::Loader::doc_info) and
+/// [`Loader::parse_directives`](super::Loader::parse_directives).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocInfo {
+    pub(crate) pos: u64,
+    pub(crate) version: Version,
+    pub(crate) tags: BTreeMap<String, String>,
+}
+
+impl DocInfo {
+    /// The starting position of the document, which is the position of the
+    /// directives or the document marker `---`.
+    pub fn pos(&self) -> u64 {
+        self.pos
+    }
+
+    /// The effective YAML version of the document.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// The tag handles declared by the `%TAG` directives of the document,
+    /// maps the handle names (`e` for `!e!`, or `!` and `!!` for the primary
+    /// and secondary handles) to the prefixes.
+    pub fn tags(&self) -> &BTreeMap<String, String> {
+        &self.tags
+    }
+}
@@ -0,0 +1,59 @@
+use super::*;
+
+/// An iterator parses the documents of the stream one by one, returned by
+/// [`Loader::documents`].
+///
+/// The rest of the stream is not parsed until the next iteration, and the
+/// iteration is stopped after an error. The anchors of the yielded documents
+/// are released to keep the memory usage of the long streams, so
+/// [`Loader::get_anchors`] only returns the empty anchors for them.
+///
+/// ```
+/// use yaml_peg::{node, parser::Loader, repr::RcRepr};
+///
+/// let doc = b"kind: a\n---\nkind: b\n---\n{'a' b: c}\n";
+/// let mut loader = Loader::<RcRepr>::new(doc);
+/// let mut docs = loader.documents();
+/// assert_eq!(docs.next().unwrap().unwrap(), node!({"kind" => "a"}));
+/// assert_eq!(docs.next().unwrap().unwrap(), node!({"kind" => "b"}));
+/// assert!(docs.next().unwrap().is_err());
+/// assert!(docs.next().is_none());
+/// ```
+pub struct Documents<'b, 'a, R: Repr> {
+    loader: &'b mut Loader<'a, R>,
+    n: usize,
+    done: bool,
+}
+
+impl<'b, 'a, R: Repr> Documents<'b, 'a, R> {
+    pub(crate) fn new(loader: &'b mut Loader<'a, R>) -> Self {
+        Self { loader, n: 0, done: false }
+    }
+
+    fn next_doc(&mut self) -> PResult<Option<Node<R>>> {
+        if !self.loader.next_doc(self.n)? {
+            return Ok(None);
+        }
+        let node = self.loader.doc()?;
+        if let Some(anchors) = self.loader.anchors.last_mut() {
+            *anchors = Anchors::new();
+        }
+        self.n += 1;
+        Ok(Some(node))
+    }
+}
+
+impl<R: Repr> Iterator for Documents<'_, '_, R> {
+    type Item = PResult<Node<R>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let ret = self.next_doc().transpose();
+        self.done = !matches!(ret, Some(Ok(_)));
+        ret
+    }
+}
+
+impl<R: Repr> core::iter::FusedIterator for Documents<'_, '_, R> {}
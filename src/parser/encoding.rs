@@ -0,0 +1,79 @@
+use super::PError;
+use crate::indicated_msg;
+use alloc::borrow::Cow;
+use alloc::{boxed::Box, string::String};
+
+/// The byte order mark of UTF-8.
+pub(crate) const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Decode the document into string, the encoding is detected by the byte
+/// order mark or the null bytes of the first character (YAML spec 5.2).
+///
+/// The byte order mark is removed. UTF-16 documents are transcoded with
+/// `utf16` feature, otherwise an error is returned.
+///
+/// ```
+/// use yaml_peg::{node, parse, parser::decode};
+///
+/// let doc = decode(b"\xEF\xBB\xBFa: b").unwrap();
+/// assert_eq!(vec![node!({"a" => "b"})], parse(&doc).unwrap());
+/// assert!(decode(b"\xFF\xFEa\0").is_ok() == cfg!(feature = "utf16"));
+/// assert!(decode(b"a: \xFF").is_err());
+/// ```
+pub fn decode(doc: &[u8]) -> Result<Cow<'_, str>, PError> {
+    match doc {
+        [0xFE, 0xFF, doc @ ..] => utf16(doc, u16::from_be_bytes),
+        [0xFF, 0xFE, doc @ ..] => utf16(doc, u16::from_le_bytes),
+        [0, _, ..] => utf16(doc, u16::from_be_bytes),
+        [_, 0, ..] => utf16(doc, u16::from_le_bytes),
+        _ => {
+            let doc = doc.strip_prefix(BOM).unwrap_or(doc);
+            core::str::from_utf8(doc)
+                .map(Cow::Borrowed)
+                .map_err(|e| PError::Terminate {
+                    name: "encoding",
+                    pos: e.valid_up_to() as u64,
+                    expected: &["UTF-8"],
+                    found: String::new(),
+                    context: Box::new([]),
+                    msg: if cfg!(feature = "compact-errors") {
+                        String::new()
+                    } else {
+                        indicated_msg(doc, e.valid_up_to() as u64)
+                    },
+                })
+        }
+    }
+}
+
+#[cfg(feature = "utf16")]
+fn utf16(doc: &[u8], f: fn([u8; 2]) -> u16) -> Result<Cow<'_, str>, PError> {
+    let err = |msg: String| PError::Terminate {
+        name: "encoding",
+        pos: 0,
+        expected: &[],
+        found: String::new(),
+        context: Box::new([]),
+        msg,
+    };
+    let chunks = doc.chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        return Err(err("odd length of UTF-16 document".into()));
+    }
+    char::decode_utf16(chunks.map(|c| f([c[0], c[1]])))
+        .collect::<Result<String, _>>()
+        .map(Cow::Owned)
+        .map_err(|e| err(alloc::format!("invalid UTF-16 document: {e}")))
+}
+
+#[cfg(not(feature = "utf16"))]
+fn utf16(_doc: &[u8], _f: fn([u8; 2]) -> u16) -> Result<Cow<'_, str>, PError> {
+    Err(PError::Terminate {
+        name: "encoding",
+        pos: 0,
+        expected: &[],
+        found: String::new(),
+        context: Box::new([]),
+        msg: "UTF-16 document requires `utf16` feature".into(),
+    })
+}
@@ -1,4 +1,5 @@
-use alloc::string::String;
+use crate::indicated_msg;
+use alloc::{boxed::Box, string::String};
 use core::fmt::{Display, Error, Formatter};
 
 /// Type of the parser result.
@@ -15,7 +16,29 @@ pub enum PError {
     Terminate {
         /// Name of sub-parser group.
         name: &'static str,
-        /// Document position.
+        /// The position of the error.
+        pos: u64,
+        /// The expected grammar elements, empty if not specified.
+        expected: &'static [&'static str],
+        /// The text found at the position, the rest of the line is truncated.
+        ///
+        /// Empty if the `compact-errors` feature is enabled.
+        found: String,
+        /// The grammar elements enclosing the error, from the outermost, e.g.,
+        /// `["doc", "map", "value", "flow seq"]`.
+        context: Box<[&'static str]>,
+        /// Document position, empty if the `compact-errors` feature is
+        /// enabled.
+        msg: String,
+    },
+    /// The resource limit of the [`Loader`](super::Loader) is exceeded.
+    LimitExceeded {
+        /// Name of the limit.
+        name: &'static str,
+        /// The position reached.
+        pos: u64,
+        /// Document position, empty if the `compact-errors` feature is
+        /// enabled.
         msg: String,
     },
 }
@@ -29,17 +52,56 @@ impl PError {
     {
         match self {
             Self::Mismatch => f(),
-            Self::Terminate { .. } => Err(self),
+            Self::Terminate { .. } | Self::LimitExceeded { .. } => Err(self),
         }
     }
+
+    /// The position of the error, zero if mismatched.
+    pub fn pos(&self) -> u64 {
+        match self {
+            Self::Mismatch => 0,
+            Self::Terminate { pos, .. } | Self::LimitExceeded { pos, .. } => *pos,
+        }
+    }
+
+    /// Render the indicated message of the error position with the document,
+    /// see [`indicated_msg`].
+    ///
+    /// This function is useful when the `compact-errors` feature is enabled,
+    /// which leaves the messages and the found text empty.
+    ///
+    /// ```
+    /// use yaml_peg::{parse, repr::RcRepr};
+    ///
+    /// let doc = "{'a' b: c}";
+    /// let e = parse::<RcRepr>(doc).unwrap_err();
+    /// assert_eq!("1:6\n{'a' b: c}\n     ^", e.render(doc.as_bytes()));
+    /// ```
+    pub fn render(&self, doc: &[u8]) -> String {
+        indicated_msg(doc, self.pos())
+    }
 }
 
 impl Display for PError {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         match self {
             Self::Mismatch => write!(f, "not matched"),
-            Self::Terminate { name, msg } => {
-                write!(f, "invalid {}: \n\n{}", name, msg)
+            Self::Terminate { name, pos, context, msg, .. } => {
+                write!(f, "invalid {}", name)?;
+                if !context.is_empty() {
+                    write!(f, " in {}", context.join(" > "))?;
+                }
+                if msg.is_empty() {
+                    write!(f, " at {}", pos)
+                } else {
+                    write!(f, ": \n\n{}", msg)
+                }
+            }
+            Self::LimitExceeded { name, pos, msg } if msg.is_empty() => {
+                write!(f, "{} limit exceeded at {}", name, pos)
+            }
+            Self::LimitExceeded { name, msg, .. } => {
+                write!(f, "{} limit exceeded: \n\n{}", name, msg)
             }
         }
     }
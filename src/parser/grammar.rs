@@ -0,0 +1,74 @@
+use super::*;
+
+/// The scalar sub-parsers of the [`Loader`], see [`Loader::grammar`].
+///
+/// Each method matches a scalar at the cursor of the [`Parser`] like the
+/// other sub-parsers, returns [`PError::Mismatch`] to let the loader try the
+/// next kind of the node, or [`PError::Terminate`] to stop the parsing. The
+/// default implementations call the sub-parsers of the same name, so a custom
+/// grammar only overrides the methods it needs, and the overridden methods
+/// can still fall back to the default sub-parsers.
+///
+/// ```
+/// use std::borrow::Cow;
+/// use yaml_peg::{node, parser::{Grammar, Loader, PResult, Parser, TakeOpt}, repr::RcRepr};
+///
+/// /// The expressions `$(...)` are plain strings, even if they contain `: ` or ` #`.
+/// struct Expr;
+///
+/// impl Grammar for Expr {
+///     fn string_plain<'a>(&self, p: &mut Parser<'a>, level: usize, inner: bool) -> PResult<Cow<'a, str>> {
+///         if !p.food().starts_with(b"$(") {
+///             return p.string_plain(level, inner);
+///         }
+///         p.context(|p| {
+///             p.sym_seq(b"$(")?;
+///             p.take_while(Parser::not_in(b")\n\r"), TakeOpt::More(0))?;
+///             p.sym(b')')?;
+///             Ok(Cow::Owned(p.text()))
+///         })
+///     }
+/// }
+///
+/// let doc = b"cmd: $(echo a: b # c)\nlist: [$(x, y), z]\n";
+/// let root = Loader::<RcRepr>::new(doc).grammar(Expr).parse().unwrap();
+/// assert_eq!(root[0], node!({"cmd" => "$(echo a: b # c)", "list" => node!(["$(x, y)", "z"])}));
+/// ```
+pub trait Grammar {
+    /// Match plain string, see [`Parser::string_plain`].
+    fn string_plain<'a>(
+        &self,
+        p: &mut Parser<'a>,
+        level: usize,
+        inner: bool,
+    ) -> PResult<Cow<'a, str>> {
+        p.string_plain(level, inner)
+    }
+
+    /// Match quoted string, see [`Parser::string_quoted`].
+    fn string_quoted<'a>(
+        &self,
+        p: &mut Parser<'a>,
+        sym: u8,
+        ignore: &[u8],
+    ) -> PResult<Cow<'a, str>> {
+        p.string_quoted(sym, ignore)
+    }
+
+    /// Match literal string, see [`Parser::string_literal`].
+    fn string_literal(&self, p: &mut Parser, level: usize) -> PResult<String> {
+        p.string_literal(level)
+    }
+
+    /// Match folded string, see [`Parser::string_folded`].
+    fn string_folded(&self, p: &mut Parser, level: usize) -> PResult<String> {
+        p.string_folded(level)
+    }
+}
+
+/// The default grammar of the [`Loader`], which uses the sub-parsers of
+/// [`Parser`] as is.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct YamlGrammar;
+
+impl Grammar for YamlGrammar {}
@@ -10,23 +10,63 @@
 //!
 //! # Errors
 //!
+//! The errors below are [`PError::Terminate`] unless noted, the names are
+//! listed as the `name` field. The position, the expected grammar elements and
+//! the found text are provided as well, so the diagnostics can be built
+//! without parsing the message. The enclosing grammar elements are listed
+//! as the `context` field, such as `doc > map > value > flow seq`.
+//!
+//! ```
+//! use yaml_peg::{parse, parser::PError, repr::RcRepr};
+//!
+//! let e = parse::<RcRepr>("{'a' b: c}").unwrap_err();
+//! let PError::Terminate { name, pos, expected, found, context, .. } = e else { unreachable!() };
+//! assert_eq!("flow map splitter", name);
+//! assert_eq!(5, pos);
+//! assert_eq!([":", ",", "}"], expected);
+//! assert_eq!(["doc", "flow map"], &*context);
+//! # #[cfg(not(feature = "compact-errors"))]
+//! assert_eq!("b: c}", found);
+//! ```
+//!
 //! ## Document
 //!
 //! **WRONG**: Invalid tag directive will be ignored.
 //!
+//! + encoding: The document is not encoded in UTF-8, see [`decode`].
 //! + document splitter: Error about the document splitter `---` / `...`.
 //! + checked version: Version directive `%YAML 1.2` is used again.
 //! + version: Version directive is wrong, must be `1.2`.
+//! + tag handle: The named tag handle is not declared, see
+//!   [`Loader::strict_tags`].
+//! + tag escape: The URI escape of the tag is invalid, such as `%2`.
+//!
+//! ## Limits
+//!
+//! These errors are [`PError::LimitExceeded`].
+//!
+//! + document count: The number of documents exceeds [`Loader::max_docs`].
+//! + nesting depth: The collections are nested deeper than
+//!   [`Loader::max_depth`].
+//! + node count: The number of nodes exceeds [`Loader::max_nodes`].
+//! + scalar bytes: The total length of the scalars exceeds
+//!   [`Loader::max_scalar_bytes`].
+//!
+//! ## Scalar
+//!
+//! + quoted string: The quoted string is not terminated.
+//! + escape sequence: The escape sequence in double-quoted string is invalid.
 //!
 //! ## Structure
 //!
 //! ### Flow Array
 //!
-//! + flow sequence item: Item in `[]` bracket is invalid.
+//! + flow sequence item: Item in `[]` bracket is invalid, or empty, see
+//!   [`Loader::lenient_flow`].
 //!
 //! ### Flow Map
 //!
-//! + flow map key: Key of map item in `{}` bracket is invalid.
+//! + flow map key: Key of map item in `{}` bracket is invalid, or empty.
 //! + flow map value: Value of map item in `{}` bracket is invalid.
 //! + flow map splitter: Splitter `:` of map item in `{}` bracket is invalid.
 //!
@@ -34,7 +74,7 @@
 //!
 //! + sequence item: Item behind `-` indicator is invalid.
 //! + sequence terminator: The end of sequence is invalid, may caused by the
-//! last item   (like wrapped string).
+//!   last item (like wrapped string).
 //!
 //! ### Map
 //!
@@ -43,21 +83,54 @@
 //! + map splitter: Splitter `:` of map item is invalid.
 //! + map terminator: The end of map is invalid, may caused by the last value
 //!   (like wrapped string).
+//!
+//! ### Alias
+//!
+//! + alias properties: The alias node has an anchor or a tag, see
+//!   [`Loader::alias_props`].
+//! + duplicated anchor definition: The anchor is defined again in the same
+//!   document, see [`Loader::anchor_policy`].
+#[allow(deprecated)]
+pub use self::anchors::anchor_resolve;
+pub(crate) use self::anchors::shared_nodes;
+#[cfg(feature = "rayon")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "rayon")))]
+pub use self::parallel::parse_parallel;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+pub use self::stats::{parse_stats, ParseStats};
 pub use self::{
-    base::{Parser, TakeOpt},
+    anchors::{anchor_resolve_checked, AnchorPolicy, Anchors},
+    base::{CommentToken, Parser, TakeOpt},
+    doc::{DocInfo, Schema, Version},
+    documents::Documents,
+    encoding::decode,
     error::{PError, PResult},
+    grammar::{Grammar, YamlGrammar},
 };
 use crate::{repr::Repr, *};
 use alloc::{
+    borrow::Cow,
+    boxed::Box,
     collections::BTreeMap,
+    format,
     string::{String, ToString},
     vec,
     vec::Vec,
 };
 use core::ops::{Deref, DerefMut};
 
+mod anchors;
 mod base;
+mod doc;
+mod documents;
+mod encoding;
 mod error;
+mod grammar;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "std")]
+mod stats;
 
 macro_rules! tag_prefix {
     () => {
@@ -67,8 +140,6 @@ macro_rules! tag_prefix {
 
 pub(crate) use tag_prefix;
 
-/// The type of anchor recorder in the [`Loader`].
-pub type Anchors<R> = BTreeMap<String, Node<R>>;
 /// The default prefix of the YAML sub tag.
 pub const DEFAULT_PREFIX: &str = tag_prefix!();
 
@@ -92,8 +163,10 @@ pub const DEFAULT_PREFIX: &str = tag_prefix!();
 /// + They will move the current cursor if matched.
 /// + Returned value:
 ///     + `Result<(), PError>` represents the sub-parser can be matched and
-/// mismatched.     + [`PError`] represents the sub-parser can be totally
-/// breaked when mismatched. + Use `?` to match a condition.
+///       mismatched.
+///     + [`PError`] represents the sub-parser can be totally breaked when
+///       mismatched.
+/// + Use `?` to match a condition.
 /// + Use [`Result::unwrap_or_default`] to match an optional condition.
 /// + Method [`Parser::forward`] is used to move on.
 /// + Method [`Parser::text`] is used to get the matched string.
@@ -102,17 +175,67 @@ pub struct Loader<'a, R: Repr> {
     /// Parser base.
     pub parser: Parser<'a>,
     cyclic_mode: bool,
+    anchor_policy: AnchorPolicy,
     anchors: Vec<Anchors<R>>,
+    docs: Vec<DocInfo>,
+    default_version: Version,
+    schema: Schema,
+    style: ScalarStyle,
+    intern: bool,
+    interned: [BTreeMap<SmallStr, R::Rc>; 3],
+    alias_props: bool,
+    lenient_flow: bool,
+    max_docs: usize,
+    truncate_docs: bool,
+    max_depth: usize,
+    depth: usize,
+    depth_peak: usize,
+    max_nodes: usize,
+    nodes: usize,
+    max_scalar_bytes: usize,
+    scalar_bytes: usize,
+    matchers: [Vec<ScalarMatcher<'a, R>>; 2],
+    grammar: Box<dyn Grammar + 'a>,
+    matched_tag: String,
+    alias_name: String,
+    aliases: Vec<(String, u64)>,
+    undefined: Vec<(String, u64)>,
     doc_ind: usize,
 }
 
+type ScalarMatcher<'a, R> = Box<dyn Fn(&str) -> Option<Node<R>> + 'a>;
+
 impl<'a, R: Repr> Loader<'a, R> {
     /// Create YAML loader includes a parser.
     pub fn new(doc: &'a [u8]) -> Self {
         Self {
             parser: Parser::new(doc),
             cyclic_mode: false,
+            anchor_policy: AnchorPolicy::default(),
             anchors: Vec::new(),
+            docs: Vec::new(),
+            default_version: Version::default(),
+            schema: Schema::default(),
+            style: ScalarStyle::Plain,
+            intern: false,
+            interned: Default::default(),
+            alias_props: false,
+            lenient_flow: false,
+            max_docs: usize::MAX,
+            truncate_docs: false,
+            max_depth: 64,
+            depth: 0,
+            depth_peak: 0,
+            max_nodes: usize::MAX,
+            nodes: 0,
+            max_scalar_bytes: usize::MAX,
+            scalar_bytes: 0,
+            matchers: Default::default(),
+            grammar: Box::new(YamlGrammar),
+            matched_tag: String::new(),
+            alias_name: String::new(),
+            aliases: Vec::new(),
+            undefined: Vec::new(),
             doc_ind: 0,
         }
     }
@@ -134,7 +257,7 @@ impl<'a, R: Repr> Loader<'a, R> {
 ///
 /// The `flow` parameter presents that the expression is in a **flow**
 /// expression.
-impl<R: Repr> Loader<'_, R> {
+impl<'a, R: Repr> Loader<'a, R> {
     /// Keep the anchor insertion.
     ///
     /// + Allow alias used before undefined anchor created. E.g., cyclic data.
@@ -146,6 +269,410 @@ impl<R: Repr> Loader<'_, R> {
         Self { cyclic_mode, ..self }
     }
 
+    /// Set the handling of the anchors which are defined again in the same
+    /// document, default to [`AnchorPolicy::Error`].
+    ///
+    /// ```
+    /// use yaml_peg::{node, parser::{AnchorPolicy, Loader}, repr::RcRepr};
+    ///
+    /// let doc = b"- &a 1\n- *a\n- &a 2\n- *a\n";
+    /// assert!(Loader::<RcRepr>::new(doc).parse().is_err());
+    /// let mut loader = Loader::<RcRepr>::new(doc).anchor_policy(AnchorPolicy::LastWins);
+    /// assert_eq!(loader.parse().unwrap(), [node!([1, 1, 2, 2])]);
+    /// assert_eq!(loader.get_anchors()[0].len(), 1);
+    /// let mut loader = Loader::<RcRepr>::new(doc).anchor_policy(AnchorPolicy::Rename);
+    /// assert_eq!(loader.parse().unwrap(), [node!([1, 1, 2, 2])]);
+    /// let anchors = loader.get_anchors().remove(0);
+    /// assert_eq!((&anchors["a"], &anchors["a-2"]), (&node!(2), &node!(1)));
+    /// ```
+    pub fn anchor_policy(self, anchor_policy: AnchorPolicy) -> Self {
+        Self { anchor_policy, ..self }
+    }
+
+    /// Set the source name of the document, then the error messages will be
+    /// indicated like [`indicated_msg_file`].
+    ///
+    /// ```
+    /// use yaml_peg::{parser::Loader, repr::RcRepr};
+    ///
+    /// let e = Loader::<RcRepr>::new(b"a: *b")
+    ///     .with_filename("my/file.yaml")
+    ///     .parse()
+    ///     .unwrap_err();
+    /// # #[cfg(not(feature = "compact-errors"))]
+    /// assert!(e.to_string().contains("my/file.yaml:1:6"));
+    /// ```
+    pub fn with_filename(self, filename: impl ToString) -> Self {
+        Self {
+            parser: self.parser.with_filename(filename),
+            ..self
+        }
+    }
+
+    /// Set the indentation of the levels which cannot be detected, see
+    /// [`Parser::with_indent_hint`].
+    ///
+    /// ```
+    /// use yaml_peg::{node, parser::Loader, repr::RcRepr};
+    ///
+    /// let doc = b"-   a: 1\n    b: 2\n";
+    /// assert!(Loader::<RcRepr>::new(doc).parse().is_err());
+    /// let root = Loader::<RcRepr>::new(doc).with_indent_hint(4).parse().unwrap();
+    /// assert_eq!(root, [node!([node!({"a" => 1, "b" => 2})])]);
+    /// ```
+    pub fn with_indent_hint(self, indent: usize) -> Self {
+        Self {
+            parser: self.parser.with_indent_hint(indent),
+            ..self
+        }
+    }
+
+    /// Raise "inconsistent indentation" error for the mixed indentation, see
+    /// [`Parser::strict_indent`].
+    ///
+    /// ```
+    /// use yaml_peg::{parser::Loader, repr::RcRepr};
+    ///
+    /// let doc = b"a:\n  b: 1\nc:\n    d: 2\n";
+    /// assert!(Loader::<RcRepr>::new(doc).parse().is_ok());
+    /// let e = Loader::<RcRepr>::new(doc).strict_indent(true).parse().unwrap_err();
+    /// assert!(e.to_string().contains("inconsistent indentation"));
+    /// ```
+    pub fn strict_indent(self, strict_indent: bool) -> Self {
+        Self {
+            parser: self.parser.strict_indent(strict_indent),
+            ..self
+        }
+    }
+
+    /// Raise "tag handle" error for the undeclared tag handles, see
+    /// [`Parser::strict_tags`].
+    ///
+    /// ```
+    /// use yaml_peg::{parser::Loader, repr::RcRepr};
+    ///
+    /// let doc = b"!e!foo 1\n";
+    /// let root = Loader::<RcRepr>::new(doc).parse().unwrap();
+    /// assert_eq!(root[0].tag(), "!e!foo");
+    /// let e = Loader::<RcRepr>::new(doc).strict_tags(true).parse().unwrap_err();
+    /// assert!(e.to_string().contains("tag handle"));
+    /// ```
+    pub fn strict_tags(self, strict_tags: bool) -> Self {
+        Self {
+            parser: self.parser.strict_tags(strict_tags),
+            ..self
+        }
+    }
+
+    /// Share the data holders of the same scalars (integers, floats and
+    /// strings), which reduces the allocations of the large documents with
+    /// many repeated values, e.g., the keys of the mapping records.
+    ///
+    /// The interned scalars are the same node for
+    /// [`DumpOptions::auto_anchor`](crate::dumper::DumpOptions::auto_anchor).
+    ///
+    /// ```
+    /// use yaml_peg::{parser::Loader, repr::RcRepr, Ind};
+    /// use std::rc::Rc;
+    ///
+    /// let doc = b"- kind: user\n- kind: user\n";
+    /// let root = Loader::<RcRepr>::new(doc).intern_scalars(true).parse().unwrap();
+    /// let (v1, v2) = (&root[0][Ind(0)]["kind"], &root[0][Ind(1)]["kind"]);
+    /// assert!(Rc::ptr_eq(v1.rc_ref(), v2.rc_ref()));
+    /// ```
+    pub fn intern_scalars(self, intern: bool) -> Self {
+        Self { intern, ..self }
+    }
+
+    /// Allow the properties (anchor and tag) on the alias nodes, which are
+    /// invalid in YAML spec. Default to false, raise "alias properties"
+    /// error.
+    ///
+    /// If enabled, the tag is attached to a new node that shares the data of
+    /// the resolved node, and the anchor refers to the new node. The resolved
+    /// node is not changed. The alias without a tag inherits the tag of the
+    /// resolved node, see [`Node::anchor_name`].
+    ///
+    /// ```
+    /// use yaml_peg::{node, parser::Loader, repr::RcRepr, Ind};
+    ///
+    /// let doc = b"- &a 1\n- !t *a\n- &b *a\n- *b\n";
+    /// assert!(Loader::<RcRepr>::new(doc).parse().is_err());
+    /// let root = Loader::<RcRepr>::new(doc).alias_props(true).parse().unwrap();
+    /// assert_eq!(root[0], node!([1, 1, 1, 1]));
+    /// assert_eq!(root[0][Ind(1)].tag(), "t");
+    /// assert_eq!(root[0][Ind(0)].tag(), "tag:yaml.org,2002:int");
+    /// ```
+    pub fn alias_props(self, alias_props: bool) -> Self {
+        Self { alias_props, ..self }
+    }
+
+    /// Skip the empty entries in the flow collections, such as the repeated
+    /// trailing commas of the hand-written documents, which are invalid in
+    /// YAML spec. Default to false, raise "flow sequence item" or "flow map
+    /// key" error.
+    ///
+    /// A single trailing comma and the comments between the lines are always
+    /// allowed.
+    ///
+    /// ```
+    /// use yaml_peg::{node, parser::Loader, repr::RcRepr};
+    ///
+    /// let doc = b"[a, b, # c\n  ,\n]";
+    /// assert!(Loader::<RcRepr>::new(doc).parse().is_err());
+    /// let root = Loader::<RcRepr>::new(doc).lenient_flow(true).parse().unwrap();
+    /// assert_eq!(root, [node!(["a", "b"])]);
+    /// ```
+    pub fn lenient_flow(self, lenient_flow: bool) -> Self {
+        Self { lenient_flow, ..self }
+    }
+
+    /// Set the maximum number of documents in the stream, raise "document
+    /// count" error if there are more documents. The first document is always
+    /// parsed.
+    ///
+    /// ```
+    /// use yaml_peg::{node, parser::Loader, repr::RcRepr};
+    ///
+    /// let doc = b"a\n---\nb\n---\nc\n";
+    /// let e = Loader::<RcRepr>::new(doc).max_docs(2).parse().unwrap_err();
+    /// assert!(e.to_string().contains("document count"));
+    /// let root = Loader::<RcRepr>::new(doc).max_docs(2).truncate_docs(true).parse().unwrap();
+    /// assert_eq!(root, vec![node!("a"), node!("b")]);
+    /// ```
+    pub fn max_docs(self, max_docs: usize) -> Self {
+        Self { max_docs, ..self }
+    }
+
+    /// Return the first documents instead of the error when the number of
+    /// documents exceeds [`Loader::max_docs`]. The rest of the stream is not
+    /// parsed.
+    pub fn truncate_docs(self, truncate_docs: bool) -> Self {
+        Self { truncate_docs, ..self }
+    }
+
+    /// Set the maximum nesting depth of the collections, raise "nesting depth"
+    /// error if the collections are nested deeper. This prevents the stack
+    /// overflow caused by the malicious documents. Defaults to 64, which is
+    /// safe for the 2 MiB stack of the spawned threads in the debug build.
+    ///
+    /// ```
+    /// use yaml_peg::{parser::Loader, repr::RcRepr};
+    ///
+    /// let doc = b"[[[{a: [b]}]]]";
+    /// let e = Loader::<RcRepr>::new(doc).max_depth(4).parse().unwrap_err();
+    /// assert!(e.to_string().contains("nesting depth"));
+    /// assert!(Loader::<RcRepr>::new(doc).max_depth(5).parse().is_ok());
+    /// ```
+    pub fn max_depth(self, max_depth: usize) -> Self {
+        Self { max_depth, ..self }
+    }
+
+    /// Set the maximum number of nodes in the stream, raise "node count" error
+    /// if there are more nodes. The keys and the aliases are counted as well.
+    ///
+    /// ```
+    /// use yaml_peg::{parser::{Loader, PError}, repr::RcRepr};
+    ///
+    /// let doc = b"[a, b, {c: d}]";
+    /// let e = Loader::<RcRepr>::new(doc).max_nodes(5).parse().unwrap_err();
+    /// assert!(matches!(e, PError::LimitExceeded { name: "node count", pos: 14, .. }));
+    /// assert!(Loader::<RcRepr>::new(doc).max_nodes(6).parse().is_ok());
+    /// ```
+    pub fn max_nodes(self, max_nodes: usize) -> Self {
+        Self { max_nodes, ..self }
+    }
+
+    /// Set the maximum total length in bytes of the scalars in the stream,
+    /// raise "scalar bytes" error if the scalars are longer.
+    ///
+    /// ```
+    /// use yaml_peg::{parser::{Loader, PError}, repr::RcRepr};
+    ///
+    /// let doc = b"- abc\n- 'def'\n";
+    /// let e = Loader::<RcRepr>::new(doc).max_scalar_bytes(5).parse().unwrap_err();
+    /// assert!(matches!(e, PError::LimitExceeded { name: "scalar bytes", .. }));
+    /// assert!(Loader::<RcRepr>::new(doc).max_scalar_bytes(6).parse().is_ok());
+    /// ```
+    pub fn max_scalar_bytes(self, max_scalar_bytes: usize) -> Self {
+        Self { max_scalar_bytes, ..self }
+    }
+
+    /// Set the YAML version of the documents which have no `%YAML` directive.
+    ///
+    /// ```
+    /// use yaml_peg::{parser::{Loader, Version}, repr::RcRepr};
+    ///
+    /// let doc = "\
+    /// a: 1
+    /// ...
+    /// %YAML 1.2
+    /// ---
+    /// b: 2
+    /// ";
+    /// let mut loader = Loader::<RcRepr>::new(doc.as_bytes()).default_version(Version::V1_1);
+    /// loader.parse().unwrap();
+    /// let versions = loader.doc_info().iter().map(|info| info.version()).collect::<Vec<_>>();
+    /// assert_eq!(versions, [Version::V1_1, Version::V1_2]);
+    /// ```
+    pub fn default_version(self, default_version: Version) -> Self {
+        Self { default_version, ..self }
+    }
+
+    /// Set the schema to resolve the types of the plain scalars, default to
+    /// the core schema.
+    ///
+    /// ```
+    /// use yaml_peg::{node, parser::{Loader, Schema}, repr::RcRepr};
+    ///
+    /// let doc = b"[yes, ~, 0x10, 1e3, Null]";
+    /// let root = Loader::<RcRepr>::new(doc).schema(Schema::Yaml11).parse().unwrap();
+    /// assert_eq!(root, [node!([true, (), 16, 1000., ()])]);
+    /// let root = Loader::<RcRepr>::new(doc).parse().unwrap();
+    /// assert_eq!(root, [node!(["yes", (), 16, 1000., ()])]);
+    /// let root = Loader::<RcRepr>::new(doc).schema(Schema::Json).parse().unwrap();
+    /// assert_eq!(root, [node!(["yes", "~", "0x10", 1000., "Null"])]);
+    /// ```
+    pub fn schema(self, schema: Schema) -> Self {
+        Self { schema, ..self }
+    }
+
+    /// Register a custom matcher for the plain scalars, which runs before the
+    /// built-in types are resolved. The matcher returns a node if the text is
+    /// recognized, its data and tag are used. The tag in the document
+    /// overrides the tag of the matcher, but the data are still resolved by
+    /// the matcher like the built-in types.
+    ///
+    /// The matchers are tried in the registration order.
+    ///
+    /// ```
+    /// use yaml_peg::{node, parser::Loader, repr::RcRepr, Ind, Node};
+    ///
+    /// let doc = b"[0x1F, 0x20, 1.2.3, !ref 1.2.3]";
+    /// let root = Loader::<RcRepr>::new(doc)
+    ///     .scalar_matcher(|s| s.strip_prefix("0x1").map(|id| Node::new(id, 0, "id")))
+    ///     .scalar_matcher(|s| {
+    ///         let ok = s.split('.').count() == 3 && s.split('.').all(|n| n.parse::<u32>().is_ok());
+    ///         ok.then(|| Node::new(s, 0, "ver"))
+    ///     })
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(root[0], node!(["F", 32, "1.2.3", "1.2.3"]));
+    /// assert_eq!(root[0][Ind(0)].tag(), "id");
+    /// assert_eq!(root[0][Ind(2)].tag(), "ver");
+    /// assert_eq!(root[0][Ind(3)].tag(), "ref");
+    /// ```
+    pub fn scalar_matcher<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> Option<Node<R>> + 'a,
+    {
+        self.matchers[0].push(Box::new(f));
+        self
+    }
+
+    /// Register a custom matcher for the plain scalars like
+    /// [`Loader::scalar_matcher`], but it only runs if the text is resolved
+    /// as a string by the built-in types.
+    ///
+    /// ```
+    /// use yaml_peg::{node, parser::Loader, repr::RcRepr, Ind, Node};
+    ///
+    /// let doc = b"[/a+b/, 12, /0/]";
+    /// let root = Loader::<RcRepr>::new(doc)
+    ///     .scalar_fallback(|s| {
+    ///         let re = s.strip_prefix('/')?.strip_suffix('/')?;
+    ///         Some(Node::new(re, 0, "regex"))
+    ///     })
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(root[0], node!(["a+b", 12, "0"]));
+    /// assert_eq!(root[0][Ind(0)].tag(), "regex");
+    /// assert_eq!(root[0][Ind(1)].tag(), "tag:yaml.org,2002:int");
+    /// ```
+    pub fn scalar_fallback<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> Option<Node<R>> + 'a,
+    {
+        self.matchers[1].push(Box::new(f));
+        self
+    }
+
+    /// Replace the scalar sub-parsers with the custom grammar, default to
+    /// [`YamlGrammar`]. See [`Grammar`] for the example.
+    pub fn grammar(self, grammar: impl Grammar + 'a) -> Self {
+        Self { grammar: Box::new(grammar), ..self }
+    }
+
+    /// The information of the parsed documents.
+    pub fn doc_info(&self) -> &[DocInfo] {
+        &self.docs
+    }
+
+    /// The aliases which have no anchor definition in their documents, with
+    /// the names and the positions.
+    ///
+    /// Only the cyclic mode allows such aliases, otherwise they are errors.
+    /// The aliases are checked at the end of each document, so the anchors
+    /// defined after the alias are valid.
+    ///
+    /// ```
+    /// use yaml_peg::{parser::Loader, repr::RcRepr};
+    ///
+    /// let doc = b"a: *defualts\nb: &defaults {x: *b}\nc: *defaults\n---\n*defaults\n";
+    /// let mut loader = Loader::<RcRepr>::new(doc).cyclic_mode(true);
+    /// loader.parse().unwrap();
+    /// let undefined = loader.undefined_aliases();
+    /// assert_eq!(undefined, [("defualts".to_string(), 3), ("b".to_string(), 30), ("defaults".to_string(), 51)]);
+    /// ```
+    pub fn undefined_aliases(&self) -> &[(String, u64)] {
+        &self.undefined
+    }
+
+    /// Find the index of the parsed document which contains the position,
+    /// e.g., the position of a node or an error.
+    ///
+    /// ```
+    /// use yaml_peg::{parser::Loader, repr::RcRepr};
+    ///
+    /// let doc = b"a: 1\n---\nb: 2\n---\nc: 3\n";
+    /// let mut loader = Loader::<RcRepr>::new(doc);
+    /// let root = loader.parse().unwrap();
+    /// let starts = loader.doc_info().iter().map(|info| info.pos()).collect::<Vec<_>>();
+    /// assert_eq!(starts, [0, 5, 14]);
+    /// assert_eq!(loader.doc_index(root[2]["c"].pos()), Some(2));
+    /// ```
+    pub fn doc_index(&self, pos: u64) -> Option<usize> {
+        self.docs
+            .partition_point(|info| info.pos <= pos)
+            .checked_sub(1)
+    }
+
+    /// Match the directives of the next document without parsing its body,
+    /// the position is not moved.
+    ///
+    /// This is useful to detect the YAML version and the tag handles of the
+    /// document before parsing it. The default version is returned if there
+    /// is no `%YAML` directive.
+    ///
+    /// ```
+    /// use yaml_peg::{parser::{Loader, Version}, repr::RcRepr};
+    ///
+    /// let doc = b"%YAML 1.1\n%TAG !e! tag:example.com,2000:\n--- !e!foo\na: 1\n";
+    /// let mut loader = Loader::<RcRepr>::new(doc);
+    /// let info = loader.parse_directives().unwrap();
+    /// assert_eq!(info.version(), Version::V1_1);
+    /// assert_eq!(info.tags()["e"], "tag:example.com,2000:");
+    /// let root = loader.parse().unwrap();
+    /// assert_eq!(root[0].tag(), "tag:example.com,2000:foo");
+    /// assert_eq!(loader.doc_info()[0], info);
+    /// ```
+    pub fn parse_directives(&mut self) -> PResult<DocInfo> {
+        self.forward();
+        let ret = self.directives();
+        self.backward();
+        ret.map(|_| self.docs.pop().unwrap())
+    }
+
     /// Consume this loader and return the recorded anchors.
     pub fn get_anchors(self) -> Vec<Anchors<R>> {
         self.anchors
@@ -153,6 +680,60 @@ impl<R: Repr> Loader<'_, R> {
 
     /// YAML entry point, return entire doc if exist.
     pub fn parse(&mut self) -> PResult<Vec<Node<R>>> {
+        self.parse_with(Self::doc)
+    }
+
+    /// Return an iterator that parses one document per iteration, so the
+    /// stream can be processed one by one and stopped early. See
+    /// [`Documents`] for more information.
+    pub fn documents(&mut self) -> Documents<'_, 'a, R> {
+        Documents::new(self)
+    }
+
+    /// Match the directives and the documents, the documents are handled by
+    /// the given sub-parser.
+    pub(crate) fn parse_with<T, E, F>(&mut self, mut f: F) -> Result<Vec<T>, E>
+    where
+        E: From<PError>,
+        F: FnMut(&mut Self) -> Result<T, E>,
+    {
+        let mut v = vec![];
+        while self.next_doc(v.len())? {
+            v.push(f(self)?);
+        }
+        Ok(v)
+    }
+
+    /// Match the directives and the splitter of the next document, return
+    /// false if the stream is ended. `n` is the number of parsed documents.
+    pub(crate) fn next_doc(&mut self, n: usize) -> PResult<bool> {
+        if n == 0 {
+            self.directives()?;
+            self.gap(true).unwrap_or_default();
+            self.sym_seq(b"---").unwrap_or_default();
+            return Ok(true);
+        }
+        self.gap(true).unwrap_or_default();
+        if self.food().is_empty() {
+            return Ok(false);
+        }
+        if n >= self.max_docs {
+            if self.truncate_docs {
+                return Ok(false);
+            }
+            return self.limit_err("document count");
+        }
+        self.directives()?;
+        if self.sym_seq(b"---").is_err() {
+            return self.err_expected("document splitter", &["---"]);
+        }
+        Ok(true)
+    }
+
+    /// Match the directives of a document and record its information.
+    fn directives(&mut self) -> PResult<()> {
+        let pos = self.indicator();
+        self.bom()?;
         loop {
             match self.context(Parser::directive) {
                 Ok(()) => (),
@@ -160,33 +741,40 @@ impl<R: Repr> Loader<'_, R> {
                 Err(e) => return Err(e),
             }
         }
-        self.gap(true).unwrap_or_default();
-        self.sym_seq(b"---").unwrap_or_default();
-        let mut v = vec![self.doc()?];
-        loop {
-            self.gap(true).unwrap_or_default();
-            if self.food().is_empty() {
-                break;
-            }
-            if self.sym_seq(b"---").is_err() {
-                return self.err("document splitter");
-            }
-            v.push(self.doc()?);
-        }
-        Ok(v)
+        let version = self.version.take().unwrap_or(self.default_version);
+        let tags = core::mem::take(&mut self.tag_directives);
+        self.docs.push(DocInfo { pos, version, tags });
+        Ok(())
     }
 
     /// Match one doc block.
     pub fn doc(&mut self) -> PResult<Node<R>> {
+        self.doc_start();
+        self.scopes.push("doc");
+        let ret = self.scalar(0, false, false);
+        self.scopes.pop();
+        let ret = ret?;
+        self.doc_finish();
+        Ok(ret)
+    }
+
+    pub(crate) fn doc_start(&mut self) {
         self.context(|p| p.bound().unwrap_or_default());
+        // The root node can be started at the next line
+        self.gap(true).unwrap_or_default();
         self.forward();
         self.anchors.push(Anchors::new());
-        let ret = self.scalar(0, false, false)?;
+    }
+
+    pub(crate) fn doc_finish(&mut self) {
         self.gap(true).unwrap_or_default();
         self.sym_seq(b"...").unwrap_or_default();
         self.forward();
+        let anchors = &self.anchors[self.doc_ind];
+        let aliases = self.aliases.drain(..);
+        self.undefined
+            .extend(aliases.filter(|(s, _)| !anchors.contains_key(s)));
         self.doc_ind += 1;
-        Ok(ret)
     }
 
     /// Match doc end.
@@ -206,17 +794,21 @@ impl<R: Repr> Loader<'_, R> {
 
     /// Match scalar.
     pub fn scalar(&mut self, level: usize, map: bool, flow: bool) -> PResult<Node<R>> {
-        self.scalar_node(|p| {
-            if let Ok(s) = p.string_literal(level) {
-                Ok(R::new_rc(Yaml::Str(s)))
-            } else if let Ok(s) = p.string_folded(level) {
-                Ok(R::new_rc(Yaml::Str(s)))
-            } else {
-                p.seq(level, map)
-                    .or_else(|e| e.or(|| p.map(level, map, flow)))
-                    .or_else(|e| e.or(|| p.scalar_term(level, flow)))
-            }
-        })
+        self.scalar_node(|p| p.scalar_body(level, map, flow))
+    }
+
+    pub(crate) fn scalar_body(&mut self, level: usize, map: bool, flow: bool) -> PResult<R::Rc> {
+        if let Ok(s) = self.grammar.string_literal(&mut self.parser, level) {
+            self.style = ScalarStyle::Literal;
+            self.new_scalar(Yaml::Str(s))
+        } else if let Ok(s) = self.grammar.string_folded(&mut self.parser, level) {
+            self.style = ScalarStyle::Folded;
+            self.new_scalar(Yaml::Str(s))
+        } else {
+            self.seq(level, map)
+                .or_else(|e| e.or(|| self.map(level, map, flow)))
+                .or_else(|e| e.or(|| self.scalar_term(level, flow)))
+        }
     }
 
     /// Match flow scalar.
@@ -228,12 +820,97 @@ impl<R: Repr> Loader<'_, R> {
     where
         F: FnOnce(&mut Self) -> PResult<R::Rc>,
     {
+        let (anchor, tag) = self.props()?;
+        let key_props = self.is_key_props(&anchor, &tag);
+        let pos = self.indicator();
+        self.style = ScalarStyle::Plain;
+        let yaml = f(self)?;
+        let style = core::mem::take(&mut self.style);
+        let tag = self.scalar_tag(tag);
+        let alias = core::mem::take(&mut self.alias_name);
+        self.nodes += 1;
+        if self.nodes > self.max_nodes {
+            return self.limit_err("node count");
+        }
+        self.forward();
+        let mut node = Node::new_repr(yaml, pos, "");
+        node.set_style(style);
+        node.set_anchor_name(alias);
+        self.props_node(anchor, tag, key_props, node)
+    }
+
+    /// Match a nested collection, raise "nesting depth" error if the depth
+    /// exceeds [`Loader::max_depth`].
+    ///
+    /// The name of the collection is recorded by the errors, see
+    /// [`PError::Terminate`].
+    fn nest<T, F>(&mut self, name: &'static str, f: F) -> PResult<T>
+    where
+        F: FnOnce(&mut Self) -> PResult<T>,
+    {
+        if self.depth >= self.max_depth {
+            return self.limit_err("nesting depth");
+        }
+        self.depth += 1;
+        self.depth_peak = self.depth_peak.max(self.depth);
+        self.scopes.push(name);
+        let ret = f(self);
+        self.scopes.pop();
+        self.depth -= 1;
+        ret
+    }
+
+    /// Return true if the properties are followed by a block map in the same
+    /// line, then they belong to the first key.
+    pub(crate) fn is_key_props(&mut self, anchor: &str, tag: &str) -> bool {
+        if anchor.is_empty() && tag.is_empty() {
+            return false;
+        }
+        let inline = self.gap(true).is_err() && !matches!(self.food().first(), Some(b'{' | b'?'));
+        self.backward();
+        inline
+    }
+
+    /// Apply the properties to the node, or its first key if `key_props` is
+    /// true and the node is a map.
+    pub(crate) fn props_node(
+        &mut self,
+        anchor: String,
+        tag: String,
+        key_props: bool,
+        node: Node<R>,
+    ) -> PResult<Node<R>> {
+        let (yaml, pos) = (node.clone_yaml(), node.pos());
+        match &*yaml {
+            // The map resolved from the alias takes the properties itself
+            Yaml::Map(m) if key_props && node.anchor_name().is_none() => {
+                let mut m = m.clone().into_iter().collect::<Vec<_>>();
+                // The key has no properties since they are taken by the map
+                let k = &mut m[0].0;
+                let mut key = Node::new_repr(k.clone_yaml(), k.pos(), tag);
+                key.set_style(k.style());
+                key.set_anchor_name(k.anchor_name().unwrap_or_default());
+                *k = self.insert_anchor(anchor, key)?;
+                Ok(Node::new(m.into_iter().collect::<Yaml<R>>(), pos, ""))
+            }
+            _ => {
+                let style = node.style();
+                let mut n = Node::new_repr(yaml, pos, tag);
+                n.set_style(style);
+                n.set_anchor_name(node.anchor_name().unwrap_or_default());
+                self.insert_anchor(anchor, n)
+            }
+        }
+    }
+
+    /// Match the node properties, the anchor and the tag.
+    pub(crate) fn props(&mut self) -> PResult<(String, String)> {
         let mut anchor = self.anchor().unwrap_or_default();
         if !anchor.is_empty() {
             self.bound()?;
         }
         self.forward();
-        let tag = self.tag().unwrap_or_default();
+        let tag = self.tag().or_else(|e| e.or(|| Ok(String::new())))?;
         if !tag.is_empty() {
             self.bound()?;
         }
@@ -248,234 +925,531 @@ impl<R: Repr> Loader<'_, R> {
             }
         }
         self.forward();
-        let pos = self.indicator();
-        let yaml = f(self)?;
-        self.forward();
-        let node = Node::new_repr(yaml, pos, &tag);
-        if !anchor.is_empty()
-            && self.anchors[self.doc_ind]
-                .insert(anchor, node.clone())
-                .is_some()
+        if !self.alias_props
+            && (!anchor.is_empty() || !tag.is_empty())
+            && self.food().first() == Some(&b'*')
         {
-            self.err("duplicated anchor definition")
+            return self.err("alias properties");
+        }
+        Ok((anchor, tag))
+    }
+
+    pub(crate) fn insert_anchor(&mut self, anchor: String, mut node: Node<R>) -> PResult<Node<R>> {
+        if anchor.is_empty() {
+            return Ok(node);
+        }
+        node.set_anchor(&anchor);
+        let anchors = &mut self.anchors[self.doc_ind];
+        let Some(mut old) = anchors.insert(anchor.clone(), node.clone()) else {
+            return Ok(node);
+        };
+        match self.anchor_policy {
+            AnchorPolicy::Error => self.err("duplicated anchor definition"),
+            AnchorPolicy::LastWins => Ok(node),
+            AnchorPolicy::Rename => {
+                let name = (2..)
+                    .map(|i| format!("{anchor}-{i}"))
+                    .find(|name| !anchors.contains_key(name))
+                    .unwrap();
+                old.set_anchor(&name);
+                anchors.insert(name, old);
+                Ok(node)
+            }
+        }
+    }
+
+    fn add_scalar_bytes(&mut self, len: usize) -> PResult<()> {
+        self.scalar_bytes = self.scalar_bytes.saturating_add(len);
+        if self.scalar_bytes > self.max_scalar_bytes {
+            self.limit_err("scalar bytes")
         } else {
-            Ok(node)
+            Ok(())
         }
     }
 
+    /// Create the data holder of the scalar, reuse the interned one if
+    /// enabled. See [`Loader::intern_scalars`].
+    fn new_scalar(&mut self, yaml: Yaml<R>) -> PResult<R::Rc> {
+        let (i, s) = match &yaml {
+            Yaml::Int(s) => (0, s.as_str()),
+            Yaml::Float(s) => (1, s.as_str()),
+            Yaml::Str(s) => (2, s.as_str()),
+            _ => return Ok(R::new_rc(yaml)),
+        };
+        self.add_scalar_bytes(s.len())?;
+        if !self.intern {
+            return Ok(R::new_rc(yaml));
+        }
+        if let Some(rc) = self.interned[i].get(s) {
+            return Ok(rc.clone());
+        }
+        let s = SmallStr::from(s);
+        let rc = R::new_rc(yaml);
+        self.interned[i].insert(s, rc.clone());
+        Ok(rc)
+    }
+
     /// Match flow scalar terminal.
     pub fn scalar_term(&mut self, level: usize, flow: bool) -> PResult<R::Rc> {
-        let yaml = if let Ok(s) = self.float() {
-            R::new_rc(Yaml::Float(s))
-        } else if let Ok(s) = self.sci_float() {
-            R::new_rc(Yaml::Float(s))
-        } else if let Ok(s) = self.int() {
-            R::new_rc(Yaml::Int(s))
-        } else if let Ok(s) = self.anchor_use() {
+        if flow {
+            // The newlines are the white spaces in flow context, e.g., behind
+            // the properties
+            self.gap_flow()?;
+            self.forward();
+        }
+        match self.scalar_leaf(level, flow)? {
+            Some(yaml) => Ok(yaml),
+            None => self
+                .seq_flow(level)
+                .or_else(|e| e.or(|| self.map_flow(level)))
+                .or_else(|e| e.or(|| Ok(R::new_rc(Yaml::Null)))),
+        }
+    }
+
+    /// Match the alias or the scalar of the flow scalar terminal.
+    ///
+    /// This is separated from [`Loader::scalar_term`] to keep the stack frames
+    /// of the nested flow collections small.
+    fn scalar_leaf(&mut self, level: usize, flow: bool) -> PResult<Option<R::Rc>> {
+        let yaml = if let Ok(s) = self.anchor_use() {
             if self.cyclic_mode {
+                let pos = self.indicator() - s.len() as u64 - 1;
+                self.aliases.push((s.clone(), pos));
                 R::new_rc(Yaml::Alias(s))
             } else if let Some(node) = self.anchors[self.doc_ind].get(&s) {
+                // The tag of the alias overrides the anchored node
+                self.matched_tag = node.raw_tag().to_string();
+                self.alias_name = s;
                 node.clone_yaml()
             } else {
                 return self.err("anchor referenced before definition");
             }
-        } else if let Ok(s) = self.string_quoted(b'\'', b"''") {
-            R::new_rc(Yaml::Str(s))
-        } else if let Ok(s) = self.string_quoted(b'"', b"\\\"") {
-            R::new_rc(Yaml::Str(Parser::escape(&s)))
-        } else if let Ok(s) = self.string_plain(level, flow) {
-            R::new_rc(match s.as_str() {
-                "~" | "null" | "Null" | "NULL" => Yaml::Null,
-                "true" | "True" | "TRUE" => Yaml::Bool(true),
-                "false" | "False" | "FALSE" => Yaml::Bool(false),
-                ".nan" | ".NaN" | ".NAN" => Yaml::Float("NaN".to_string()),
-                ".inf" | ".Inf" | ".INF" => Yaml::Float("inf".to_string()),
-                "-.inf" | "-.Inf" | "-.INF" => Yaml::Float("-inf".to_string()),
-                _ => Yaml::Str(s),
-            })
+        } else if let Some(s) = self.quoted(b'\'', b"''")? {
+            self.style = ScalarStyle::SingleQuoted;
+            self.new_scalar(Yaml::Str(s.into()))?
+        } else if let Some(s) = self.quoted(b'"', b"\\\"")? {
+            self.style = ScalarStyle::DoubleQuoted;
+            self.new_scalar(Yaml::Str(s.into()))?
+        } else if let Ok(s) = self.grammar.string_plain(&mut self.parser, level, flow) {
+            self.plain(s)?
         } else {
-            self.seq_flow(level)
-                .or_else(|e| e.or(|| self.map_flow(level)))
-                .or_else(|e| e.or(|| Ok(R::new_rc(Yaml::Null))))?
+            return Ok(None);
         };
-        Ok(yaml)
+        Ok(Some(yaml))
+    }
+
+    /// Resolve the plain scalar by the custom matchers and the built-in types.
+    fn plain(&mut self, s: Cow<str>) -> PResult<R::Rc> {
+        let matched = |m: &[ScalarMatcher<R>]| m.iter().find_map(|f| f(&s));
+        let node = if let Some(node) = matched(&self.matchers[0]) {
+            node
+        } else if self.matchers[1].is_empty() {
+            return self.new_scalar(Self::resolve_plain(s, self.schema));
+        } else {
+            match Self::resolve_plain(s.as_ref().into(), self.schema) {
+                yaml @ Yaml::Str(_) => match matched(&self.matchers[1]) {
+                    Some(node) => node,
+                    None => return self.new_scalar(yaml),
+                },
+                yaml => return self.new_scalar(yaml),
+            }
+        };
+        self.add_scalar_bytes(s.len())?;
+        self.matched_tag = node.raw_tag().to_string();
+        Ok(node.clone_yaml())
+    }
+
+    /// Return the tag of the node properties, or the tag of the custom
+    /// matcher if there is no tag.
+    pub(crate) fn scalar_tag(&mut self, tag: String) -> String {
+        let matched = core::mem::take(&mut self.matched_tag);
+        if tag.is_empty() {
+            matched
+        } else {
+            tag
+        }
+    }
+
+    /// Resolve the type of the plain string by the whole text.
+    pub(crate) fn resolve_plain(s: Cow<str>, schema: Schema) -> Yaml<R> {
+        fn num<'a>(
+            s: &'a str,
+            f: impl FnOnce(&mut Parser<'a>) -> PResult<String>,
+        ) -> Option<String> {
+            let mut p = Parser::new(s.as_bytes());
+            f(&mut p).ok().filter(|_| p.food().is_empty())
+        }
+
+        if schema == Schema::Json {
+            return match &*s {
+                "null" => Yaml::Null,
+                "true" => Yaml::Bool(true),
+                "false" => Yaml::Bool(false),
+                _ => match num(&s, Parser::json_number) {
+                    Some(n) if n.contains(['.', 'e', 'E']) => Yaml::Float(n),
+                    Some(n) => Yaml::Int(n),
+                    None => Yaml::Str(s.into()),
+                },
+            };
+        }
+        match &*s {
+            "~" | "null" | "Null" | "NULL" => Yaml::Null,
+            "true" | "True" | "TRUE" => Yaml::Bool(true),
+            "false" | "False" | "FALSE" => Yaml::Bool(false),
+            "y" | "Y" | "yes" | "Yes" | "YES" | "on" | "On" | "ON" if schema == Schema::Yaml11 => {
+                Yaml::Bool(true)
+            }
+            "n" | "N" | "no" | "No" | "NO" | "off" | "Off" | "OFF" if schema == Schema::Yaml11 => {
+                Yaml::Bool(false)
+            }
+            ".nan" | ".NaN" | ".NAN" => Yaml::Float("NaN".into()),
+            ".inf" | ".Inf" | ".INF" => Yaml::Float("inf".into()),
+            "-.inf" | "-.Inf" | "-.INF" => Yaml::Float("-inf".into()),
+            _ => {
+                if let Some(n) = num(&s, Parser::float).or_else(|| num(&s, Parser::sci_float)) {
+                    Yaml::Float(n)
+                } else if let Some(n) = num(&s, Parser::int) {
+                    Yaml::Int(n)
+                } else {
+                    Yaml::Str(s.into())
+                }
+            }
+        }
+    }
+
+    fn quoted(&mut self, sym: u8, ignore: &[u8]) -> PResult<Option<Cow<'a, str>>> {
+        self.grammar
+            .string_quoted(&mut self.parser, sym, ignore)
+            .map(Some)
+            .or_else(|e| e.or(|| Ok(None)))
     }
 
     /// Match flow sequence.
+    ///
+    /// A single pair `key: value` in the sequence is a map.
     pub fn seq_flow(&mut self, level: usize) -> PResult<R::Rc> {
         self.sym(b'[')?;
-        let mut v = vec![];
-        loop {
-            self.inv(TakeOpt::More(0))?;
-            self.forward();
-            if self.sym(b']').is_ok() {
-                break;
-            }
-            self.forward();
-            let n = self
-                .scalar(level + 1, false, true)
-                .or_else(|e| e.or(|| self.err("flow sequence item")))?;
-            v.push(n);
-            self.inv(TakeOpt::More(0))?;
-            if self.sym(b',').is_err() {
-                self.inv(TakeOpt::More(0))?;
-                self.sym(b']')?;
-                break;
+        self.nest("flow seq", |p| {
+            let mut v = vec![];
+            loop {
+                p.gap_flow()?;
+                p.forward();
+                if p.sym(b']').is_ok() {
+                    break;
+                }
+                if p.food().first() == Some(&b',') {
+                    if !p.lenient_flow {
+                        return p.err_expected("flow sequence item", &["node", "]"]);
+                    }
+                    p.sym(b',')?;
+                    continue;
+                }
+                p.forward();
+                p.scopes.push("item");
+                let n = p.scalar(level + 1, false, true);
+                p.scopes.pop();
+                let n =
+                    n.or_else(|e| e.or(|| p.err_expected("flow sequence item", &["node", "]"])))?;
+                v.push(n);
+                p.gap_flow()?;
+                p.forward();
+                if p.sym(b',').is_err() {
+                    p.sym(b']')?;
+                    break;
+                }
             }
-        }
-        self.forward();
-        Ok(R::new_rc(v.into_iter().collect()))
+            p.forward();
+            Ok(R::new_rc(v.into_iter().collect()))
+        })
     }
 
     /// Match flow map.
+    ///
+    /// The value can be omitted as null, e.g., `{a, b: c}`.
     pub fn map_flow(&mut self, level: usize) -> PResult<R::Rc> {
         self.sym(b'{')?;
-        let mut m = vec![];
-        loop {
-            self.inv(TakeOpt::More(0))?;
-            self.forward();
-            if self.sym(b'}').is_ok() {
-                break;
-            }
-            self.forward();
-            let k = if self.complex_mapping().is_ok() {
-                self.forward();
-                let k = self
-                    .scalar(level + 1, false, true)
-                    .or_else(|e| e.or(|| self.err("flow map key")))?;
-                if self.gap(true).is_ok() {
-                    self.ind(level)?;
+        self.nest("flow map", |p| {
+            let mut m = vec![];
+            loop {
+                p.gap_flow()?;
+                p.forward();
+                if p.sym(b'}').is_ok() {
+                    break;
+                }
+                if p.food().first() == Some(&b',') {
+                    if !p.lenient_flow {
+                        return p.err_expected("flow map key", &["key", "}"]);
+                    }
+                    p.sym(b',')?;
+                    continue;
+                }
+                p.forward();
+                if p.complex_mapping().is_ok() {
+                    p.gap_flow()?;
+                    p.forward();
+                }
+                p.scopes.push("key");
+                let k = p.scalar_flow(level + 1, true);
+                p.scopes.pop();
+                let k = k.or_else(|e| e.or(|| p.err_expected("flow map key", &["key", "}"])))?;
+                p.gap_flow()?;
+                p.forward();
+                let v = if p.sym(b':').is_ok() {
+                    p.value_flow(level, &k)?
+                } else if matches!(p.food().first(), Some(b',' | b'}')) {
+                    Node::new(Yaml::Null, p.indicator(), "")
+                } else {
+                    return p.err_expected("flow map splitter", &[":", ",", "}"]);
+                };
+                m.push((k, v));
+                p.gap_flow()?;
+                p.forward();
+                if p.sym(b',').is_err() {
+                    p.sym(b'}')?;
+                    break;
                 }
-                k
-            } else {
-                self.scalar_flow(level + 1, true)
-                    .or_else(|e| e.or(|| self.err("flow map key")))?
-            };
-            if self.sym(b':').is_err() {
-                return self.err("flow map splitter");
-            }
-            self.context(|p| p.bound().unwrap_or_default());
-            self.forward();
-            let v = self
-                .scalar(level + 1, false, true)
-                .or_else(|e| e.or(|| self.err("flow map value")))?;
-            m.push((k, v));
-            if self.sym(b',').is_err() {
-                self.inv(TakeOpt::More(0))?;
-                self.sym(b'}')?;
-                break;
             }
-        }
+            p.forward();
+            Ok(R::new_rc(m.into_iter().collect()))
+        })
+    }
+
+    /// Match the value of a flow map item behind the splitter `:`.
+    fn value_flow(&mut self, level: usize, k: &Node<R>) -> PResult<Node<R>> {
+        self.context(|p| p.bound().unwrap_or_default());
         self.forward();
-        Ok(R::new_rc(m.into_iter().collect()))
+        self.parents.push(k.pos());
+        self.scopes.push("value");
+        let v = self.scalar(level + 1, false, true);
+        self.scopes.pop();
+        let v = v.or_else(|e| e.or(|| self.err_expected("flow map value", &["node"])));
+        self.parents.pop();
+        v
     }
 
     /// Match sequence.
     pub fn seq(&mut self, level: usize, map: bool) -> PResult<R::Rc> {
-        let mut v = vec![];
-        loop {
-            self.forward();
-            if v.is_empty() {
-                // First item
-                if map {
-                    self.gap(true)?;
-                    self.ind_define(level)?;
-                } else if self.gap(true).is_ok() {
-                    // Root
-                    self.ind(level)?;
-                }
-                self.sym(b'-')?;
-                self.bound()?;
-            } else {
-                if self.gap(true).is_err() && !self.doc_end() {
-                    return self.err("sequence terminator");
-                }
-                if self.doc_end() || self.ind(level).is_err() {
-                    break;
-                }
-                if self.sym(b'-').is_err() || self.bound().is_err() {
+        self.seq_item(level, map, true)?;
+        self.seq_rest(level, map)
+    }
+
+    /// Match the rest items of a sequence, the first indicator is matched.
+    pub(crate) fn seq_rest(&mut self, level: usize, map: bool) -> PResult<R::Rc> {
+        self.nest("seq", |p| {
+            let mut v = vec![];
+            loop {
+                p.scopes.push("item");
+                let n = p.scalar(level + 1, false, false);
+                p.scopes.pop();
+                let n = n.or_else(|e| e.or(|| p.err_expected("sequence item", &["node"])))?;
+                v.push(n);
+                if !p.seq_item(level, map, false)? {
                     break;
                 }
-                self.forward();
+            }
+            Ok(R::new_rc(v.into_iter().collect()))
+        })
+    }
+
+    /// Match the indicator `-` of a sequence item.
+    ///
+    /// Return `false` if the sequence is ended, and the last wrapping will be
+    /// kept.
+    pub fn seq_item(&mut self, level: usize, map: bool, first: bool) -> PResult<bool> {
+        self.forward();
+        if first {
+            let block = if map && !self.explicit_node() {
+                self.gap(true)?;
+                self.ind_define(level)?;
+                true
+            } else if self.gap(true).is_ok() {
+                // Root
+                self.ind(level)?;
+                true
+            } else {
+                level == 0
+            };
+            let start = self.pos;
+            self.sym(b'-')?;
+            self.bound()?;
+            if block {
+                self.record_indent(level, start)?;
+            }
+        } else {
+            if self.gap(true).is_err() && !self.doc_end() {
+                return self.err_expected("sequence terminator", &["newline"]);
+            }
+            if self.doc_end()
+                || self.ind(level).is_err()
+                || self.sym(b'-').is_err()
+                || self.bound().is_err()
+            {
+                // Keep last wrapping
+                self.backward();
+                return Ok(false);
             }
             self.forward();
-            let n = self
-                .scalar(level + 1, false, false)
-                .or_else(|e| e.or(|| self.err("sequence item")))?;
-            v.push(n);
         }
-        // Keep last wrapping
-        self.backward();
-        Ok(R::new_rc(v.into_iter().collect()))
+        self.forward();
+        Ok(true)
     }
 
     /// Match map.
     pub fn map(&mut self, level: usize, map: bool, flow: bool) -> PResult<R::Rc> {
-        let mut m = vec![];
-        loop {
-            self.forward();
-            let k = if m.is_empty() {
-                // First item
-                if map {
-                    self.gap(true)?;
-                    self.ind_define(level)?;
-                } else if self.gap(true).is_ok() {
-                    // Root
-                    self.ind(level)?;
-                }
-                self.forward();
-                let k = if self.complex_mapping().is_ok() {
-                    self.forward();
-                    let k = self
-                        .scalar(level + 1, true, flow)
-                        .or_else(|e| e.or(|| self.err("map key")))?;
-                    if self.gap(true).is_ok() {
-                        self.ind(level)?;
-                    }
-                    k
-                } else {
-                    self.scalar_flow(level + 1, flow)?
-                };
-                if self.sym(b':').is_err() || self.bound().is_err() {
-                    // Return key
-                    return Ok(k.clone_yaml());
-                }
-                k
-            } else {
-                if self.gap(true).is_err() && !self.doc_end() {
-                    return self.err("map terminator");
-                }
-                if self.doc_end() || self.ind(level).is_err() {
-                    break;
-                }
-                self.forward();
-                let k = if self.complex_mapping().is_ok() {
-                    self.forward();
-                    let k = self
-                        .scalar(level + 1, true, flow)
-                        .or_else(|e| e.or(|| self.err("map key")))?;
-                    if self.gap(true).is_ok() {
-                        self.ind(level)?;
-                    }
-                    k
-                } else {
-                    self.scalar_flow(level + 1, flow)
-                        .or_else(|e| e.or(|| self.err("map key")))?
-                };
-                if self.sym(b':').is_err() || self.bound().is_err() {
-                    return self.err("map splitter");
+        let (k, is_map) = self.map_key_first(level, map, flow)?;
+        if is_map && flow {
+            // Single pair in flow sequence
+            self.scopes.push("flow map");
+            let v = self.value_flow(level, &k);
+            self.scopes.pop();
+            let v = v?;
+            Ok(R::new_rc(Yaml::from_iter([(k, v)])))
+        } else if is_map {
+            self.map_rest(level, flow, k)
+        } else {
+            // Return key, which is counted as the node itself
+            self.nodes -= 1;
+            self.style = k.style();
+            self.matched_tag = k.raw_tag().to_string();
+            self.alias_name = k.anchor_name().unwrap_or_default().to_string();
+            Ok(k.clone_yaml())
+        }
+    }
+
+    /// Match the rest items of a map, the first key is matched.
+    pub(crate) fn map_rest(&mut self, level: usize, flow: bool, mut k: Node<R>) -> PResult<R::Rc> {
+        self.nest("map", |p| {
+            let mut m = vec![];
+            loop {
+                p.forward();
+                p.parents.push(k.pos());
+                p.scopes.push("value");
+                let v = p.scalar(level + 1, true, false);
+                p.scopes.pop();
+                let v = v.or_else(|e| e.or(|| p.err_expected("map value", &["node"])))?;
+                p.parents.pop();
+                m.push((k, v));
+                match p.map_key_next(level, flow)? {
+                    Some(next) => k = next,
+                    None => break,
                 }
-                k
-            };
+            }
+            Ok(R::new_rc(m.into_iter().collect()))
+        })
+    }
+
+    /// Match the first key of a map and its splitter `:`.
+    ///
+    /// If the splitter is missing, the key is returned with `false`,
+    /// which means the node is not a map.
+    pub fn map_key_first(
+        &mut self,
+        level: usize,
+        map: bool,
+        flow: bool,
+    ) -> PResult<(Node<R>, bool)> {
+        self.forward();
+        let pos = self.pos;
+        let inline = map && self.explicit_node();
+        let block = self.map_indent(level, map && !inline)?;
+        self.forward();
+        let start = self.pos;
+        if self.complex_mapping().is_ok() {
+            if block && !flow {
+                self.record_indent(level, start)?;
+            }
+            return Ok((self.map_key_complex(level, flow)?, true));
+        }
+        let k = self.scalar_flow(level + 1, flow)?;
+        let is_map = self.sym(b':').is_ok() && self.bound().is_ok();
+        if is_map && block && !flow {
+            self.record_indent(level, start)?;
+        }
+        if inline && !is_map {
+            // Leave the scalar to its own level
+            self.pos = pos;
             self.forward();
-            let v = self
-                .scalar(level + 1, true, false)
-                .or_else(|e| e.or(|| self.err("map value")))?;
-            m.push((k, v));
+            return Err(PError::Mismatch);
         }
-        // Keep last wrapping
-        self.backward();
-        Ok(R::new_rc(m.into_iter().collect()))
+        Ok((k, is_map))
+    }
+
+    /// Match the indentation before the first key of a map, return true if
+    /// the map is in block context.
+    ///
+    /// This is separated from [`Loader::map_key_first`] to keep the stack
+    /// frames of the nested collections small.
+    fn map_indent(&mut self, level: usize, define: bool) -> PResult<bool> {
+        if define {
+            self.gap(true)?;
+            self.ind_define(level)?;
+            if self.indent[level] == 0 {
+                // The map value must be indented
+                self.indent.truncate(level);
+                self.backward();
+                return Err(PError::Mismatch);
+            }
+            Ok(true)
+        } else if self.gap(true).is_ok() {
+            // Root
+            self.ind(level)?;
+            Ok(true)
+        } else {
+            Ok(level == 0)
+        }
+    }
+
+    /// Match the following key of a map and its splitter `:`.
+    ///
+    /// Return `None` if the map is ended, and the last wrapping will be kept.
+    pub fn map_key_next(&mut self, level: usize, flow: bool) -> PResult<Option<Node<R>>> {
+        self.forward();
+        if self.gap(true).is_err() && !self.doc_end() {
+            return self.err_expected("map terminator", &["newline"]);
+        }
+        if self.doc_end() || self.ind(level).is_err() {
+            // Keep last wrapping
+            self.backward();
+            return Ok(None);
+        }
+        self.forward();
+        if self.complex_mapping().is_ok() {
+            return self.map_key_complex(level, flow).map(Some);
+        }
+        self.scopes.push("key");
+        let k = self.scalar_flow(level + 1, flow);
+        self.scopes.pop();
+        let k = k.or_else(|e| e.or(|| self.err_expected("map key", &["key"])))?;
+        if self.sym(b':').is_err() || self.bound().is_err() {
+            return self.err_expected("map splitter", &[":"]);
+        }
+        Ok(Some(k))
+    }
+
+    /// Match the explicit key behind the indicator `?`.
+    ///
+    /// The key can be any node, and the splitter `:` can be placed at the
+    /// next line. If the splitter is missing, the value is null.
+    fn map_key_complex(&mut self, level: usize, flow: bool) -> PResult<Node<R>> {
+        self.nest("key", |p| {
+            p.forward();
+            let k = p
+                .scalar(level + 1, true, flow)
+                .or_else(|e| e.or(|| p.err_expected("map key", &["key"])))?;
+            p.forward();
+            let splitter = p.context(|p| {
+                if p.gap(true).is_ok() {
+                    p.ind(level)?;
+                }
+                p.sym(b':')?;
+                p.bound()
+            });
+            if splitter.is_err() {
+                // Keep last wrapping
+                p.backward();
+            }
+            Ok(k)
+        })
     }
 }
 
@@ -521,10 +1495,34 @@ impl<R: Repr> DerefMut for Loader<'_, R> {
 ///     "age" => 46,
 /// })]);
 /// ```
-pub fn parse<R: Repr>(doc: &str) -> Result<Seq<R>, PError> {
+pub fn parse<R: Repr>(doc: &str) -> Result<Vec<Node<R>>, PError> {
     Loader::new(doc.as_bytes()).parse()
 }
 
+/// Parse non-cyclic YAML document like [`parse`], and return the anchors of
+/// each document as well.
+///
+/// The aliases are replaced, so the anchors are only used to look up the nodes
+/// by name.
+///
+/// ```
+/// use yaml_peg::{node, parse_with_anchors};
+///
+/// let doc = "
+/// base: &base
+///   port: 80
+/// site: *base
+/// ";
+/// let (root, anchors) = parse_with_anchors(doc).unwrap();
+/// assert_eq!(anchors[0]["base"], node!({"port" => 80}));
+/// assert_eq!(root[0].get_checked("site").unwrap(), &anchors[0]["base"]);
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn parse_with_anchors<R: Repr>(doc: &str) -> Result<(Vec<Node<R>>, Vec<Anchors<R>>), PError> {
+    let mut loader = Loader::new(doc.as_bytes());
+    loader.parse().map(|root| (root, loader.get_anchors()))
+}
+
 /// Parse cyclic YAML document into [`alloc::rc::Rc`] or [`alloc::sync::Arc`]
 /// data holder. Return an sequence of nodes and keep the anchors placeholder.
 ///
@@ -539,7 +1537,31 @@ pub fn parse<R: Repr>(doc: &str) -> Result<Seq<R>, PError> {
 /// assert_eq!(vec![node!({"map" => node!(*"root")})], root);
 /// assert_eq!(anchors[0].get("root").unwrap(), &root[0]);
 /// ```
-pub fn parse_cyclic<R: Repr>(doc: &str) -> Result<(Seq<R>, Vec<Anchors<R>>), PError> {
+#[allow(clippy::type_complexity)]
+pub fn parse_cyclic<R: Repr>(doc: &str) -> Result<(Vec<Node<R>>, Vec<Anchors<R>>), PError> {
     let mut loader = Loader::new(doc.as_bytes()).cyclic_mode(true);
     loader.parse().map(|root| (root, loader.get_anchors()))
 }
+
+/// Parse a single YAML value without the document framing (directives and
+/// document markers), return the node and the consumed length in bytes.
+///
+/// The text behind the value is not parsed, so the value can be embedded in
+/// other formats, e.g., the command line arguments.
+///
+/// ```
+/// use yaml_peg::{node, parser::parse_fragment, repr::RcRepr};
+///
+/// let (n, len) = parse_fragment::<RcRepr>("[a, {b: 1}] rest").unwrap();
+/// assert_eq!(n, node!(["a", node!({"b" => 1})]));
+/// assert_eq!(len, 11);
+/// let (n, _) = parse_fragment::<RcRepr>("a: &x 1\nb: *x\n").unwrap();
+/// assert_eq!(n, node!({"a" => 1, "b" => 1}));
+/// ```
+pub fn parse_fragment<R: Repr>(doc: &str) -> Result<(Node<R>, usize), PError> {
+    let mut loader = Loader::new(doc.as_bytes());
+    loader.doc_start();
+    let node = loader.scalar(0, false, false)?;
+    loader.forward();
+    Ok((node, loader.pos))
+}
@@ -0,0 +1,81 @@
+use super::*;
+use crate::{repr::ArcRepr, NodeArc};
+use rayon::prelude::*;
+
+/// Parse non-cyclic YAML document like [`parse`], but the documents are split
+/// at the `---` boundaries and parsed on the thread pool of `rayon` crate.
+///
+/// Each document only accesses the directives and anchors of itself.
+///
+/// ```
+/// use yaml_peg::{node, parse_parallel};
+///
+/// let doc = "
+/// a: &x 1
+/// b: *x
+/// ---
+/// - 2
+/// ...
+/// ---
+/// 3
+/// ";
+/// let root = parse_parallel(doc).unwrap();
+/// assert_eq!(root, vec![
+///     node!(arc{"a" => 1, "b" => 1}),
+///     node!(arc[2]),
+///     node!(arc 3),
+/// ]);
+/// ```
+pub fn parse_parallel(doc: &str) -> Result<Vec<NodeArc>, PError> {
+    let doc = doc.as_bytes();
+    let mut bounds = doc_bounds(doc);
+    bounds.push(doc.len());
+    let docs = bounds
+        .par_windows(2)
+        .map(|w| {
+            let mut loader = Loader::<ArcRepr>::new(&doc[..w[1]]);
+            loader.parser = loader.parser.pos(w[0]);
+            loader.parse()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(docs.into_iter().flatten().collect())
+}
+
+/// Return the starting positions of the documents.
+///
+/// A document starts at the `---` marker (or its directives) after the
+/// content or the `...` marker of the previous document.
+fn doc_bounds(doc: &[u8]) -> Vec<usize> {
+    let is_marker = |line: &[u8], marker: &[u8]| {
+        line.starts_with(marker)
+            && (line.len() == marker.len() || line[marker.len()].is_ascii_whitespace())
+    };
+    let mut bounds = vec![0];
+    let mut pos = 0;
+    let mut content = false;
+    let mut ended = false;
+    for line in doc.split_inclusive(|c| *c == b'\n') {
+        let len = line.len();
+        let line = line.strip_prefix(encoding::BOM).unwrap_or(line);
+        if is_marker(line, b"---") {
+            if content || ended {
+                bounds.push(pos);
+            }
+            content = true;
+            ended = false;
+        } else if is_marker(line, b"...") {
+            ended = true;
+        } else if line.starts_with(b"%") {
+            if content || ended {
+                bounds.push(pos);
+            }
+            content = false;
+            ended = false;
+        } else if !matches!(line.trim_ascii_start().first(), None | Some(b'#')) {
+            content = true;
+        }
+        pos += len;
+    }
+    bounds.dedup();
+    bounds
+}
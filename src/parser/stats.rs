@@ -0,0 +1,49 @@
+use super::*;
+use std::time::{Duration, Instant};
+
+/// The statistics of a parsing, see [`parse_stats`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    /// The number of the documents.
+    pub docs: usize,
+    /// The number of the parsed nodes, includes the aliases.
+    pub nodes: usize,
+    /// The total length of the scalar lexemes in bytes.
+    pub scalar_bytes: usize,
+    /// The number of the anchors.
+    pub anchors: usize,
+    /// The maximum nesting depth of the collections.
+    pub depth: usize,
+    /// The time spent on parsing, excludes dropping the nodes.
+    pub elapsed: Duration,
+}
+
+/// Parse non-cyclic YAML document like [`parse`], and report the node counts
+/// and the time spent instead of the nodes.
+///
+/// This is useful to watch the performance of the grammar changes.
+///
+/// ```
+/// use yaml_peg::{parser::parse_stats, repr::RcRepr};
+///
+/// let stats = parse_stats::<RcRepr>("a: &x [1, 2]\nb: *x\n---\nc\n").unwrap();
+/// assert_eq!(2, stats.docs);
+/// assert_eq!(8, stats.nodes);
+/// assert_eq!(1, stats.anchors);
+/// assert_eq!(2, stats.depth);
+/// ```
+pub fn parse_stats<R: Repr>(doc: &str) -> Result<ParseStats, PError> {
+    let mut loader = Loader::<R>::new(doc.as_bytes());
+    let start = Instant::now();
+    let root = loader.parse()?;
+    let elapsed = start.elapsed();
+    let stats = ParseStats {
+        docs: root.len(),
+        nodes: loader.nodes,
+        scalar_bytes: loader.scalar_bytes,
+        anchors: loader.anchors.iter().map(|a| a.len()).sum(),
+        depth: loader.depth_peak,
+        elapsed,
+    };
+    Ok(stats)
+}
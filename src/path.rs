@@ -0,0 +1,141 @@
+use crate::{repr::Repr, *};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+/// A segment of the [`NodePath`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// The key of the map item, the keys which are not scalars are recorded
+    /// as empty strings.
+    Key(String),
+    /// The index of the sequence item.
+    Ind(usize),
+}
+
+/// The path from a node to its descendant, a sequence of the map keys and
+/// the sequence indices.
+///
+/// The path is displayed in the syntax of [`Node::get_path_checked`], so the
+/// descendant can be found again by the string.
+///
+/// ```
+/// use yaml_peg::{node, NodePath};
+///
+/// let path = NodePath::new().key("a").ind(1).key("b");
+/// assert_eq!("a[1].b", path.to_string());
+/// let n = node!({"a" => node!([0, node!({"b" => 2})])});
+/// assert_eq!(&node!(2), n.get_path_checked(&path.to_string()).unwrap());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct NodePath(Vec<PathSegment>);
+
+impl NodePath {
+    /// Create an empty path, which refers to the node itself.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a map key to the path.
+    pub fn key(mut self, key: impl ToString) -> Self {
+        self.0.push(PathSegment::Key(key.to_string()));
+        self
+    }
+
+    /// Append a sequence index to the path.
+    pub fn ind(mut self, ind: usize) -> Self {
+        self.0.push(PathSegment::Ind(ind));
+        self
+    }
+
+    /// Push a segment to the end of the path.
+    pub fn push(&mut self, segment: PathSegment) {
+        self.0.push(segment);
+    }
+
+    /// Remove the last segment of the path.
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.0.pop()
+    }
+
+    /// The segments of the path.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    /// Return true if the path refers to the node itself.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Display for NodePath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) if i == 0 => f.write_str(key)?,
+                PathSegment::Key(key) => write!(f, ".{key}")?,
+                PathSegment::Ind(ind) => write!(f, "[{ind}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Extend<PathSegment> for NodePath {
+    fn extend<T: IntoIterator<Item = PathSegment>>(&mut self, iter: T) {
+        self.0.extend(iter);
+    }
+}
+
+impl FromIterator<PathSegment> for NodePath {
+    fn from_iter<T: IntoIterator<Item = PathSegment>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Parse the path string of [`Node::get_path_checked`], the invalid segments
+/// are skipped.
+pub(crate) fn parse_path(path: &str) -> NodePath {
+    let mut found = NodePath::new();
+    for piece in path.split('.') {
+        let (key, inds) = piece.split_once('[').unwrap_or((piece, ""));
+        if !key.is_empty() {
+            found.push(PathSegment::Key(key.to_string()));
+        }
+        for ind in inds.split('[') {
+            if let Some(ind) = ind.strip_suffix(']').and_then(|ind| ind.parse().ok()) {
+                found.push(PathSegment::Ind(ind));
+            }
+        }
+    }
+    found
+}
+
+/// Collect the paths of the nodes which match the predicate, see
+/// [`Node::find_all`].
+pub(crate) fn find_all<R, F>(node: &Node<R>, f: &F, path: &mut NodePath, found: &mut Vec<NodePath>)
+where
+    R: Repr,
+    F: Fn(&Node<R>) -> bool,
+{
+    if f(node) {
+        found.push(path.clone());
+    }
+    match node.yaml() {
+        Yaml::Seq(v) => v.iter().enumerate().for_each(|(i, n)| {
+            path.push(PathSegment::Ind(i));
+            find_all(n, f, path, found);
+            path.pop();
+        }),
+        Yaml::Map(m) => m.iter().for_each(|(k, v)| {
+            let key = k.as_value_checked().unwrap_or_default();
+            path.push(PathSegment::Key(key.to_string()));
+            find_all(v, f, path, found);
+            path.pop();
+        }),
+        _ => (),
+    }
+}
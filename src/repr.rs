@@ -3,10 +3,20 @@
 //!
 //! [`Rc`] is the single thread reference counter,
 //! and [`Arc`] is the multiple thread reference counter.
+//! The `arena` feature provides [`ArenaRepr`], which allocates the data in
+//! chunks for the very large trees.
+#[cfg(all(feature = "arena", test))]
+pub(crate) use self::arena::chunks as arena_chunks;
+#[cfg(feature = "arena")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "arena")))]
+pub use self::arena::{ArenaRc, ArenaRepr};
 use crate::Yaml;
 use alloc::{rc::Rc, sync::Arc};
 use core::{fmt::Debug, hash::Hash, ops::Deref};
 
+#[cfg(feature = "arena")]
+mod arena;
+
 /// The representation symbol for [`Rc`].
 pub struct RcRepr;
 /// The representation symbol for [`Arc`].
@@ -21,6 +31,14 @@ pub trait Repr: Sized {
 
     /// The creation function of this type.
     fn new_rc(yaml: Yaml<Self>) -> Self::Rc;
+
+    /// Take the data out if the holder is the last reference, otherwise return
+    /// the holder back.
+    ///
+    /// The default implementation never takes the data.
+    fn try_unwrap(rc: Self::Rc) -> Result<Yaml<Self>, Self::Rc> {
+        Err(rc)
+    }
 }
 
 impl Repr for RcRepr {
@@ -29,6 +47,10 @@ impl Repr for RcRepr {
     fn new_rc(yaml: Yaml<Self>) -> Self::Rc {
         Rc::new(yaml)
     }
+
+    fn try_unwrap(rc: Self::Rc) -> Result<Yaml<Self>, Self::Rc> {
+        Rc::try_unwrap(rc)
+    }
 }
 
 impl Repr for ArcRepr {
@@ -37,4 +59,8 @@ impl Repr for ArcRepr {
     fn new_rc(yaml: Yaml<Self>) -> Self::Rc {
         Arc::new(yaml)
     }
+
+    fn try_unwrap(rc: Self::Rc) -> Result<Yaml<Self>, Self::Rc> {
+        Arc::try_unwrap(rc)
+    }
 }
@@ -0,0 +1,241 @@
+use super::Repr;
+use crate::Yaml;
+use alloc::boxed::Box;
+use core::{
+    cell::{Cell, RefCell, UnsafeCell},
+    fmt::{Debug, Formatter},
+    hash::{Hash, Hasher},
+    mem::{ManuallyDrop, MaybeUninit},
+    ops::Deref,
+    ptr::NonNull,
+};
+
+/// The number of the slots in a chunk.
+const CHUNK_LEN: usize = 256;
+
+std::thread_local! {
+    static CURRENT: RefCell<Current> = RefCell::new(Current::new());
+    static CHUNKS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The representation symbol for [`ArenaRc`], the data are allocated in the
+/// chunks of a thread-local arena.
+///
+/// The holders are reference counted like [`Rc`](alloc::rc::Rc), but the
+/// allocations are shared by the chunks of 256 nodes, and a chunk is freed
+/// after all of its nodes are dropped. This reduces the allocations of the
+/// very large trees, which are built and dropped at once.
+///
+/// The holders cannot be sent to other threads, use [`Node::to_repr`] to
+/// convert the tree into other representations.
+///
+/// [`Node::to_repr`]: crate::Node::to_repr
+///
+/// ```
+/// use yaml_peg::{node, parse, repr::{ArenaRepr, RcRepr}};
+///
+/// let root = parse::<ArenaRepr>("a: [1, 2]").unwrap().remove(0);
+/// assert_eq!(node!({"a" => node!([1, 2])}), root.to_repr::<RcRepr>());
+/// assert_eq!(root, node!({"a" => node!([1, 2])}).to_repr::<ArenaRepr>());
+/// ```
+pub struct ArenaRepr;
+
+/// The data holder of [`ArenaRepr`], a reference counted slot of the arena.
+pub struct ArenaRc(NonNull<Slot>);
+
+struct Slot {
+    count: Cell<usize>,
+    chunk: Cell<*const Chunk>,
+    yaml: UnsafeCell<MaybeUninit<Yaml<ArenaRepr>>>,
+}
+
+struct Chunk {
+    slots: Box<[Slot]>,
+    // The number of the allocated slots which are not dropped
+    live: Cell<usize>,
+    // The chunk is no longer used for allocation
+    retired: Cell<bool>,
+}
+
+impl Chunk {
+    fn alloc() -> NonNull<Self> {
+        let slots = (0..CHUNK_LEN)
+            .map(|_| Slot {
+                count: Cell::new(0),
+                chunk: Cell::new(core::ptr::null()),
+                yaml: UnsafeCell::new(MaybeUninit::uninit()),
+            })
+            .collect();
+        let chunk = Box::new(Self {
+            slots,
+            live: Cell::new(0),
+            retired: Cell::new(false),
+        });
+        let chunk = NonNull::from(Box::leak(chunk));
+        // SAFETY: The chunk is just allocated
+        let c = unsafe { chunk.as_ref() };
+        c.slots
+            .iter()
+            .for_each(|slot| slot.chunk.set(chunk.as_ptr()));
+        CHUNKS.with(|n| n.set(n.get() + 1));
+        chunk
+    }
+
+    /// Free the chunk if it is retired and all of its slots are dropped.
+    ///
+    /// # Safety
+    ///
+    /// The chunk must be allocated by [`Chunk::alloc`] and not freed yet, and
+    /// it must not be used after freed.
+    unsafe fn try_free(chunk: NonNull<Self>) {
+        let c = chunk.as_ref();
+        if c.retired.get() && c.live.get() == 0 {
+            drop(Box::from_raw(chunk.as_ptr()));
+            // The counter is gone if the thread is exiting
+            let _ = CHUNKS.try_with(|n| n.set(n.get() - 1));
+        }
+    }
+
+    /// Mark the slot as dropped, and free the chunk if it is unused.
+    ///
+    /// # Safety
+    ///
+    /// The data of the slot must be dropped or moved out, and the slot must
+    /// not be used after this call.
+    unsafe fn release(slot: &Slot) {
+        let chunk = NonNull::new_unchecked(slot.chunk.get() as *mut Self);
+        let c = chunk.as_ref();
+        c.live.set(c.live.get() - 1);
+        Self::try_free(chunk);
+    }
+}
+
+/// The chunk for allocation of the current thread.
+struct Current {
+    chunk: NonNull<Chunk>,
+    next: usize,
+}
+
+impl Current {
+    fn new() -> Self {
+        Self { chunk: Chunk::alloc(), next: 0 }
+    }
+
+    fn retire(&self) {
+        // SAFETY: The current chunk is not retired, so it is not freed
+        unsafe {
+            self.chunk.as_ref().retired.set(true);
+            Chunk::try_free(self.chunk);
+        }
+    }
+}
+
+impl Drop for Current {
+    fn drop(&mut self) {
+        self.retire();
+    }
+}
+
+impl Repr for ArenaRepr {
+    type Rc = ArenaRc;
+
+    fn new_rc(yaml: Yaml<Self>) -> Self::Rc {
+        CURRENT.with(|cur| {
+            let mut cur = cur.borrow_mut();
+            if cur.next == CHUNK_LEN {
+                // The old chunk is retired when dropped
+                *cur = Current::new();
+            }
+            let index = cur.next;
+            cur.next += 1;
+            // SAFETY: The current chunk is alive, and the slot is not used
+            let c = unsafe { cur.chunk.as_ref() };
+            c.live.set(c.live.get() + 1);
+            let slot = &c.slots[index];
+            slot.count.set(1);
+            unsafe { (*slot.yaml.get()).write(yaml) };
+            ArenaRc(NonNull::from(slot))
+        })
+    }
+
+    fn try_unwrap(rc: Self::Rc) -> Result<Yaml<Self>, Self::Rc> {
+        if rc.slot().count.get() != 1 {
+            return Err(rc);
+        }
+        let rc = ManuallyDrop::new(rc);
+        let slot = rc.slot();
+        // SAFETY: The holder is the last reference, the slot is dead then
+        unsafe {
+            let yaml = (*slot.yaml.get()).assume_init_read();
+            Chunk::release(slot);
+            Ok(yaml)
+        }
+    }
+}
+
+impl ArenaRc {
+    fn slot(&self) -> &Slot {
+        // SAFETY: The slot is alive while referenced
+        unsafe { self.0.as_ref() }
+    }
+}
+
+impl Deref for ArenaRc {
+    type Target = Yaml<ArenaRepr>;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The data of the slot is initialized while referenced
+        unsafe { (*self.slot().yaml.get()).assume_init_ref() }
+    }
+}
+
+impl Clone for ArenaRc {
+    fn clone(&self) -> Self {
+        let count = &self.slot().count;
+        count.set(count.get() + 1);
+        Self(self.0)
+    }
+}
+
+impl Drop for ArenaRc {
+    fn drop(&mut self) {
+        let slot = self.slot();
+        let count = slot.count.get() - 1;
+        slot.count.set(count);
+        if count > 0 {
+            return;
+        }
+        // SAFETY: The holder is the last reference, the children are dropped
+        // before the slot is released, so the chunk is still alive
+        unsafe {
+            (*slot.yaml.get()).assume_init_drop();
+            Chunk::release(slot);
+        }
+    }
+}
+
+impl Hash for ArenaRc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl PartialEq for ArenaRc {
+    fn eq(&self, rhs: &Self) -> bool {
+        **self == **rhs
+    }
+}
+
+impl Eq for ArenaRc {}
+
+impl Debug for ArenaRc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+/// The number of the allocated chunks of the current thread.
+#[cfg(test)]
+pub(crate) fn chunks() -> usize {
+    CHUNKS.with(Cell::get)
+}
@@ -0,0 +1,36 @@
+use alloc::vec::Vec;
+
+/// Decode the base64 payload of `!!binary` node, the whitespaces (line
+/// breaks of the block scalars) are ignored, and the padding is optional.
+///
+/// Return `None` if the payload is invalid.
+pub(crate) fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() / 4 * 3);
+    let (mut buf, mut bits, mut pad) = (0u32, 0, 0);
+    for c in s.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                pad += 1;
+                continue;
+            }
+            _ => return None,
+        };
+        if pad > 0 {
+            return None;
+        }
+        buf = buf << 6 | u32::from(v);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buf >> bits) as u8);
+            buf &= (1 << bits) - 1;
+        }
+    }
+    // The rest bits should be the zeros of the last byte
+    (bits < 6 && buf == 0 && pad <= 2).then_some(out)
+}
@@ -1,17 +1,22 @@
-use super::SerdeError;
+use super::{binary::decode_base64, foreign::FOREIGN_NAME, ser_node::NODE_NAME, SerdeError};
 use crate::{
-    parse,
+    anchor_resolve_checked, parse,
+    parser::{Anchors, Loader, Parser},
     repr::{RcRepr, Repr},
-    to_f64, to_i64, Map, Node, Seq, Yaml,
+    to_f64, to_i128, to_i64, to_u128, to_u64, Map, Node, Seq, Yaml,
 };
-use alloc::{format, string::ToString, vec::Vec};
-use core::marker::PhantomData;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt::Display, marker::PhantomData};
 use serde::{
     de::{
         DeserializeOwned, DeserializeSeed, EnumAccess, Error, Expected, MapAccess, SeqAccess,
         Unexpected, VariantAccess, Visitor,
     },
-    serde_if_integer128, Deserialize, Deserializer,
+    Deserialize, Deserializer,
 };
 
 macro_rules! impl_visitor {
@@ -33,14 +38,67 @@ macro_rules! impl_deserializer {
         where
             V: Visitor<'a>,
         {
-            match self.yaml() {
+            match self.node.yaml() {
                 Yaml::$ty($n) => visitor.$visit($value),
-                _ => Err(unexpected(&self, visitor)),
+                _ => Err(unexpected(&self.node, visitor)),
             }
         })+
     };
 }
 
+macro_rules! impl_deserializer_int {
+    ($(fn $method:ident => $visit:ident($parse:ident -> $ty:ty))+) => {
+        $(fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'a>,
+        {
+            match self.node.yaml() {
+                Yaml::Int(n) => {
+                    let v = self.num(n, $parse)?;
+                    let v = self.int_range::<_, $ty>(v, &visitor)?;
+                    visitor.$visit(v)
+                }
+                Yaml::Float(n) if self.opt.lenient_number => {
+                    let v = self.float_to_int(n, &visitor)?;
+                    visitor.$visit(v)
+                }
+                _ => Err(unexpected(&self.node, visitor)),
+            }
+        })+
+    };
+}
+
+macro_rules! impl_deserializer_float {
+    ($(fn $method:ident => $visit:ident($ty:ty))+) => {
+        $(fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'a>,
+        {
+            match self.node.yaml() {
+                Yaml::Float(n) => visitor.$visit(self.num(n, to_f64)? as $ty),
+                Yaml::Int(n) => match (to_i64(n), to_i128(n)) {
+                    (Ok(n), _) => visitor.visit_i64(n),
+                    // Out of range of 64-bit integers, cast to the float
+                    (_, Ok(n)) => visitor.$visit(n as $ty),
+                    _ => visitor.$visit(self.num(n, to_u128)? as $ty),
+                },
+                _ => Err(unexpected(&self.node, visitor)),
+            }
+        })+
+    };
+}
+
+macro_rules! impl_forward {
+    ($(fn $method:ident$(($($arg:ident: $ty:ty),+))?)+) => {
+        $(fn $method<V>(self, $($($arg: $ty,)+)? visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'a>,
+        {
+            NodeDeserializer::new(self).$method($($($arg,)+)? visitor)
+        })+
+    };
+}
+
 /// Parse the document and deserialize nodes to a specific type.
 ///
 /// Since the document can be split into multiple parts,
@@ -63,7 +121,7 @@ macro_rules! impl_deserializer {
 /// married: true
 /// age: 46
 /// ";
-/// // Return Vec<Member>, use `.remove(0)` to get the first one
+/// // Return Vec<Member>, see `from_str_single` for the single document
 /// let officer = from_str::<Member>(doc).unwrap().remove(0);
 /// assert_eq!("Bob", officer.name);
 /// assert!(officer.married);
@@ -73,8 +131,126 @@ pub fn from_str<D>(doc: &str) -> Result<Vec<D>, SerdeError>
 where
     D: DeserializeOwned,
 {
-    let root = parse::<RcRepr>(doc).map_err(|e| e.to_string())?;
-    root.into_iter().map(D::deserialize).collect()
+    let root = parse::<RcRepr>(doc)?;
+    root.into_iter()
+        .map(|n| {
+            // The buffered data, such as the tagged enums, are deserialized
+            // by serde, their errors have no position
+            let pos = n.pos();
+            D::deserialize(n).map_err(|e| e.or_pos(pos))
+        })
+        .collect()
+}
+
+/// Parse the document which contains exactly one part, and deserialize it to a
+/// specific type.
+///
+/// An error is returned before deserializing if the document is empty or
+/// split into multiple parts, see [`from_str_opt`] for the optional document.
+///
+/// ```
+/// use yaml_peg::serde::from_str_single;
+///
+/// let n = from_str_single::<Vec<u8>>("[1, 2]").unwrap();
+/// assert_eq!(vec![1, 2], n);
+/// let e = from_str_single::<Vec<u8>>("# empty\n").unwrap_err();
+/// assert_eq!("missing document", e.msg);
+/// let e = from_str_single::<u8>("1\n---\n2\n").unwrap_err();
+/// assert_eq!("expected a single document, found 2", e.msg);
+/// assert_eq!(2, e.pos);
+/// ```
+pub fn from_str_single<D>(doc: &str) -> Result<D, SerdeError>
+where
+    D: DeserializeOwned,
+{
+    from_str_opt(doc)?.ok_or_else(|| SerdeError::from("missing document".to_string()))
+}
+
+/// Parse the document which contains at most one part, and deserialize it to
+/// a specific type.
+///
+/// Return `None` if the document is empty, which only contains the
+/// whitespaces and the comments. The explicit empty document `---` is
+/// deserialized from the null value. An error is returned before
+/// deserializing if the document is split into multiple parts.
+///
+/// ```
+/// use serde::Deserialize;
+/// use yaml_peg::serde::from_str_opt;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     #[serde(default)]
+///     name: String,
+/// }
+///
+/// let config = from_str_opt::<Config>("name: app\n").unwrap().unwrap();
+/// assert_eq!("app", config.name);
+/// assert!(from_str_opt::<Config>("").unwrap().is_none());
+/// assert!(from_str_opt::<Option<Config>>("---\n").unwrap().unwrap().is_none());
+/// ```
+pub fn from_str_opt<D>(doc: &str) -> Result<Option<D>, SerdeError>
+where
+    D: DeserializeOwned,
+{
+    let mut p = Parser::new(doc.as_bytes());
+    p.bom()?;
+    p.gap_flow()?;
+    if p.food().is_empty() {
+        return Ok(None);
+    }
+    let mut loader = Loader::<RcRepr>::new(doc.as_bytes());
+    let mut root = loader.parse()?;
+    if root.len() > 1 {
+        let msg = format!("expected a single document, found {}", root.len());
+        return Err(SerdeError::from(msg).pos(loader.doc_info()[1].pos()));
+    }
+    let n = root.remove(0);
+    let pos = n.pos();
+    D::deserialize(n).map(Some).map_err(|e| e.or_pos(pos))
+}
+
+impl<R: Repr> Node<R> {
+    /// Deserialize the node at the path, see [`Node::get_path_checked`] for the
+    /// path syntax.
+    ///
+    /// This method is useful for extracting a small part of a huge document
+    /// without defining the types for the entire document.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use yaml_peg::{parse, repr::RcRepr};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Container {
+    ///     name: String,
+    ///     image: String,
+    /// }
+    ///
+    /// let doc = "
+    /// spec:
+    ///   containers:
+    ///   - name: web
+    ///     image: nginx
+    ///     ports: [80]
+    /// ";
+    /// let root = parse::<RcRepr>(doc).unwrap().remove(0);
+    /// let c = root.deserialize_path::<Container>("spec.containers[0]").unwrap();
+    /// assert_eq!("nginx", c.image);
+    /// let port = root.deserialize_path::<u16>("spec.containers[0].ports[0]");
+    /// assert_eq!(80, port.unwrap());
+    /// let err = root.deserialize_path::<u16>("spec.volumes").unwrap_err();
+    /// assert_eq!("missing path spec.volumes", err.msg);
+    /// ```
+    pub fn deserialize_path<D>(&self, path: &str) -> Result<D, SerdeError>
+    where
+        D: DeserializeOwned,
+    {
+        let node = self
+            .get_path_checked(path)
+            .map_err(|e| SerdeError::from(format!("missing path {path}")).pos(e.pos))?;
+        D::deserialize(node.clone())
+    }
 }
 
 struct NodeVisitor<R: Repr>(PhantomData<R>);
@@ -90,6 +266,8 @@ impl<'a, R: Repr> Visitor<'a> for NodeVisitor<R> {
         fn visit_bool(bool)
         fn visit_i64(i64)
         fn visit_u64(u64)
+        fn visit_i128(i128)
+        fn visit_u128(u128)
         fn visit_f64(f64)
         fn visit_str(&str)
         fn visit_none
@@ -103,6 +281,23 @@ impl<'a, R: Repr> Visitor<'a> for NodeVisitor<R> {
         Deserialize::deserialize(deserializer)
     }
 
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'a>,
+    {
+        // The tagged node, see `NodeDeserializer::deserialize_newtype_struct`
+        let (tag, v) = data.variant::<String>()?;
+        let node = v.newtype_variant::<Node<R>>()?;
+        Ok(Node::new_repr(node.clone_yaml(), node.pos(), tag))
+    }
+
     fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
     where
         A: SeqAccess<'a>,
@@ -118,7 +313,7 @@ impl<'a, R: Repr> Visitor<'a> for NodeVisitor<R> {
     where
         A: MapAccess<'a>,
     {
-        let mut m = Map::<R>::new();
+        let mut m = Map::<R>::default();
         while let Some((k, v)) = map.next_entry()? {
             m.insert(k, v);
         }
@@ -126,13 +321,7 @@ impl<'a, R: Repr> Visitor<'a> for NodeVisitor<R> {
     }
 }
 
-struct SeqVisitor<R: Repr>(<Seq<R> as IntoIterator>::IntoIter);
-
-impl<R: Repr> From<Seq<R>> for SeqVisitor<R> {
-    fn from(v: Seq<R>) -> Self {
-        Self(v.into_iter())
-    }
-}
+struct SeqVisitor<R: Repr>(<Seq<R> as IntoIterator>::IntoIter, DeserializerOptions);
 
 impl<'a, R: Repr> SeqAccess<'a> for SeqVisitor<R> {
     type Error = SerdeError;
@@ -142,19 +331,22 @@ impl<'a, R: Repr> SeqAccess<'a> for SeqVisitor<R> {
         T: DeserializeSeed<'a>,
     {
         match self.0.next() {
-            Some(e) => seed.deserialize(e).map(Some),
+            Some(e) => {
+                let pos = e.pos();
+                seed.deserialize(NodeDeserializer::with_options(e, self.1))
+                    .map(Some)
+                    .map_err(|e| e.or_pos(pos))
+            }
             None => Ok(None),
         }
     }
 }
 
-struct MapVisitor<R: Repr>(<Map<R> as IntoIterator>::IntoIter, Option<Node<R>>);
-
-impl<R: Repr> From<Map<R>> for MapVisitor<R> {
-    fn from(m: Map<R>) -> Self {
-        Self(m.into_iter(), None)
-    }
-}
+struct MapVisitor<R: Repr>(
+    <Map<R> as IntoIterator>::IntoIter,
+    Option<Node<R>>,
+    DeserializerOptions,
+);
 
 impl<'a, R: Repr> MapAccess<'a> for MapVisitor<R> {
     type Error = SerdeError;
@@ -166,7 +358,7 @@ impl<'a, R: Repr> MapAccess<'a> for MapVisitor<R> {
         match self.0.next() {
             Some((k, v)) => {
                 self.1 = Some(v);
-                seed.deserialize(k).map(Some)
+                seed.deserialize(NodeDeserializer::key(k, self.2)).map(Some)
             }
             None => Ok(None),
         }
@@ -177,13 +369,17 @@ impl<'a, R: Repr> MapAccess<'a> for MapVisitor<R> {
         V: DeserializeSeed<'a>,
     {
         match self.1.take() {
-            Some(v) => seed.deserialize(v),
+            Some(v) => {
+                let pos = v.pos();
+                seed.deserialize(NodeDeserializer::with_options(v, self.2))
+                    .map_err(|e| e.or_pos(pos))
+            }
             None => unreachable!("visit_value called before visit_key"),
         }
     }
 }
 
-struct EnumVisitor<R: Repr>(Node<R>, Option<Node<R>>);
+struct EnumVisitor<R: Repr>(Node<R>, Option<Node<R>>, DeserializerOptions);
 
 impl<'a, R: Repr> EnumAccess<'a> for EnumVisitor<R> {
     type Error = SerdeError;
@@ -193,19 +389,20 @@ impl<'a, R: Repr> EnumAccess<'a> for EnumVisitor<R> {
     where
         V: DeserializeSeed<'a>,
     {
-        let visitor = VariantVisitor(self.1);
-        seed.deserialize(self.0).map(|v| (v, visitor))
+        let visitor = VariantVisitor(self.1, self.2);
+        seed.deserialize(NodeDeserializer::with_options(self.0, self.2))
+            .map(|v| (v, visitor))
     }
 }
 
-struct VariantVisitor<R: Repr>(Option<Node<R>>);
+struct VariantVisitor<R: Repr>(Option<Node<R>>, DeserializerOptions);
 
 impl<'a, R: Repr> VariantAccess<'a> for VariantVisitor<R> {
     type Error = SerdeError;
 
     fn unit_variant(self) -> Result<(), Self::Error> {
         match self.0 {
-            Some(v) => Deserialize::deserialize(v),
+            Some(v) => Deserialize::deserialize(NodeDeserializer::with_options(v, self.1)),
             None => Ok(()),
         }
     }
@@ -215,7 +412,7 @@ impl<'a, R: Repr> VariantAccess<'a> for VariantVisitor<R> {
         T: DeserializeSeed<'a>,
     {
         match self.0 {
-            Some(v) => seed.deserialize(v),
+            Some(v) => seed.deserialize(NodeDeserializer::with_options(v, self.1)),
             None => Err(Error::invalid_type(
                 Unexpected::UnitVariant,
                 &"new type variant",
@@ -229,7 +426,7 @@ impl<'a, R: Repr> VariantAccess<'a> for VariantVisitor<R> {
     {
         match self.0 {
             Some(node) => match node.yaml() {
-                Yaml::Seq(v) => visitor.visit_seq(SeqVisitor::from(v.clone())),
+                Yaml::Seq(v) => visitor.visit_seq(SeqVisitor(v.clone().into_iter(), self.1)),
                 _ => Err(unexpected(&node, "tuple variant")),
             },
             None => Err(Error::invalid_type(
@@ -249,7 +446,7 @@ impl<'a, R: Repr> VariantAccess<'a> for VariantVisitor<R> {
     {
         match self.0 {
             Some(node) => match node.yaml() {
-                Yaml::Map(m) => visitor.visit_map(MapVisitor::from(m.clone())),
+                Yaml::Map(m) => visitor.visit_map(MapVisitor(m.clone().into_iter(), None, self.1)),
                 _ => Err(unexpected(&node, "struct variant")),
             },
             None => Err(Error::invalid_type(
@@ -260,58 +457,332 @@ impl<'a, R: Repr> VariantAccess<'a> for VariantVisitor<R> {
     }
 }
 
+/// The options of the deserializer, the default options are the strict mode.
+///
+/// ```
+/// use serde::Deserialize;
+/// use yaml_peg::{
+///     node,
+///     serde::{DeserializerOptions, NodeDeserializer},
+/// };
+///
+/// let opt = DeserializerOptions::new().lenient_number(true);
+/// let n = i64::deserialize(NodeDeserializer::with_options(node!(3.0), opt)).unwrap();
+/// assert_eq!(3, n);
+/// let err = i64::deserialize(NodeDeserializer::with_options(node!(3.5), opt)).unwrap_err();
+/// assert_eq!(
+///     "invalid value: floating point `3.5`, expected an integer without fractional part",
+///     err.msg
+/// );
+/// assert!(i64::deserialize(node!(3.0)).is_err());
+/// assert_eq!(3., f64::deserialize(node!(3)).unwrap());
+/// ```
+#[derive(Clone, Copy, Default, Debug)]
+pub struct DeserializerOptions {
+    lenient_number: bool,
+    base64_bytes: bool,
+}
+
+impl DeserializerOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow the float without fractional part (e.g., `3.0`) to be
+    /// deserialized as integer.
+    ///
+    /// The integer is always accepted by the float fields.
+    pub fn lenient_number(self, lenient_number: bool) -> Self {
+        Self { lenient_number, ..self }
+    }
+
+    /// Decode the strings as base64 for the bytes fields, such as
+    /// `serde_bytes::ByteBuf`, even if they are not tagged with `!!binary`.
+    ///
+    /// The `!!binary` strings are always decoded.
+    ///
+    /// ```
+    /// use serde::{de::{Deserializer, Visitor}, Deserialize};
+    /// use yaml_peg::{node, serde::{DeserializerOptions, NodeDeserializer}};
+    ///
+    /// # #[derive(Debug, PartialEq)]
+    /// # struct Bytes(Vec<u8>);
+    /// # impl<'a> Deserialize<'a> for Bytes {
+    /// #     fn deserialize<D: Deserializer<'a>>(de: D) -> Result<Self, D::Error> {
+    /// #         struct V;
+    /// #         impl<'a> Visitor<'a> for V {
+    /// #             type Value = Bytes;
+    /// #             fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    /// #                 f.write_str("bytes")
+    /// #             }
+    /// #             fn visit_bytes<E>(self, v: &[u8]) -> Result<Bytes, E> {
+    /// #                 Ok(Bytes(v.to_vec()))
+    /// #             }
+    /// #             fn visit_str<E>(self, v: &str) -> Result<Bytes, E> {
+    /// #                 Ok(Bytes(v.as_bytes().to_vec()))
+    /// #             }
+    /// #         }
+    /// #         de.deserialize_byte_buf(V)
+    /// #     }
+    /// # }
+    /// // `Bytes` is a type like `serde_bytes::ByteBuf`
+    /// let n = node!("aGk=");
+    /// assert_eq!(Bytes(b"aGk=".to_vec()), Bytes::deserialize(n.clone()).unwrap());
+    /// let opt = DeserializerOptions::new().base64_bytes(true);
+    /// let de = NodeDeserializer::with_options(n, opt);
+    /// assert_eq!(Bytes(b"hi".to_vec()), Bytes::deserialize(de).unwrap());
+    /// ```
+    pub fn base64_bytes(self, base64_bytes: bool) -> Self {
+        Self { base64_bytes, ..self }
+    }
+}
+
+/// The deserializer of [`Node`] with options.
+///
+/// The [`Node`] type can be deserialized directly with the default options.
+pub struct NodeDeserializer<R: Repr> {
+    node: Node<R>,
+    opt: DeserializerOptions,
+    key: bool,
+}
+
+impl<R: Repr> NodeDeserializer<R> {
+    /// Create the deserializer with default options.
+    pub fn new(node: Node<R>) -> Self {
+        Self::with_options(node, DeserializerOptions::default())
+    }
+
+    /// Create the deserializer with options.
+    pub fn with_options(node: Node<R>, opt: DeserializerOptions) -> Self {
+        Self { node, opt, key: false }
+    }
+
+    /// Create the deserializer with options, and replace the aliases with the
+    /// anchored nodes before deserializing, see [`anchor_resolve_checked`].
+    ///
+    /// The aliases are kept by the [`parse_cyclic`](crate::parse_cyclic) or
+    /// the loader in cyclic mode, and the anchors map is returned together.
+    ///
+    /// Returns `Err` with the position of the alias if the anchor is missing
+    /// or recursive.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use yaml_peg::{
+    ///     parse_cyclic,
+    ///     repr::RcRepr,
+    ///     serde::{DeserializerOptions, NodeDeserializer},
+    /// };
+    ///
+    /// let (root, anchors) = parse_cyclic::<RcRepr>("[&a 10, *a]").unwrap();
+    /// let root = root[0].clone();
+    /// let err = Vec::<u8>::deserialize(root.clone()).unwrap_err();
+    /// assert!(err.msg.starts_with("unresolved alias *a"));
+    /// assert_eq!(8, err.pos);
+    /// let opt = DeserializerOptions::new();
+    /// let de = NodeDeserializer::with_anchors(root, opt, &anchors[0]).unwrap();
+    /// assert_eq!(vec![10, 10], Vec::<u8>::deserialize(de).unwrap());
+    /// ```
+    pub fn with_anchors(
+        node: Node<R>,
+        opt: DeserializerOptions,
+        anchors: &Anchors<R>,
+    ) -> Result<Self, SerdeError> {
+        let node = anchor_resolve_checked(&node, anchors, usize::MAX).map_err(|e| {
+            SerdeError::from(String::from("missing or recursive anchor")).pos(e.pos)
+        })?;
+        Ok(Self::with_options(node, opt))
+    }
+
+    /// Create the deserializer of the map key, the scalar keys are accepted
+    /// by the string keys.
+    fn key(node: Node<R>, opt: DeserializerOptions) -> Self {
+        Self { node, opt, key: true }
+    }
+
+    fn seq(&self, v: &Seq<R>) -> SeqVisitor<R> {
+        SeqVisitor(v.clone().into_iter(), self.opt)
+    }
+
+    fn map(&self, m: &Map<R>) -> MapVisitor<R> {
+        MapVisitor(m.clone().into_iter(), None, self.opt)
+    }
+
+    /// Parse the number lexeme, raise an error with the node position if
+    /// the lexeme is invalid.
+    fn num<N, T, E>(&self, n: &N, parse: impl Fn(&N) -> Result<T, E>) -> Result<T, SerdeError>
+    where
+        N: AsRef<str> + ?Sized,
+        E: Display,
+    {
+        parse(n).map_err(|e| {
+            let n = n.as_ref();
+            SerdeError::from(format!("invalid number `{n}`: {e}")).pos(self.node.pos())
+        })
+    }
+
+    /// Check the range of the integer, raise an error with the node position
+    /// if overflowed.
+    fn int_range<S, T>(&self, v: S, exp: &dyn Expected) -> Result<T, SerdeError>
+    where
+        S: IntUnexpected,
+        T: TryFrom<S>,
+    {
+        T::try_from(v)
+            .map_err(|_| SerdeError::invalid_value(v.unexpected(), exp).pos(self.node.pos()))
+    }
+
+    /// Convert the float without fractional part into the target integer
+    /// type, so the range of the target type is checked.
+    fn float_to_int<T: FromF64>(&self, n: &str, exp: &dyn Expected) -> Result<T, SerdeError> {
+        let f = self.num(n, to_f64)?;
+        // The floats out of the 64-bit range have no fractional part
+        let range = i64::MIN as f64..i64::MAX as f64;
+        if f.is_nan() || range.contains(&f) && f as i64 as f64 != f {
+            let exp = "an integer without fractional part";
+            Err(SerdeError::invalid_value(Unexpected::Float(f), &exp).pos(self.node.pos()))
+        } else {
+            T::from_f64(f).ok_or_else(|| {
+                SerdeError::invalid_value(Unexpected::Float(f), exp).pos(self.node.pos())
+            })
+        }
+    }
+}
+
+trait FromF64: Sized {
+    /// Convert the float without fractional part, returns `None` if it is
+    /// out of range.
+    fn from_f64(f: f64) -> Option<Self>;
+}
+
+macro_rules! impl_from_f64 {
+    ($($ty:ty),+) => {
+        $(impl FromF64 for $ty {
+            fn from_f64(f: f64) -> Option<Self> {
+                // `MAX as f64` of the wide types is already rounded up to
+                // `MAX + 1`, which is excluded
+                (f >= <$ty>::MIN as f64 && f < <$ty>::MAX as f64 + 1.).then_some(f as $ty)
+            }
+        })+
+    };
+}
+
+impl_from_f64!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128);
+
 impl<'a, R: Repr> Deserialize<'a> for Node<R> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'a>,
     {
-        deserializer.deserialize_any(NodeVisitor(PhantomData))
+        deserializer.deserialize_newtype_struct(NODE_NAME, NodeVisitor(PhantomData))
     }
 }
 
-impl<'a, R: Repr> Deserializer<'a> for Node<R> {
+impl<'a, R: Repr> Deserializer<'a> for NodeDeserializer<R> {
     type Error = SerdeError;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'a>,
     {
-        match self.yaml() {
+        match self.node.yaml() {
             Yaml::Null => visitor.visit_unit(),
             Yaml::Bool(b) => visitor.visit_bool(*b),
-            Yaml::Int(n) => visitor.visit_i64(to_i64(n).unwrap()),
-            Yaml::Float(n) => visitor.visit_f64(to_f64(n).unwrap()),
+            Yaml::Int(n) => match (to_i64(n), to_u64(n)) {
+                (Ok(n), _) => visitor.visit_i64(n),
+                // Out of range of signed integer
+                (_, Ok(n)) => visitor.visit_u64(n),
+                // Out of range of 64-bit integers, the oversized ones are
+                // visited as their lexemes
+                _ => match (to_i128(n), to_u128(n)) {
+                    (Ok(n), _) => visitor.visit_i128(n),
+                    (_, Ok(n)) => visitor.visit_u128(n),
+                    (Err(e), _) if is_overflow(&e) => visitor.visit_str(n),
+                    _ => visitor.visit_u128(self.num(n, to_u128)?),
+                },
+            },
+            Yaml::Float(n) => visitor.visit_f64(self.num(n, to_f64)?),
             Yaml::Str(s) => visitor.visit_str(s),
-            Yaml::Seq(v) => visitor.visit_seq(SeqVisitor::from(v.clone())),
-            Yaml::Map(m) => visitor.visit_map(MapVisitor::from(m.clone())),
-            Yaml::Alias(a) => Err(SerdeError::from(format!("anchor {a}")).pos(self.pos())),
+            Yaml::Seq(v) => visitor.visit_seq(self.seq(v)),
+            Yaml::Map(m) => visitor.visit_map(self.map(m)),
+            Yaml::Alias(a) => Err(alias_error(a, self.node.pos())),
         }
     }
 
     impl_deserializer! {
         fn deserialize_bool(Bool) => visit_bool(v => *v)
-        fn deserialize_i8(Int) => visit_i8(n => to_i64(n).unwrap() as i8)
-        fn deserialize_i16(Int) => visit_i16(n => to_i64(n).unwrap() as i16)
-        fn deserialize_i32(Int) => visit_i32(n => to_i64(n).unwrap() as i32)
-        fn deserialize_i64(Int) => visit_i64(n => to_i64(n).unwrap())
-        fn deserialize_u8(Int) => visit_u8(n => to_i64(n).unwrap() as u8)
-        fn deserialize_u16(Int) => visit_u16(n => to_i64(n).unwrap() as u16)
-        fn deserialize_u32(Int) => visit_u32(n => to_i64(n).unwrap() as u32)
-        fn deserialize_u64(Int) => visit_u64(n => to_i64(n).unwrap() as u64)
-        fn deserialize_f32(Float) => visit_f32(n => to_f64(n).unwrap() as f32)
-        fn deserialize_f64(Float) => visit_f64(n => to_f64(n).unwrap())
-        fn deserialize_str(Str) => visit_str(s => s)
-        fn deserialize_string(Str) => visit_str(s => s)
         fn deserialize_char(Str) => visit_str(s => s)
-        fn deserialize_seq(Seq) => visit_seq(a => SeqVisitor::from(a.clone()))
-        fn deserialize_map(Map) => visit_map(m => MapVisitor::from(m.clone()))
-        fn deserialize_identifier(Str) => visit_str(s => s)
     }
 
-    serde_if_integer128! {
-        impl_deserializer! {
-            fn deserialize_i128(Int) => visit_i128(n => to_i64(n).unwrap() as i128)
-            fn deserialize_u128(Int) => visit_u128(n => to_i64(n).unwrap() as u128)
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        match self.node.yaml() {
+            Yaml::Str(s) => visitor.visit_str(s),
+            // The scalar keys can be used as the string keys
+            Yaml::Bool(b) if self.key => visitor.visit_str(if *b { "true" } else { "false" }),
+            Yaml::Int(n) if self.key => visitor.visit_str(n),
+            Yaml::Float(n) if self.key => visitor.visit_str(n),
+            _ => Err(unexpected(&self.node, visitor)),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        match self.node.yaml() {
+            Yaml::Str(s) => visitor.visit_str(s),
+            // Other keys might be captured by the flattened fields
+            _ if self.key => self.deserialize_any(visitor),
+            _ => Err(unexpected(&self.node, visitor)),
+        }
+    }
+
+    impl_deserializer_int! {
+        fn deserialize_i8 => visit_i8(to_i64 -> i8)
+        fn deserialize_i16 => visit_i16(to_i64 -> i16)
+        fn deserialize_i32 => visit_i32(to_i64 -> i32)
+        fn deserialize_i64 => visit_i64(to_i64 -> i64)
+        fn deserialize_i128 => visit_i128(to_i128 -> i128)
+        fn deserialize_u8 => visit_u8(to_i64 -> u8)
+        fn deserialize_u16 => visit_u16(to_i64 -> u16)
+        fn deserialize_u32 => visit_u32(to_i64 -> u32)
+        fn deserialize_u64 => visit_u64(to_u64 -> u64)
+        fn deserialize_u128 => visit_u128(to_u128 -> u128)
+    }
+
+    impl_deserializer_float! {
+        fn deserialize_f32 => visit_f32(f32)
+        fn deserialize_f64 => visit_f64(f64)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        match self.node.yaml() {
+            Yaml::Seq(v) => visitor.visit_seq(self.seq(v)),
+            _ => Err(unexpected(&self.node, visitor)),
+        }
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        match self.node.yaml() {
+            Yaml::Map(m) => visitor.visit_map(self.map(m)),
+            _ => Err(unexpected(&self.node, visitor)),
         }
     }
 
@@ -326,10 +797,20 @@ impl<'a, R: Repr> Deserializer<'a> for Node<R> {
     where
         V: Visitor<'a>,
     {
-        match self.yaml() {
+        match self.node.yaml() {
+            Yaml::Str(s) if self.opt.base64_bytes || self.node.is_tag("!!binary") => {
+                match decode_base64(s) {
+                    Some(v) => visitor.visit_byte_buf(v),
+                    None => {
+                        let exp = "a base64 string";
+                        Err(SerdeError::invalid_value(Unexpected::Str(s), &exp)
+                            .pos(self.node.pos()))
+                    }
+                }
+            }
             Yaml::Str(s) => visitor.visit_str(s),
-            Yaml::Seq(v) => visitor.visit_seq(&mut SeqVisitor::from(v.clone())),
-            _ => Err(unexpected(&self, visitor)),
+            Yaml::Seq(v) => visitor.visit_seq(&mut self.seq(v)),
+            _ => Err(unexpected(&self.node, visitor)),
         }
     }
 
@@ -337,7 +818,7 @@ impl<'a, R: Repr> Deserializer<'a> for Node<R> {
     where
         V: Visitor<'a>,
     {
-        match self.yaml() {
+        match self.node.yaml() {
             Yaml::Null => visitor.visit_none(),
             _ => visitor.visit_some(self),
         }
@@ -347,10 +828,10 @@ impl<'a, R: Repr> Deserializer<'a> for Node<R> {
     where
         V: Visitor<'a>,
     {
-        if self.is_null() {
+        if self.node.is_null() {
             visitor.visit_unit()
         } else {
-            Err(unexpected(&self, visitor))
+            Err(unexpected(&self.node, visitor))
         }
     }
 
@@ -367,13 +848,23 @@ impl<'a, R: Repr> Deserializer<'a> for Node<R> {
 
     fn deserialize_newtype_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'a>,
     {
-        visitor.visit_newtype_struct(self)
+        let tag = self.node.raw_tag();
+        if name == NODE_NAME && !tag.is_empty() {
+            // Pass the tag as the variant name
+            let node = Node::<R>::new_repr(self.node.clone_yaml(), self.node.pos(), "");
+            visitor.visit_enum(EnumVisitor(Node::from(tag), Some(node), self.opt))
+        } else if let (FOREIGN_NAME, Yaml::Alias(a)) = (name, self.node.yaml()) {
+            // Pass the alias name to the foreign data
+            visitor.visit_str(a)
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
     }
 
     fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
@@ -404,10 +895,10 @@ impl<'a, R: Repr> Deserializer<'a> for Node<R> {
     where
         V: Visitor<'a>,
     {
-        match self.yaml() {
-            Yaml::Seq(v) => visitor.visit_seq(SeqVisitor::from(v.clone())),
-            Yaml::Map(m) => visitor.visit_map(MapVisitor::from(m.clone())),
-            _ => Err(unexpected(&self, visitor)),
+        match self.node.yaml() {
+            Yaml::Seq(v) => visitor.visit_seq(self.seq(v)),
+            Yaml::Map(m) => visitor.visit_map(self.map(m)),
+            _ => Err(unexpected(&self.node, visitor)),
         }
     }
 
@@ -420,10 +911,10 @@ impl<'a, R: Repr> Deserializer<'a> for Node<R> {
     where
         V: Visitor<'a>,
     {
-        let (k, v) = match self.yaml() {
+        let (k, v) = match self.node.yaml() {
             Yaml::Map(m) => {
                 if m.len() != 1 {
-                    return Err(unexpected(&self, "map with single pair"));
+                    return Err(unexpected(&self.node, "map with single pair"));
                 }
                 if let Some((k, v)) = m.into_iter().next() {
                     (k.clone(), Some(v.clone()))
@@ -431,10 +922,10 @@ impl<'a, R: Repr> Deserializer<'a> for Node<R> {
                     unreachable!()
                 }
             }
-            Yaml::Str(_) => (self.clone(), None),
-            _ => return Err(unexpected(&self, visitor)),
+            Yaml::Str(_) => (self.node.clone(), None),
+            _ => return Err(unexpected(&self.node, visitor)),
         };
-        visitor.visit_enum(EnumVisitor(k, v))
+        visitor.visit_enum(EnumVisitor(k, v, self.opt))
     }
 
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -445,17 +936,121 @@ impl<'a, R: Repr> Deserializer<'a> for Node<R> {
     }
 }
 
+impl<'a, R: Repr> Deserializer<'a> for Node<R> {
+    type Error = SerdeError;
+
+    impl_forward! {
+        fn deserialize_any
+        fn deserialize_bool
+        fn deserialize_i8
+        fn deserialize_i16
+        fn deserialize_i32
+        fn deserialize_i64
+        fn deserialize_i128
+        fn deserialize_u8
+        fn deserialize_u16
+        fn deserialize_u32
+        fn deserialize_u64
+        fn deserialize_u128
+        fn deserialize_f32
+        fn deserialize_f64
+        fn deserialize_char
+        fn deserialize_str
+        fn deserialize_string
+        fn deserialize_bytes
+        fn deserialize_byte_buf
+        fn deserialize_option
+        fn deserialize_unit
+        fn deserialize_unit_struct(name: &'static str)
+        fn deserialize_newtype_struct(name: &'static str)
+        fn deserialize_seq
+        fn deserialize_tuple(len: usize)
+        fn deserialize_tuple_struct(name: &'static str, len: usize)
+        fn deserialize_map
+        fn deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        fn deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        fn deserialize_identifier
+        fn deserialize_ignored_any
+    }
+}
+
+trait IntUnexpected: Copy {
+    fn unexpected(self) -> Unexpected<'static>;
+}
+
+impl IntUnexpected for i64 {
+    fn unexpected(self) -> Unexpected<'static> {
+        Unexpected::Signed(self)
+    }
+}
+
+impl IntUnexpected for u64 {
+    fn unexpected(self) -> Unexpected<'static> {
+        Unexpected::Unsigned(self)
+    }
+}
+
+impl IntUnexpected for i128 {
+    fn unexpected(self) -> Unexpected<'static> {
+        match i64::try_from(self) {
+            Ok(n) => Unexpected::Signed(n),
+            Err(_) => Unexpected::Other("128-bit integer"),
+        }
+    }
+}
+
+impl IntUnexpected for u128 {
+    fn unexpected(self) -> Unexpected<'static> {
+        match u64::try_from(self) {
+            Ok(n) => Unexpected::Unsigned(n),
+            Err(_) => Unexpected::Other("128-bit integer"),
+        }
+    }
+}
+
+/// Return true if the integer lexeme is valid but out of range.
+pub(crate) fn is_overflow(e: &core::num::ParseIntError) -> bool {
+    use core::num::IntErrorKind::*;
+    matches!(e.kind(), PosOverflow | NegOverflow)
+}
+
+/// The error of the alias which cannot be deserialized, with the hint for
+/// resolving it.
+#[cold]
+fn alias_error(a: &str, pos: u64) -> SerdeError {
+    let msg = format!(
+        "unresolved alias *{a}, use `parse` to replace the aliases, \
+         or `NodeDeserializer::with_anchors` to resolve them with the anchors map"
+    );
+    SerdeError::from(msg).pos(pos)
+}
+
 #[cold]
 fn unexpected<R: Repr>(node: &Node<R>, exp: impl Expected) -> SerdeError {
+    // The oversized or invalid numbers are shown by their lexemes
+    let lexeme;
     let ty = match node.yaml() {
         Yaml::Null => Unexpected::Unit,
         Yaml::Bool(b) => Unexpected::Bool(*b),
-        Yaml::Int(n) => Unexpected::Signed(to_i64(n).unwrap()),
-        Yaml::Float(n) => Unexpected::Float(to_f64(n).unwrap()),
+        Yaml::Int(n) => match (to_i64(n), to_u64(n)) {
+            (Ok(n), _) => Unexpected::Signed(n),
+            (_, Ok(n)) => Unexpected::Unsigned(n),
+            _ => {
+                lexeme = format!("integer `{}`", n.as_str());
+                Unexpected::Other(&lexeme)
+            }
+        },
+        Yaml::Float(n) => match to_f64(n) {
+            Ok(f) => Unexpected::Float(f),
+            Err(_) => {
+                lexeme = format!("floating point `{}`", n.as_str());
+                Unexpected::Other(&lexeme)
+            }
+        },
         Yaml::Str(s) => Unexpected::Str(s),
         Yaml::Seq(_) => Unexpected::Seq,
         Yaml::Map(_) => Unexpected::Map,
-        Yaml::Alias(_) => Unexpected::Other("anchor"),
+        Yaml::Alias(a) => return alias_error(a, node.pos()),
     };
     SerdeError::invalid_type(ty, &exp).pos(node.pos())
 }
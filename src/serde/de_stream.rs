@@ -0,0 +1,339 @@
+use super::{ser_node::NODE_NAME, DeserializerOptions, NodeDeserializer, SerdeError};
+use crate::{
+    parser::{Loader, PError},
+    repr::{RcRepr, Repr},
+    Node, Yaml,
+};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use serde::{
+    de::{DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    forward_to_deserialize_any, Deserializer,
+};
+
+macro_rules! impl_to_node {
+    ($(fn $method:ident$(($($arg:ident: $ty:ty),+))?)+) => {
+        $(fn $method<V>(mut self, $($($arg: $ty,)+)? visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.node_de()?.$method($($($arg,)+)? visitor)
+        })+
+    };
+}
+
+/// Parse the document and deserialize nodes to a specific type,
+/// without building the entire [`Node`] tree first.
+///
+/// The block sequences and block maps are passed to the visitors item by
+/// item, other nodes (scalars, flow collections and anchored nodes) are still
+/// built as [`Node`] then deserialized.
+///
+/// ```
+/// use serde::Deserialize;
+/// use yaml_peg::serde::from_str_stream;
+///
+/// #[derive(Deserialize)]
+/// struct Member {
+///     name: String,
+///     married: bool,
+///     age: u8,
+/// }
+///
+/// let doc = "
+/// ---
+/// name: Bob
+/// married: true
+/// age: 46
+/// ";
+/// let officer = from_str_stream::<Member>(doc).unwrap().remove(0);
+/// assert_eq!("Bob", officer.name);
+/// assert!(officer.married);
+/// assert_eq!(46, officer.age);
+/// ```
+pub fn from_str_stream<D>(doc: &str) -> Result<Vec<D>, SerdeError>
+where
+    D: DeserializeOwned,
+{
+    let mut loader = Loader::<RcRepr>::new(doc.as_bytes());
+    loader.parse_with(|loader| {
+        loader.doc_start();
+        let d = D::deserialize(StreamDeserializer::new(loader, 0, false, "document"))?;
+        loader.doc_finish();
+        Ok(d)
+    })
+}
+
+enum Peeked<R: Repr> {
+    Seq(u64, String),
+    Map(u64, String, Node<R>),
+    Node(Node<R>),
+}
+
+/// A deserializer drives the visitors from the sub-parsers of [`Loader`].
+///
+/// Please see [`from_str_stream`] for more information.
+pub struct StreamDeserializer<'a, 'b, R: Repr> {
+    loader: &'b mut Loader<'a, R>,
+    level: usize,
+    map: bool,
+    name: &'static str,
+    peeked: Option<Peeked<R>>,
+    opt: DeserializerOptions,
+}
+
+impl<'a, 'b, R: Repr> StreamDeserializer<'a, 'b, R> {
+    /// Create the deserializer from a loader.
+    ///
+    /// The arguments are the same as [`Loader::scalar`], and the `name` is
+    /// the error name if the node is mismatched.
+    pub fn new(loader: &'b mut Loader<'a, R>, level: usize, map: bool, name: &'static str) -> Self {
+        Self {
+            loader,
+            level,
+            map,
+            name,
+            peeked: None,
+            opt: DeserializerOptions::default(),
+        }
+    }
+
+    /// Set the options of the deserializer.
+    pub fn with_options(self, opt: DeserializerOptions) -> Self {
+        Self { opt, ..self }
+    }
+
+    /// Match the node until its type is known.
+    fn peek(&mut self) -> Result<Peeked<R>, SerdeError> {
+        if let Some(peeked) = self.peeked.take() {
+            return Ok(peeked);
+        }
+        let (level, map) = (self.level, self.map);
+        let p = &mut *self.loader;
+        let (anchor, mut tag) = p.props().or_else(|e| e.or(|| p.err(self.name)))?;
+        let key_props = p.is_key_props(&anchor, &tag);
+        let pos = p.indicator();
+        if !anchor.is_empty() {
+            // The anchored node is required to be recorded
+            let yaml = p.scalar_body(level, map, false)?;
+            p.forward();
+            let tag = p.scalar_tag(tag);
+            let node = p.props_node(anchor, tag, key_props, Node::new_repr(yaml, pos, ""))?;
+            return Ok(Peeked::Node(node));
+        }
+        let yaml = if let Ok(s) = p.string_literal(level) {
+            R::new_rc(Yaml::Str(s))
+        } else if let Ok(s) = p.string_folded(level) {
+            R::new_rc(Yaml::Str(s))
+        } else {
+            match p.seq_item(level, map, true) {
+                Ok(_) => return Ok(Peeked::Seq(pos, tag)),
+                Err(PError::Mismatch) => (),
+                Err(e) => return Err(e.into()),
+            }
+            match p.map_key_first(level, map, false) {
+                Ok((k, true)) if key_props => {
+                    let k = Node::new_repr(k.clone_yaml(), k.pos(), tag);
+                    return Ok(Peeked::Map(pos, String::new(), k));
+                }
+                Ok((k, true)) => return Ok(Peeked::Map(pos, tag, k)),
+                Ok((k, false)) => {
+                    if tag.is_empty() {
+                        tag = k.raw_tag().to_string();
+                    }
+                    k.clone_yaml()
+                }
+                Err(PError::Mismatch) => p.scalar_term(level, false)?,
+                Err(e) => return Err(e.into()),
+            }
+        };
+        p.forward();
+        let tag = p.scalar_tag(tag);
+        Ok(Peeked::Node(Node::new_repr(yaml, pos, tag)))
+    }
+
+    /// Match the rest part of the node.
+    fn rest_node(&mut self) -> Result<Node<R>, SerdeError> {
+        let (pos, tag, yaml) = match self.peek()? {
+            Peeked::Seq(pos, tag) => (pos, tag, self.loader.seq_rest(self.level, self.map)?),
+            Peeked::Map(pos, tag, k) => (pos, tag, self.loader.map_rest(self.level, false, k)?),
+            Peeked::Node(n) => return Ok(n),
+        };
+        self.loader.forward();
+        Ok(Node::new_repr(yaml, pos, tag))
+    }
+
+    fn node_de(&mut self) -> Result<NodeDeserializer<R>, SerdeError> {
+        Ok(NodeDeserializer::with_options(self.rest_node()?, self.opt))
+    }
+
+    fn sub(&mut self, map: bool, name: &'static str) -> StreamDeserializer<'a, '_, R> {
+        StreamDeserializer::new(self.loader, self.level + 1, map, name).with_options(self.opt)
+    }
+}
+
+impl<'de, R: Repr> Deserializer<'de> for StreamDeserializer<'_, '_, R> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.peek()? {
+            Peeked::Seq(..) => {
+                let mut seq = SeqStream { de: self, first: true };
+                let v = visitor.visit_seq(&mut seq)?;
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(v)
+            }
+            Peeked::Map(_, _, k) => {
+                let mut map = MapStream { de: self, key: Some(k), value: None };
+                let v = visitor.visit_map(&mut map)?;
+                while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+                Ok(v)
+            }
+            Peeked::Node(n) => NodeDeserializer::with_options(n, self.opt).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.peek()? {
+            Peeked::Node(n) => {
+                NodeDeserializer::with_options(n, self.opt).deserialize_option(visitor)
+            }
+            peeked => {
+                self.peeked = Some(peeked);
+                visitor.visit_some(self)
+            }
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        mut self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if name == NODE_NAME {
+            // Keep the tags of the raw nodes
+            self.node_de()?.deserialize_newtype_struct(name, visitor)
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    impl_to_node! {
+        fn deserialize_bool
+        fn deserialize_i8
+        fn deserialize_i16
+        fn deserialize_i32
+        fn deserialize_i64
+        fn deserialize_i128
+        fn deserialize_u8
+        fn deserialize_u16
+        fn deserialize_u32
+        fn deserialize_u64
+        fn deserialize_u128
+        fn deserialize_f32
+        fn deserialize_f64
+        fn deserialize_char
+        fn deserialize_str
+        fn deserialize_string
+        fn deserialize_bytes
+        fn deserialize_byte_buf
+        fn deserialize_unit
+        fn deserialize_unit_struct(name: &'static str)
+        fn deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        fn deserialize_identifier
+    }
+
+    fn deserialize_ignored_any<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.rest_node()?;
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        seq tuple tuple_struct map struct
+    }
+}
+
+/// The first item is matched by the peeking.
+struct SeqStream<'a, 'b, R: Repr> {
+    de: StreamDeserializer<'a, 'b, R>,
+    first: bool,
+}
+
+impl<'de, R: Repr> SeqAccess<'de> for SeqStream<'_, '_, R> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let de = &mut self.de;
+        if self.first {
+            self.first = false;
+        } else if !de.loader.seq_item(de.level, de.map, false)? {
+            de.loader.forward();
+            return Ok(None);
+        }
+        seed.deserialize(de.sub(false, "sequence item")).map(Some)
+    }
+}
+
+struct MapStream<'a, 'b, R: Repr> {
+    de: StreamDeserializer<'a, 'b, R>,
+    key: Option<Node<R>>,
+    value: Option<u64>,
+}
+
+impl<'de, R: Repr> MapAccess<'de> for MapStream<'_, '_, R> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.value.is_some() {
+            // Skip the value if the visitor ignored it
+            self.next_value::<IgnoredAny>()?;
+        }
+        let k = match self.key.take() {
+            Some(k) => k,
+            None => match self.de.loader.map_key_next(self.de.level, false)? {
+                Some(k) => k,
+                None => {
+                    self.de.loader.forward();
+                    return Ok(None);
+                }
+            },
+        };
+        self.value = Some(k.pos());
+        seed.deserialize(NodeDeserializer::with_options(k, self.de.opt))
+            .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let Some(pos) = self.value.take() else {
+            unreachable!("visit_value called before visit_key");
+        };
+        self.de.loader.forward();
+        self.de.loader.parents.push(pos);
+        let v = seed.deserialize(self.de.sub(true, "map value"))?;
+        self.de.loader.parents.pop();
+        Ok(v)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::parser::PError;
 use alloc::string::{String, ToString};
 use core::fmt::{Debug, Display, Formatter, Result};
 
@@ -6,7 +7,6 @@ use core::fmt::{Debug, Display, Formatter, Result};
 /// If the error is used at deserializing to a custom data,
 /// the field [`SerdeError.pos`] will provide the position of the original YAML
 /// document.
-#[derive(Debug)]
 pub struct SerdeError {
     /// Message.
     pub msg: String,
@@ -14,18 +14,43 @@ pub struct SerdeError {
     ///
     /// If not provided, this field becomes zero.
     pub pos: u64,
+    // Zero is also a valid position
+    has_pos: bool,
 }
 
 impl SerdeError {
-    pub(crate) fn pos(mut self, pos: u64) -> Self {
-        self.pos = pos;
-        self
+    pub(crate) fn pos(self, pos: u64) -> Self {
+        Self { pos, has_pos: true, ..self }
+    }
+
+    /// Set the position if it is not provided.
+    pub(crate) fn or_pos(self, pos: u64) -> Self {
+        if self.has_pos {
+            self
+        } else {
+            self.pos(pos)
+        }
     }
 }
 
 impl From<String> for SerdeError {
     fn from(msg: String) -> Self {
-        Self { msg, pos: 0 }
+        Self { msg, pos: 0, has_pos: false }
+    }
+}
+
+impl From<PError> for SerdeError {
+    fn from(e: PError) -> Self {
+        Self::from(e.to_string()).pos(e.pos())
+    }
+}
+
+impl Debug for SerdeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.debug_struct("SerdeError")
+            .field("msg", &self.msg)
+            .field("pos", &self.pos)
+            .finish()
     }
 }
 
@@ -35,8 +60,14 @@ impl Display for SerdeError {
     }
 }
 
+impl core::error::Error for SerdeError {}
+
 #[cfg(feature = "std")]
-impl std::error::Error for SerdeError {}
+impl From<std::io::Error> for SerdeError {
+    fn from(e: std::io::Error) -> Self {
+        Self::from(e.to_string())
+    }
+}
 
 impl serde::ser::Error for SerdeError {
     fn custom<T: Display>(msg: T) -> Self {
@@ -0,0 +1,157 @@
+use super::SerdeError;
+use crate::{anchor_resolve_checked, parser::Anchors, repr::Repr, Node};
+use alloc::{format, string::String};
+use core::{fmt::Formatter, marker::PhantomData};
+use serde::{
+    de::{DeserializeOwned, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/// The newtype struct name of the foreign data.
+///
+/// The deserializer of this crate visits the alias as a string in this newtype
+/// struct, and the serializer of this crate turns the string into an alias.
+pub(crate) const FOREIGN_NAME: &str = "$yaml_peg::Foreign";
+
+/// A data type that can be an anchor insertion (alias) or the data itself.
+///
+/// The alias is kept by name when deserializing from the nodes, and can be
+/// resolved later by the anchors map of the [`Loader`](crate::parser::Loader)
+/// with [`Foreign::resolve`]. The aliases are replaced by
+/// [`anchor_resolve_checked`] before the conversion.
+///
+/// ```
+/// use serde::Deserialize;
+/// use yaml_peg::{anchors, node, serde::Foreign};
+///
+/// #[derive(Deserialize)]
+/// struct Content {
+///     img: Vec<Foreign<Img>>,
+/// }
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct Img {
+///     src: String,
+/// }
+///
+/// let anchors = anchors!["a" => node!({"src" => "img/1.png"})];
+/// let n = node!({"img" => node!([node!(*"a"), node!({"src" => "img/2.png"})])});
+/// let content = Content::deserialize(n).unwrap();
+/// assert_eq!(Some("a"), content.img[0].anchor());
+/// let img = content
+///     .img
+///     .into_iter()
+///     .map(|img| img.resolve(&anchors))
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!("img/1.png", img[0].src);
+/// assert_eq!("img/2.png", img[1].src);
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub enum Foreign<D> {
+    /// Anchor insertion, the anchor name.
+    Anchor(String),
+    /// The data.
+    Data(D),
+}
+
+impl<D> Foreign<D> {
+    /// Return the anchor name if the data is an anchor insertion.
+    pub fn anchor(&self) -> Option<&str> {
+        match self {
+            Self::Anchor(a) => Some(a),
+            Self::Data(_) => None,
+        }
+    }
+
+    /// Return the data if it is not an anchor insertion.
+    pub fn data(&self) -> Option<&D> {
+        match self {
+            Self::Anchor(_) => None,
+            Self::Data(d) => Some(d),
+        }
+    }
+
+    /// Get the data, or deserialize it from the anchored node.
+    ///
+    /// Returns error if the anchor is missing, recursive, or cannot be
+    /// converted to the data type.
+    pub fn resolve<R: Repr>(self, anchors: &Anchors<R>) -> Result<D, SerdeError>
+    where
+        D: DeserializeOwned,
+    {
+        match self {
+            Self::Anchor(a) => {
+                let n = anchors
+                    .get(&a)
+                    .ok_or_else(|| SerdeError::from(format!("missing anchor {a}")))?;
+                let n = anchor_resolve_checked(n, anchors, usize::MAX)
+                    .map_err(|e| SerdeError::from(format!("recursive anchor {a}")).pos(e.pos))?;
+                D::deserialize(n)
+            }
+            Self::Data(d) => Ok(d),
+        }
+    }
+
+    /// Get the data, or convert the anchored node with the provided function.
+    ///
+    /// The function is only called when the data is an anchor insertion and
+    /// the anchor is found.
+    pub fn visit<'a, R: Repr, F>(self, anchors: &'a Anchors<R>, f: F) -> Result<D, SerdeError>
+    where
+        F: FnOnce(&'a Node<R>) -> Result<D, SerdeError>,
+    {
+        match self {
+            Self::Anchor(a) => match anchors.get(&a) {
+                Some(n) => f(n),
+                None => Err(SerdeError::from(format!("missing anchor {a}"))),
+            },
+            Self::Data(d) => Ok(d),
+        }
+    }
+}
+
+impl<D: Serialize> Serialize for Foreign<D> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Anchor(a) => serializer.serialize_newtype_struct(FOREIGN_NAME, a),
+            Self::Data(d) => d.serialize(serializer),
+        }
+    }
+}
+
+impl<'a, D: Deserialize<'a>> Deserialize<'a> for Foreign<D> {
+    fn deserialize<De>(deserializer: De) -> Result<Self, De::Error>
+    where
+        De: Deserializer<'a>,
+    {
+        struct ForeignVisitor<D>(PhantomData<D>);
+
+        impl<'a, D: Deserialize<'a>> Visitor<'a> for ForeignVisitor<D> {
+            type Value = Foreign<D>;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+                f.write_str("anchor insertion or data")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(Foreign::Anchor(v.into()))
+            }
+
+            fn visit_newtype_struct<De>(self, deserializer: De) -> Result<Self::Value, De::Error>
+            where
+                De: Deserializer<'a>,
+            {
+                D::deserialize(deserializer).map(Foreign::Data)
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(FOREIGN_NAME, ForeignVisitor(PhantomData))
+    }
+}
@@ -31,15 +31,106 @@
 //!
 //! For converting custom data into YAML data, please see [`to_node`] and
 //! [`to_arc_node`], and if you went to parse / dump YAML document, use
-//! [`from_str`] and [`to_string`].
+//! [`from_str`] and [`to_string`]. For the big documents, [`from_str_stream`]
+//! can deserialize the data without building the entire node tree first.
+//! The parts of the document can be deserialized to the different types by
+//! [`from_str_multi`] and [`MultiDoc`], e.g., the front matter formats.
+//! Use [`to_node_with_origins`] to explain the positions of the converted
+//! nodes, e.g., the errors of validating the converted data.
 //!
 //! # Anchors
 //!
 //! [`crate::Yaml::Alias`] is not support serialization.
 //! Please using direct parsing function [`crate::parse`] to avoid the alias
-//! node.
+//! node, or insert the anchored nodes by [`crate::anchor_resolve`] or
+//! [`NodeDeserializer::with_anchors`] before deserialization. The remaining
+//! aliases raise an error with their names and positions. The [`Foreign`]
+//! fields keep the aliases by name, and resolve them later with the anchors
+//! map.
 //!
-//! Cyclic data should be handled manually.
+//! Cyclic data cannot be resolved, it should be handled manually.
+//!
+//! # Map Keys
+//!
+//! The map keys are serialized as nodes, so the non-string keys such as
+//! numbers, tuples and enums are kept as the scalar or complex keys in YAML.
+//! When deserializing, the string keys also accept the number and boolean
+//! keys by their text.
+//!
+//! ```
+//! use std::collections::BTreeMap;
+//! use yaml_peg::serde::{from_str, to_string};
+//!
+//! let mut m = BTreeMap::new();
+//! m.insert((1, 2), "a");
+//! let doc = to_string(&m).unwrap();
+//! assert_eq!("?\n  - 1\n  - 2\n: a\n", doc);
+//! let m = from_str::<BTreeMap<(i32, i32), String>>(&doc).unwrap().remove(0);
+//! assert_eq!("a", m[&(1, 2)]);
+//! let m = from_str::<BTreeMap<String, i32>>("1: 1\ntrue: 2").unwrap().remove(0);
+//! assert_eq!(Some(&2), m.get("true"));
+//! ```
+//!
+//! # Enums
+//!
+//! The externally tagged enums are the single-entry maps `{Variant: value}`,
+//! and the unit variants are the strings. The internally tagged
+//! (`#[serde(tag = "type")]`) and the adjacently tagged
+//! (`#[serde(tag = "t", content = "c")]`) enums are the maps with the tag
+//! fields. The fields of them are buffered by serde, so the lenient options
+//! of [`DeserializerOptions`] are not applied, and the errors inside them are
+//! reported at the position of the map.
+//!
+//! ```
+//! use serde::Deserialize;
+//! use yaml_peg::serde::from_str;
+//!
+//! #[derive(Deserialize, Debug, PartialEq)]
+//! #[serde(tag = "type")]
+//! enum Shape {
+//!     Circle { r: f64 },
+//!     Square { side: f64 },
+//! }
+//!
+//! let doc = "- type: Circle\n  r: 1\n- {type: Square, side: 2.5}\n";
+//! let shapes = from_str::<Vec<Shape>>(doc).unwrap().remove(0);
+//! assert_eq!(shapes, [Shape::Circle { r: 1. }, Shape::Square { side: 2.5 }]);
+//! let e = from_str::<Vec<Shape>>("- type: Circle\n- type: Square\n").unwrap_err();
+//! assert_eq!(e.pos, 2);
+//! ```
+//!
+//! # Raw Nodes
+//!
+//! The [`crate::Node`] fields in the custom data keep the subtree as it is,
+//! including the tags, but the tags are not serialized, so other formats
+//! see the plain values. Use [`Verbatim`] fields to serialize the tags as well,
+//! other formats will see a tagged node as a single-entry map `{tag: value}`.
+//!
+//! A flattened node field captures the unknown fields as a map, and they are
+//! emitted again when serializing, so the extra fields are not discarded.
+//! The tags of the captured values are lost when deserializing, since serde
+//! buffers the flattened fields by itself.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//! use yaml_peg::{
+//!     node,
+//!     serde::{from_str, to_string},
+//!     NodeRc,
+//! };
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Member {
+//!     name: String,
+//!     #[serde(flatten)]
+//!     rest: NodeRc,
+//! }
+//!
+//! let doc = "name: Bob\nage: 46\npets:\n  - cat\n";
+//! let member = from_str::<Member>(doc).unwrap().remove(0);
+//! assert_eq!(node!({"age" => 46, "pets" => node!(["cat"])}), member.rest);
+//! assert_eq!(doc, to_string(&member).unwrap());
+//! ```
 //!
 //! # Mixed String Type
 //!
@@ -77,12 +168,20 @@
 //! assert_eq!("invalid type: integer `84`, expected a boolean", err.msg);
 //! assert_eq!(20, err.pos);
 //! ```
-pub use self::{de::*, error::*, inline_list::*, optional::*, ser::*, stringify::*};
+pub use self::{
+    de::*, de_stream::*, error::*, foreign::*, inline_list::*, multi_doc::*, optional::*, ser::*,
+    stringify::*, verbatim::*,
+};
 
+mod binary;
 mod de;
+mod de_stream;
 mod error;
+mod foreign;
 mod inline_list;
+mod multi_doc;
 mod optional;
 mod ser;
 mod ser_node;
 mod stringify;
+mod verbatim;
@@ -0,0 +1,112 @@
+use super::SerdeError;
+use crate::{parse, repr::RcRepr, NodeRc, Yaml};
+use alloc::{
+    string::ToString,
+    vec::{IntoIter, Vec},
+};
+use serde::de::DeserializeOwned;
+
+/// Parse the document and deserialize its parts as a sequence, so each part
+/// can be a different type, such as the tuples.
+///
+/// The parts are counted by the sequence, use [`MultiDoc`] if the rest of the
+/// parts are the same type.
+///
+/// ```
+/// use serde::Deserialize;
+/// use yaml_peg::serde::from_str_multi;
+///
+/// #[derive(Deserialize)]
+/// struct Header {
+///     title: String,
+/// }
+///
+/// let doc = "title: Report\n---\n[1, 2]\n";
+/// let (header, data) = from_str_multi::<(Header, Vec<u8>)>(doc).unwrap();
+/// assert_eq!("Report", header.title);
+/// assert_eq!(vec![1, 2], data);
+/// ```
+pub fn from_str_multi<D>(doc: &str) -> Result<D, SerdeError>
+where
+    D: DeserializeOwned,
+{
+    let root = parse::<RcRepr>(doc)?;
+    D::deserialize(NodeRc::new(Yaml::Seq(root.into_iter().collect()), 0, ""))
+}
+
+/// The parts of the document, which are deserialized one by one to the
+/// different types.
+///
+/// This is useful for the front matter formats, which have a header part
+/// and many records.
+///
+/// ```
+/// use serde::Deserialize;
+/// use yaml_peg::serde::MultiDoc;
+///
+/// #[derive(Deserialize)]
+/// struct Header {
+///     title: String,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Record {
+///     id: u32,
+/// }
+///
+/// let doc = "title: Log\n---\nid: 1\n---\nid: 2\n";
+/// let mut docs = MultiDoc::new(doc).unwrap();
+/// let header = docs.next_doc::<Header>().unwrap();
+/// assert_eq!("Log", header.title);
+/// assert_eq!(2, docs.len());
+/// let records = docs.rest::<Record>().unwrap();
+/// assert_eq!(vec![1, 2], records.iter().map(|r| r.id).collect::<Vec<_>>());
+/// ```
+#[derive(Clone, Debug)]
+pub struct MultiDoc {
+    docs: IntoIter<NodeRc>,
+    end: u64,
+}
+
+impl MultiDoc {
+    /// Parse the document, the parts are not deserialized yet.
+    pub fn new(doc: &str) -> Result<Self, SerdeError> {
+        let docs = parse::<RcRepr>(doc)?.into_iter();
+        Ok(Self { docs, end: doc.len() as u64 })
+    }
+
+    /// The number of the remaining parts.
+    pub fn len(&self) -> usize {
+        self.docs.len()
+    }
+
+    /// Return true if there is no remaining part.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Deserialize the next part, returns "missing document" error at the
+    /// end of the document if all parts are taken.
+    pub fn next_doc<D>(&mut self) -> Result<D, SerdeError>
+    where
+        D: DeserializeOwned,
+    {
+        match self.docs.next() {
+            Some(n) => deserialize(n),
+            None => Err(SerdeError::from("missing document".to_string()).pos(self.end)),
+        }
+    }
+
+    /// Deserialize the remaining parts to the same type.
+    pub fn rest<D>(self) -> Result<Vec<D>, SerdeError>
+    where
+        D: DeserializeOwned,
+    {
+        self.docs.map(deserialize).collect()
+    }
+}
+
+fn deserialize<D: DeserializeOwned>(n: NodeRc) -> Result<D, SerdeError> {
+    let pos = n.pos();
+    D::deserialize(n).map_err(|e| e.or_pos(pos))
+}
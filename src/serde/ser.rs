@@ -1,13 +1,21 @@
-use super::SerdeError;
-use crate::{dump, node, repr::Repr, Map, Node, NodeArc, NodeRc, Seq};
-use alloc::string::String;
+use super::{foreign::FOREIGN_NAME, ser_node::NODE_NAME, SerdeError};
+use crate::{
+    dumper::{dump_with_options, AnchorNamer, DumpOptions, NullStyle},
+    repr::Repr,
+    Map, Node, NodeArc, NodeRc, Seq, Yaml,
+};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
 use core::marker::PhantomData;
 use serde::{
     ser::{
         SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
         SerializeTupleStruct, SerializeTupleVariant,
     },
-    serde_if_integer128, Serialize, Serializer,
+    Serialize, Serializer,
 };
 
 macro_rules! impl_serializer {
@@ -20,18 +28,9 @@ macro_rules! impl_serializer {
     };
 }
 
-macro_rules! impl_end {
-    (@ $self:ident) => {
-        $self.0.into()
-    };
-    (@map $self:ident) => {
-        node!(@{$self.1 => $self.0})
-    };
-}
-
 macro_rules! impl_seq_serializer {
-    ($(impl $trait:ident for $ty:ident => $method:ident $(($tt:tt))?)+) => {
-        $(impl<R: Repr> $trait for $ty<R> {
+    ($(impl $trait:ident for $ty:ident => $method:ident)+) => {
+        $(impl<R: Repr> $trait for $ty<'_, R> {
             type Ok = Node<R>;
             type Error = SerdeError;
 
@@ -39,20 +38,21 @@ macro_rules! impl_seq_serializer {
             where
                 T: Serialize + ?Sized,
             {
-                self.0.push(value.serialize(NodeSerializer(PhantomData))?);
+                let i = self.0.len();
+                self.0.push(serialize_child(&mut self.1, value, |l| format!("{l}[{i}]"))?);
                 Ok(())
             }
 
             fn end(self) -> Result<Self::Ok, Self::Error> {
-                Ok(impl_end!(@$($tt)? self))
+                Ok(self.end_node())
             }
         })+
     };
 }
 
 macro_rules! impl_map_serializer {
-    ($(impl $trait:ident for $ty:ident $(($tt:tt))?)+) => {
-        $(impl<R: Repr> $trait for $ty<R> {
+    ($(impl $trait:ident for $ty:ident)+) => {
+        $(impl<R: Repr> $trait for $ty<'_, R> {
             type Ok = Node<R>;
             type Error = SerdeError;
 
@@ -60,15 +60,15 @@ macro_rules! impl_map_serializer {
             where
                 T: Serialize + ?Sized,
             {
-                self.0.insert(
-                    key.serialize(NodeSerializer(PhantomData))?,
-                    value.serialize(NodeSerializer(PhantomData))?,
-                );
+                let v = serialize_child(&mut self.1, value, |l| format!("{l}.{key}"))?;
+                let mut k = key.serialize(NodeSerializer::new())?;
+                k.set_pos(v.pos());
+                self.0.insert(k, v);
                 Ok(())
             }
 
             fn end(self) -> Result<Self::Ok, Self::Error> {
-                Ok(impl_end!(@$($tt)? self))
+                Ok(self.end_node())
             }
         })+
     };
@@ -102,7 +102,7 @@ macro_rules! impl_map_serializer {
 /// There is another version for multi-thread reference counter:
 /// [`to_arc_node`].
 pub fn to_node(any: &impl Serialize) -> Result<NodeRc, SerdeError> {
-    any.serialize(NodeSerializer(PhantomData))
+    any.serialize(NodeSerializer::new())
 }
 
 /// Serialize data into [`NodeArc`].
@@ -129,7 +129,93 @@ pub fn to_node(any: &impl Serialize) -> Result<NodeRc, SerdeError> {
 ///
 /// There is another version for single-thread reference counter: [`to_node`].
 pub fn to_arc_node(any: impl Serialize) -> Result<NodeArc, SerdeError> {
-    any.serialize(NodeSerializer(PhantomData))
+    any.serialize(NodeSerializer::new())
+}
+
+/// Same as [`to_node`], but the nodes are given the synthetic positions,
+/// which are recorded in the origins with the labels of the source data.
+///
+/// The origins can be shared by multiple calls, and the errors which refer
+/// to the positions of the nodes can be explained by [`Origins::label`].
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use yaml_peg::serde::{to_node_with_origins, Origins};
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     hosts: Vec<&'static str>,
+///     port: &'static str,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Checked {
+///     #[allow(dead_code)]
+///     hosts: Vec<String>,
+///     #[allow(dead_code)]
+///     port: u16,
+/// }
+///
+/// let config = Config { hosts: vec!["localhost"], port: "http" };
+/// let mut origins = Origins::new(1000);
+/// let n = to_node_with_origins(&config, &mut origins).unwrap();
+/// assert_eq!(origins.label(n.pos()), Some("Config"));
+/// assert_eq!(origins.label(n.get_checked("hosts").unwrap().pos()), Some("Config.hosts"));
+/// let e = Checked::deserialize(n).err().unwrap();
+/// assert_eq!(origins.label(e.pos), Some("Config.port"));
+/// ```
+pub fn to_node_with_origins<T>(any: &T, origins: &mut Origins) -> Result<NodeRc, SerdeError>
+where
+    T: Serialize + ?Sized,
+{
+    let label = core::any::type_name::<T>();
+    let label = label.split('<').next().unwrap_or(label);
+    let label = label.rsplit("::").next().unwrap_or(label);
+    let mut origin = Some(Origin { origins, label: String::new() });
+    serialize_child(&mut origin, any, |_| label.to_string())
+}
+
+/// The origins of the nodes created by [`to_node_with_origins`], a map from
+/// the synthetic positions to the labels of the source data.
+///
+/// The labels are the struct fields (`Foo.field`), the enum variants
+/// (`Foo::Variant`), the sequence items (`Foo.list[0]`) and the map values
+/// (`Foo.map.key`). The root is labeled by its type name, and the map keys
+/// share the positions of their values.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Origins {
+    base: u64,
+    labels: Vec<String>,
+}
+
+impl Origins {
+    /// Create the empty origins, the synthetic positions are started from
+    /// `base`, so they can be separated from the positions of the documents.
+    pub fn new(base: u64) -> Self {
+        Self { base, labels: Vec::new() }
+    }
+
+    /// Get the label of the synthetic position, or `None` if the position is
+    /// not recorded.
+    pub fn label(&self, pos: u64) -> Option<&str> {
+        let i = usize::try_from(pos.checked_sub(self.base)?).ok()?;
+        self.labels.get(i).map(String::as_str)
+    }
+
+    /// The number of the recorded positions.
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Return true if there is no recorded position.
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    fn push(&mut self, label: String) -> u64 {
+        self.labels.push(label);
+        self.base + self.labels.len() as u64 - 1
+    }
 }
 
 /// Serialize data into [`Node`] then dump into string.
@@ -154,21 +240,319 @@ pub fn to_arc_node(any: impl Serialize) -> Result<NodeArc, SerdeError> {
 /// assert_eq!(officer_doc.replace('\n', NL), to_string(&officer).unwrap());
 /// ```
 pub fn to_string(any: &impl Serialize) -> Result<String, SerdeError> {
-    Ok(dump(&[to_node(any)?], &[]))
+    to_string_with_options(any, SerializerOptions::default())
+}
+
+/// The options of [`to_string_with_options`].
+#[derive(Clone, Copy, Default, Debug)]
+pub struct SerializerOptions {
+    anchor_detection: bool,
+    anchor_namer: Option<AnchorNamer>,
+    null_style: NullStyle,
+    skip_null: bool,
+    skip_empty: bool,
+}
+
+impl SerializerOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Dump the repeated collections as aliases of the first one, e.g., the
+    /// data shared by [`Rc`](alloc::rc::Rc) which are serialized by value.
+    ///
+    /// The collections are compared by their content, the empty ones are
+    /// ignored. The anchor names are generated as
+    /// [`DumpOptions::auto_anchor`].
+    pub fn anchor_detection(self, anchor_detection: bool) -> Self {
+        Self { anchor_detection, ..self }
+    }
+
+    /// Generate the anchor names of the [`SerializerOptions::anchor_detection`]
+    /// with the function, e.g., by the field names. See
+    /// [`DumpOptions::anchor_namer`].
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use yaml_peg::serde::{to_string_with_options, SerializerOptions};
+    ///
+    /// #[derive(Serialize)]
+    /// struct Config {
+    ///     base: Vec<u8>,
+    ///     dev: Vec<u8>,
+    ///     test: Vec<u8>,
+    /// }
+    ///
+    /// let config = Config { base: vec![1, 2], dev: vec![1, 2], test: vec![3] };
+    /// let opt = SerializerOptions::new()
+    ///     .anchor_detection(true)
+    ///     .anchor_namer(|key, id| format!("{key}-{id}"));
+    /// let doc = to_string_with_options(&config, opt).unwrap();
+    /// assert!(doc.contains("base: &base-1"));
+    /// assert!(doc.contains("dev: *base-1"));
+    /// ```
+    pub fn anchor_namer(self, namer: AnchorNamer) -> Self {
+        Self { anchor_namer: Some(namer), ..self }
+    }
+
+    /// Set the symbol of the null values, e.g., `None` and `()`. See
+    /// [`DumpOptions::null_style`].
+    pub fn null_style(self, null_style: NullStyle) -> Self {
+        Self { null_style, ..self }
+    }
+
+    /// Omit the map items whose values are null, e.g., the `None` fields,
+    /// instead of writing `key: null`. The null items of the sequences are
+    /// kept.
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use yaml_peg::{
+    ///     dumper::{NullStyle, NL},
+    ///     serde::{to_string_with_options, SerializerOptions},
+    /// };
+    ///
+    /// #[derive(Serialize)]
+    /// struct Task {
+    ///     name: &'static str,
+    ///     owner: Option<&'static str>,
+    ///     tags: Vec<&'static str>,
+    ///     deps: Vec<Option<u8>>,
+    /// }
+    ///
+    /// let task = Task { name: "build", owner: None, tags: vec![], deps: vec![None] };
+    /// let opt = SerializerOptions::new().null_style(NullStyle::Tilde);
+    /// let doc = "name: build\nowner: ~\ntags: []\ndeps:\n  - ~\n";
+    /// assert_eq!(doc.replace('\n', NL), to_string_with_options(&task, opt).unwrap());
+    /// let opt = opt.skip_null(true).skip_empty(true);
+    /// let doc = "name: build\ndeps:\n  - ~\n";
+    /// assert_eq!(doc.replace('\n', NL), to_string_with_options(&task, opt).unwrap());
+    /// ```
+    pub fn skip_null(self, skip_null: bool) -> Self {
+        Self { skip_null, ..self }
+    }
+
+    /// Omit the map items whose values are empty sequences or maps, instead
+    /// of writing `key: []` or `key: {}`. The maps which become empty after
+    /// omitting their items are omitted as well.
+    pub fn skip_empty(self, skip_empty: bool) -> Self {
+        Self { skip_empty, ..self }
+    }
+}
+
+/// Same as [`to_string`], but with the options. See [`SerializerOptions`].
+///
+/// ```
+/// use serde::Serialize;
+/// use yaml_peg::{
+///     dumper::NL,
+///     serde::{to_string_with_options, SerializerOptions},
+/// };
+///
+/// #[derive(Serialize, Clone)]
+/// struct Member {
+///     name: &'static str,
+///     age: u8,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Team {
+///     leader: Member,
+///     members: Vec<Member>,
+/// }
+///
+/// let bob = Member { name: "Bob", age: 46 };
+/// let amy = Member { name: "Amy", age: 30 };
+/// let team = Team { leader: bob.clone(), members: vec![bob, amy] };
+/// let opt = SerializerOptions::new().anchor_detection(true);
+/// let doc = "\
+/// leader: &id001
+///   name: Bob
+///   age: 46
+/// members:
+///   - *id001
+///   - name: Amy
+///     age: 30
+/// ";
+/// assert_eq!(doc.replace('\n', NL), to_string_with_options(&team, opt).unwrap());
+/// ```
+pub fn to_string_with_options(
+    any: &impl Serialize,
+    opt: SerializerOptions,
+) -> Result<String, SerdeError> {
+    let (node, dump_opt) = prepare(any, opt)?;
+    Ok(dump_with_options(&[node], &[], dump_opt))
+}
+
+/// Serialize data and write the YAML document into the writer.
+///
+/// The dumped items are written one by one, see
+/// [`dump_to_writer`](crate::dumper::dump_to_writer).
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use yaml_peg::{dumper::NL, serde::to_writer};
+///
+/// let mut doc = Vec::new();
+/// let m = BTreeMap::from([("a", 1), ("b", 2)]);
+/// to_writer(&mut doc, &m).unwrap();
+/// let ans = "\
+/// a: 1
+/// b: 2
+/// ";
+/// assert_eq!(String::from_utf8(doc).unwrap(), ans.replace('\n', NL));
+/// ```
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+pub fn to_writer(writer: impl std::io::Write, any: &impl Serialize) -> Result<(), SerdeError> {
+    to_writer_with_options(writer, any, SerializerOptions::default())
+}
+
+/// Same as [`to_writer`], but with the options. See [`SerializerOptions`].
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+pub fn to_writer_with_options(
+    writer: impl std::io::Write,
+    any: &impl Serialize,
+    opt: SerializerOptions,
+) -> Result<(), SerdeError> {
+    let (node, dump_opt) = prepare(any, opt)?;
+    crate::dumper::dump_to_writer_with_options(writer, &[node], &[], dump_opt)?;
+    Ok(())
+}
+
+/// Build the node and the dump options for the serialization.
+fn prepare(
+    any: &impl Serialize,
+    opt: SerializerOptions,
+) -> Result<(NodeRc, DumpOptions), SerdeError> {
+    let mut node = to_node(any)?;
+    if opt.skip_null || opt.skip_empty {
+        node = skip_items(&node, &opt);
+    }
+    let mut dump_opt = DumpOptions::new().null_style(opt.null_style);
+    if opt.anchor_detection {
+        node = share_repeated(&node, &mut Map::default());
+        dump_opt = dump_opt.auto_anchor(true);
+        if let Some(namer) = opt.anchor_namer {
+            dump_opt = dump_opt.anchor_namer(namer);
+        }
+    }
+    Ok((node, dump_opt))
+}
+
+/// Remove the map items of the null values or the empty collections, see
+/// [`SerializerOptions::skip_null`] and [`SerializerOptions::skip_empty`].
+fn skip_items<R: Repr>(node: &Node<R>, opt: &SerializerOptions) -> Node<R> {
+    let yaml: Yaml<R> = match node.yaml() {
+        Yaml::Seq(v) => v.iter().map(|n| skip_items(n, opt)).collect(),
+        Yaml::Map(m) => m
+            .iter()
+            .map(|(k, v)| (k.clone(), skip_items(v, opt)))
+            .filter(|(_, v)| match v.yaml() {
+                Yaml::Null => !opt.skip_null,
+                Yaml::Seq(_) | Yaml::Map(_) => !opt.skip_empty || !v.is_empty(),
+                _ => true,
+            })
+            .collect(),
+        _ => return node.clone(),
+    };
+    let mut node = node.clone();
+    node.set_yaml(yaml);
+    node
+}
+
+/// Replace the repeated collections with the first one, so they share the
+/// same data holder. The `seen` map records the first collections.
+fn share_repeated<R: Repr>(node: &Node<R>, seen: &mut Map<R>) -> Node<R> {
+    let yaml: Yaml<R> = match node.yaml() {
+        Yaml::Seq(v) if !v.is_empty() => v.iter().map(|n| share_repeated(n, seen)).collect(),
+        Yaml::Map(m) if !m.is_empty() => m
+            .iter()
+            .map(|(k, v)| (share_repeated(k, seen), share_repeated(v, seen)))
+            .collect(),
+        _ => return node.clone(),
+    };
+    let mut node = node.clone();
+    node.set_yaml(yaml);
+    match seen.get(&node) {
+        Some(n) if n.raw_tag() == node.raw_tag() => n.clone(),
+        Some(_) => node,
+        None => {
+            seen.insert(node.clone(), node.clone());
+            node
+        }
+    }
+}
+
+/// The origin of the node, the origins and the label which is passed to the
+/// children.
+struct Origin<'a> {
+    origins: &'a mut Origins,
+    label: String,
 }
 
-struct NodeSerializer<R: Repr>(PhantomData<R>);
+/// Serialize the child data, and record its origin with the label built from
+/// the parent label if the origins are enabled.
+fn serialize_child<R, T, F>(
+    origin: &mut Option<Origin>,
+    value: &T,
+    label: F,
+) -> Result<Node<R>, SerdeError>
+where
+    R: Repr,
+    T: Serialize + ?Sized,
+    F: FnOnce(&str) -> String,
+{
+    let Some(origin) = origin else {
+        return value.serialize(NodeSerializer::new());
+    };
+    let label = label(&origin.label);
+    let pos = origin.origins.push(label.clone());
+    let origins = &mut *origin.origins;
+    let mut node = value.serialize(NodeSerializer(Some(Origin { origins, label }), PhantomData))?;
+    node.set_pos(pos);
+    Ok(node)
+}
+
+/// Create the map of the enum variant, the data of the tuple variant and the
+/// struct variant are recorded with the variant label.
+fn variant_node<R: Repr>(variant: &'static str, yaml: Yaml<R>, origin: Option<Origin>) -> Node<R> {
+    let mut v = Node::from(yaml);
+    if let Some(origin) = origin {
+        v.set_pos(origin.origins.push(origin.label));
+    }
+    let mut k = Node::from(variant);
+    k.set_pos(v.pos());
+    node!(@{k => v})
+}
+
+struct NodeSerializer<'a, R: Repr>(Option<Origin<'a>>, PhantomData<R>);
+
+impl<R: Repr> NodeSerializer<'_, R> {
+    fn new() -> Self {
+        Self(None, PhantomData)
+    }
 
-impl<R: Repr> Serializer for NodeSerializer<R> {
+    /// Replace the label of the origin, for the children of the structs and
+    /// the enum variants.
+    fn relabel(self, label: impl FnOnce() -> String) -> Self {
+        let origin = self.0.map(|o| Origin { label: label(), ..o });
+        Self(origin, PhantomData)
+    }
+}
+
+impl<'a, R: Repr> Serializer for NodeSerializer<'a, R> {
     type Ok = Node<R>;
     type Error = SerdeError;
-    type SerializeSeq = SeqSerializer<R>;
-    type SerializeTuple = SeqSerializer<R>;
-    type SerializeTupleStruct = SeqSerializer<R>;
-    type SerializeTupleVariant = TupleVariant<R>;
-    type SerializeMap = MapSerializer<R>;
-    type SerializeStruct = StructSerializer<R>;
-    type SerializeStructVariant = StructVariant<R>;
+    type SerializeSeq = SeqSerializer<'a, R>;
+    type SerializeTuple = SeqSerializer<'a, R>;
+    type SerializeTupleStruct = SeqSerializer<'a, R>;
+    type SerializeTupleVariant = TupleVariant<'a, R>;
+    type SerializeMap = MapSerializer<'a, R>;
+    type SerializeStruct = StructSerializer<'a, R>;
+    type SerializeStructVariant = StructVariant<'a, R>;
 
     impl_serializer! {
         fn serialize_bool(bool)
@@ -180,6 +564,8 @@ impl<R: Repr> Serializer for NodeSerializer<R> {
         fn serialize_u16(u16)
         fn serialize_u32(u32)
         fn serialize_u64(u64)
+        fn serialize_i128(i128)
+        fn serialize_u128(u128)
         fn serialize_f32(f32)
         fn serialize_f64(f64)
         fn serialize_char(char)
@@ -188,13 +574,6 @@ impl<R: Repr> Serializer for NodeSerializer<R> {
         fn serialize_unit
     }
 
-    serde_if_integer128! {
-        impl_serializer! {
-            fn serialize_i128(i128)
-            fn serialize_u128(u128)
-        }
-    }
-
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
         Ok(v.iter().map(|b| Node::from(*b)).collect())
     }
@@ -221,18 +600,39 @@ impl<R: Repr> Serializer for NodeSerializer<R> {
 
     fn serialize_newtype_struct<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
         T: Serialize + ?Sized,
     {
-        value.serialize(self)
+        let node = value.serialize(self)?;
+        if name == FOREIGN_NAME {
+            // Turn the anchor name of the foreign data into an alias
+            return match node.yaml() {
+                Yaml::Str(a) => Ok(Node::new(Yaml::Alias(a.to_string()), node.pos(), "")),
+                _ => Ok(node),
+            };
+        }
+        if name != NODE_NAME {
+            return Ok(node);
+        }
+        // Restore the tagged node from `{tag: node}`
+        match node.yaml() {
+            Yaml::Map(m) if m.len() == 1 => {
+                let (tag, node) = m.iter().next().unwrap();
+                match tag.as_str_checked() {
+                    Ok(tag) => Ok(Node::new_repr(node.clone_yaml(), node.pos(), tag)),
+                    Err(_) => Ok(node.clone()),
+                }
+            }
+            _ => Ok(node),
+        }
     }
 
     fn serialize_newtype_variant<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
         variant: &'static str,
         value: &T,
@@ -240,7 +640,11 @@ impl<R: Repr> Serializer for NodeSerializer<R> {
     where
         T: Serialize + ?Sized,
     {
-        Ok(node!(@{variant => value.serialize(NodeSerializer(PhantomData))?}))
+        let mut origin = self.0;
+        let v = serialize_child(&mut origin, value, |_| format!("{name}::{variant}"))?;
+        let mut k = Node::from(variant);
+        k.set_pos(v.pos());
+        Ok(node!(@{k => v}))
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
@@ -248,7 +652,7 @@ impl<R: Repr> Serializer for NodeSerializer<R> {
             Some(n) => Seq::with_capacity(n),
             None => Seq::new(),
         };
-        Ok(SeqSerializer(seq))
+        Ok(SeqSerializer(seq, self.0))
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
@@ -265,62 +669,100 @@ impl<R: Repr> Serializer for NodeSerializer<R> {
 
     fn serialize_tuple_variant(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        Ok(TupleVariant(Seq::with_capacity(len), variant))
+        let ser = self.relabel(|| format!("{name}::{variant}"));
+        Ok(TupleVariant(Seq::with_capacity(len), ser.0, variant))
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        Ok(MapSerializer(
-            match len {
-                Some(n) => Map::with_capacity(n),
-                None => Map::new(),
-            },
-            None,
-        ))
+        let map = match len {
+            Some(n) => Map::with_capacity(n),
+            None => Map::default(),
+        };
+        Ok(MapSerializer(map, self.0, None))
     }
 
     fn serialize_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        Ok(StructSerializer(Map::with_capacity(len)))
+        let ser = self.relabel(|| name.to_string());
+        Ok(StructSerializer(Map::with_capacity(len), ser.0))
     }
 
     fn serialize_struct_variant(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        Ok(StructVariant(Map::with_capacity(len), variant))
+        let ser = self.relabel(|| format!("{name}::{variant}"));
+        Ok(StructVariant(Map::with_capacity(len), ser.0, variant))
+    }
+}
+
+struct SeqSerializer<'a, R: Repr>(Seq<R>, Option<Origin<'a>>);
+struct TupleVariant<'a, R: Repr>(Seq<R>, Option<Origin<'a>>, &'static str);
+struct MapSerializer<'a, R: Repr>(Map<R>, Option<Origin<'a>>, Option<Node<R>>);
+struct StructSerializer<'a, R: Repr>(Map<R>, Option<Origin<'a>>);
+struct StructVariant<'a, R: Repr>(Map<R>, Option<Origin<'a>>, &'static str);
+
+impl<R: Repr> SeqSerializer<'_, R> {
+    fn end_node(self) -> Node<R> {
+        self.0.into()
+    }
+}
+
+impl<R: Repr> TupleVariant<'_, R> {
+    fn end_node(self) -> Node<R> {
+        variant_node(self.2, Yaml::Seq(self.0), self.1)
+    }
+}
+
+impl<R: Repr> StructSerializer<'_, R> {
+    fn end_node(self) -> Node<R> {
+        self.0.into()
     }
 }
 
-struct SeqSerializer<R: Repr>(Seq<R>);
-struct TupleVariant<R: Repr>(Seq<R>, &'static str);
-struct MapSerializer<R: Repr>(Map<R>, Option<Node<R>>);
-struct StructSerializer<R: Repr>(Map<R>);
-struct StructVariant<R: Repr>(Map<R>, &'static str);
+impl<R: Repr> StructVariant<'_, R> {
+    fn end_node(self) -> Node<R> {
+        variant_node(self.2, Yaml::Map(self.0), self.1)
+    }
+}
 
 impl_seq_serializer! {
     impl SerializeSeq for SeqSerializer => serialize_element
     impl SerializeTuple for SeqSerializer => serialize_element
     impl SerializeTupleStruct for SeqSerializer => serialize_field
-    impl SerializeTupleVariant for TupleVariant => serialize_field (map)
+    impl SerializeTupleVariant for TupleVariant => serialize_field
 }
 
 impl_map_serializer! {
     impl SerializeStruct for StructSerializer
-    impl SerializeStructVariant for StructVariant (map)
+    impl SerializeStructVariant for StructVariant
 }
 
-impl<R: Repr> SerializeMap for MapSerializer<R> {
+impl<R: Repr> MapSerializer<'_, R> {
+    fn insert<T>(&mut self, mut k: Node<R>, value: &T) -> Result<(), SerdeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = k.as_value_checked().unwrap_or("?");
+        let v = serialize_child(&mut self.1, value, |l| format!("{l}.{key}"))?;
+        k.set_pos(v.pos());
+        self.0.insert(k, v);
+        Ok(())
+    }
+}
+
+impl<R: Repr> SerializeMap for MapSerializer<'_, R> {
     type Ok = Node<R>;
     type Error = SerdeError;
 
@@ -328,7 +770,7 @@ impl<R: Repr> SerializeMap for MapSerializer<R> {
     where
         T: Serialize + ?Sized,
     {
-        self.1 = Some(key.serialize(NodeSerializer(PhantomData))?);
+        self.2 = Some(key.serialize(NodeSerializer::new())?);
         Ok(())
     }
 
@@ -336,13 +778,10 @@ impl<R: Repr> SerializeMap for MapSerializer<R> {
     where
         T: Serialize + ?Sized,
     {
-        match self.1.take() {
-            Some(k) => self
-                .0
-                .insert(k, value.serialize(NodeSerializer(PhantomData))?),
+        match self.2.take() {
+            Some(k) => self.insert(k, value),
             None => unreachable!("serialize_value called before serialize_key"),
-        };
-        Ok(())
+        }
     }
 
     fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
@@ -350,10 +789,8 @@ impl<R: Repr> SerializeMap for MapSerializer<R> {
         K: Serialize + ?Sized,
         V: Serialize + ?Sized,
     {
-        let k = key.serialize(NodeSerializer(PhantomData))?;
-        let v = value.serialize(NodeSerializer(PhantomData))?;
-        self.0.insert(k, v);
-        Ok(())
+        let k = key.serialize(NodeSerializer::new())?;
+        self.insert(k, value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
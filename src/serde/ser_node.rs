@@ -1,30 +1,115 @@
-use crate::{repr::Repr, to_f64, to_i64, Node, Yaml};
+use super::de::is_overflow;
+use crate::{repr::Repr, to_f64, to_i128, to_i64, to_u128, to_u64, Node, Yaml};
 use alloc::format;
+use core::fmt::Display;
 use serde::{
-    ser::{Error as _, SerializeMap as _},
+    ser::{Error, SerializeMap as _},
     Serialize, Serializer,
 };
 
+/// The newtype struct name of the tagged nodes.
+///
+/// The tagged nodes are serialized as a single-entry map `{tag: node}` in this
+/// newtype struct, so the serializer of this crate can restore the tag. Only
+/// the [`Verbatim`](super::Verbatim) nodes are serialized in this way.
+pub(crate) const NODE_NAME: &str = "$yaml_peg::Node";
+
+/// The node that serializes its tags and the tags of the children.
+pub(crate) struct Tagged<'a, R: Repr>(pub(crate) &'a Node<R>);
+
+impl<R: Repr> Serialize for Tagged<'_, R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let tag = self.0.raw_tag();
+        if tag.is_empty() {
+            self.0.serialize_yaml(serializer, true)
+        } else {
+            serializer.serialize_newtype_struct(NODE_NAME, &TagEntry(tag, self.0))
+        }
+    }
+}
+
+struct TagEntry<'a, R: Repr>(&'a str, &'a Node<R>);
+
+impl<R: Repr> Serialize for TagEntry<'_, R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        struct Untagged<'a, R: Repr>(&'a Node<R>);
+
+        impl<R: Repr> Serialize for Untagged<'_, R> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                self.0.serialize_yaml(serializer, true)
+            }
+        }
+
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.0, &Untagged(self.1))?;
+        map.end()
+    }
+}
+
 impl<R: Repr> Serialize for Node<R> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.serialize_yaml(serializer, false)
+    }
+}
+
+impl<R: Repr> Node<R> {
+    /// Serialize the data, the tags of the children are kept if `tagged` is
+    /// true.
+    fn serialize_yaml<S>(&self, serializer: S, tagged: bool) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
         match self.yaml() {
             Yaml::Null => serializer.serialize_unit(),
             Yaml::Bool(b) => serializer.serialize_bool(*b),
-            Yaml::Int(n) => serializer.serialize_i64(to_i64(n).unwrap()),
-            Yaml::Float(n) => serializer.serialize_f64(to_f64(n).unwrap()),
+            Yaml::Int(n) => match (to_i64(n), to_u64(n)) {
+                (Ok(n), _) => serializer.serialize_i64(n),
+                (_, Ok(n)) => serializer.serialize_u64(n),
+                // Out of range of 64-bit integers, the oversized ones are
+                // serialized as their lexemes
+                _ => match (to_i128(n), to_u128(n)) {
+                    (Ok(n), _) => serializer.serialize_i128(n),
+                    (_, Ok(n)) => serializer.serialize_u128(n),
+                    (Err(e), _) if is_overflow(&e) => serializer.serialize_str(n),
+                    (Err(e), _) => Err(self.invalid_num(n, e)),
+                },
+            },
+            Yaml::Float(n) => match to_f64(n) {
+                Ok(n) => serializer.serialize_f64(n),
+                Err(e) => Err(self.invalid_num(n, e)),
+            },
             Yaml::Str(s) => serializer.serialize_str(s),
+            Yaml::Seq(v) if tagged => serializer.collect_seq(v.iter().map(Tagged)),
             Yaml::Seq(v) => v.serialize(serializer),
             Yaml::Map(m) => {
                 let mut map = serializer.serialize_map(Some(m.len()))?;
                 for (k, v) in m {
-                    map.serialize_entry(k, v)?;
+                    if tagged {
+                        map.serialize_entry(&Tagged(k), &Tagged(v))?;
+                    } else {
+                        map.serialize_entry(k, v)?;
+                    }
                 }
                 map.end()
             }
             Yaml::Alias(a) => Err(S::Error::custom(format!("anchor {a}"))),
         }
     }
+
+    #[cold]
+    fn invalid_num<E: Error>(&self, n: &str, e: impl Display) -> E {
+        E::custom(format!("invalid number `{n}` at {}: {e}", self.pos()))
+    }
 }
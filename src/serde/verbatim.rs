@@ -0,0 +1,100 @@
+use super::ser_node::Tagged;
+use crate::{
+    repr::{RcRepr, Repr},
+    Node,
+};
+use core::{
+    fmt::{Debug, Formatter},
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut},
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A raw node that keeps the tags of the subtree when serializing.
+///
+/// The plain [`Node`] is serialized as its data, so the other formats see the
+/// same values as the parsed documents. This wrapper serializes the tagged
+/// nodes as the single-entry maps `{tag: value}` in a newtype struct instead,
+/// which are restored by the serializer of this crate. The tags are always
+/// kept when deserializing.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use yaml_peg::serde::{from_str, to_string, Verbatim};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct Config {
+///     name: String,
+///     raw: Verbatim,
+/// }
+///
+/// let doc = "name: a\nraw: !point\n  x: !!str 1\n";
+/// let config = from_str::<Config>(doc).unwrap().remove(0);
+/// assert_eq!("point", config.raw.tag());
+/// assert_eq!(doc, to_string(&config).unwrap());
+/// ```
+pub struct Verbatim<R: Repr = RcRepr>(pub Node<R>);
+
+impl<R: Repr> Debug for Verbatim<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("Verbatim").field(&self.0).finish()
+    }
+}
+
+impl<R: Repr> Clone for Verbatim<R> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<R: Repr> Hash for Verbatim<R> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl<R: Repr> PartialEq for Verbatim<R> {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.0 == rhs.0
+    }
+}
+
+impl<R: Repr> Eq for Verbatim<R> {}
+
+impl<R: Repr> From<Node<R>> for Verbatim<R> {
+    fn from(n: Node<R>) -> Self {
+        Self(n)
+    }
+}
+
+impl<R: Repr> Deref for Verbatim<R> {
+    type Target = Node<R>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<R: Repr> DerefMut for Verbatim<R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<R: Repr> Serialize for Verbatim<R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Tagged(&self.0).serialize(serializer)
+    }
+}
+
+impl<'a, R: Repr> Deserialize<'a> for Verbatim<R> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        Node::deserialize(deserializer).map(Self)
+    }
+}
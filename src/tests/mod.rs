@@ -1,4 +1,11 @@
 use crate::{parser::PError, *};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+#[cfg(all(feature = "serde", not(feature = "std")))]
+use alloc::vec;
 
 fn show_err<E>(e: PError) -> E {
     panic!("{}", e)
@@ -42,7 +49,7 @@ fn test_json() {
             "e:f" => "g"
         })
     );
-    let n = node.get("a").unwrap();
+    let n = node.get_checked("a").unwrap();
     assert_eq!(n, &node!("b"));
 }
 
@@ -72,7 +79,7 @@ fn test_yaml() {
                 ]),
             }),
             "-a2" => 4.03,
-            node!(["q", "r", "s"]) => node!({1 => 2, 3 => 4}),
+            node!(["q", "r", "s"]) => node!({1 => 2, "3:4" => ()}),
             "?a3" => node!([
                 node!(["d1🀄🃏", "中文"]),
                 (),
@@ -80,10 +87,10 @@ fn test_yaml() {
                 (),
             ]),
             node!({"a4" => ()}) => -30,
-            node!({"a4" => ()}) => "b3, b4",
+            node!({node!({"a4" => ()}) => "b3, b4"}) => (),
             "test multiline" => node!([
                 node!({
-                    "folded" => "aaa{}[] bbb ccc\nddd\n# eee\n",
+                    "folded" => "aaa{}[] bbb\n  ccc\n\n  ddd\n\n# eee\n",
                     "literal" => "aaa{}[]\nbbb\n  ccc\n\n  ddd\n\n# eee\n",
                 }),
                 node!({
@@ -184,3 +191,2119 @@ fn test_anchor() {
         ])
     );
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_stream() {
+    use crate::serde::from_str_stream;
+    use ::serde::Deserialize;
+
+    for doc in [
+        include_str!("complete_doc.yaml"),
+        include_str!("indent.yaml"),
+        include_str!("anchor.yaml"),
+    ] {
+        let root = parse::<repr::RcRepr>(doc).unwrap_or_else(show_err);
+        assert_eq!(root, from_str_stream::<NodeRc>(doc).unwrap());
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Model {
+        name: String,
+        columns: Option<Vec<(String, u8)>>,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+    const DOC: &str = "\
+- name: a
+  columns:
+  - [x, 1]
+  - - y
+    - 2
+  ignored:
+    k: v
+- name: b
+  columns: ~
+";
+    let models = from_str_stream::<Vec<Model>>(DOC).unwrap().remove(0);
+    assert_eq!(
+        models,
+        vec![
+            Model {
+                name: "a".to_string(),
+                columns: Some(vec![("x".to_string(), 1), ("y".to_string(), 2)]),
+                tags: vec![],
+            },
+            Model { name: "b".to_string(), columns: None, tags: vec![] },
+        ]
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_lenient_number() {
+    use crate::serde::{DeserializerOptions, NodeDeserializer};
+    use ::serde::Deserialize;
+
+    let opt = DeserializerOptions::new().lenient_number(true);
+    let de = |f: f64| NodeDeserializer::with_options(node!(f), opt);
+    assert_eq!(u8::deserialize(de(255.)).unwrap(), 255);
+    assert!(u8::deserialize(de(256.)).is_err());
+    assert!(i32::deserialize(de(1.5)).is_err());
+    assert!(u8::deserialize(NodeDeserializer::new(node!(1.))).is_err());
+    // The range of the target type is checked
+    assert_eq!(u64::deserialize(de(1.8e19)).unwrap(), 18_000_000_000_000_000_000);
+    assert!(u64::deserialize(de(1.9e19)).is_err());
+    assert!(i64::deserialize(de(1.8e19)).unwrap_err().msg.contains("expected i64"));
+    assert_eq!(u128::deserialize(de(1e30)).unwrap(), 1_000_000_000_000_000_019_884_624_838_656);
+    assert_eq!(i128::deserialize(de(-1e30)).unwrap(), -1_000_000_000_000_000_019_884_624_838_656);
+    assert!(i128::deserialize(de(1e40)).is_err());
+    assert!(u64::deserialize(de(-1.)).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_large_unsigned() {
+    use crate::serde::{from_str, to_string};
+    use ::serde::{Deserialize, Serialize};
+
+    #[derive(Deserialize, Serialize, PartialEq, Debug)]
+    #[serde(untagged)]
+    enum Id {
+        Num(u64),
+        Name(String),
+    }
+    const DOC: &str = "[18446744073709551615, -1, 0xff, abc]";
+    let v = from_str::<Vec<u64>>("[18446744073709551615, 0xffffffffffffffff]").unwrap();
+    assert_eq!(v, vec![vec![u64::MAX, u64::MAX]]);
+    let v = from_str::<Vec<NodeRc>>(DOC).unwrap().remove(0);
+    assert_eq!(v[0], node!(u64::MAX));
+    let v = from_str::<(Id, i8, u8, Id)>(DOC).unwrap().remove(0);
+    assert_eq!(v, (Id::Num(u64::MAX), -1, 255, Id::Name("abc".to_string())));
+    assert_eq!(to_string(&node!(u64::MAX)).unwrap().trim_end(), "18446744073709551615");
+    assert_eq!(from_str::<f64>("18446744073709551615\n").unwrap(), vec![18446744073709551615.]);
+}
+
+#[cfg(not(feature = "compact-errors"))]
+#[test]
+fn test_error_parent() {
+    const DOC: &str = "\
+resources:
+  cpu: 1
+  mem: *a
+";
+    let e = parse::<repr::RcRepr>(DOC).unwrap_err();
+    let PError::Terminate { msg, .. } = e else { panic!("{e}") };
+    assert!(msg.contains("within 'mem:' started at line 3"), "{msg}");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_invalid_number() {
+    use crate::serde::to_string;
+    use ::serde::Deserialize;
+
+    let n = NodeRc::new(Yaml::Int("abc".into()), 7, "");
+    let e = i32::deserialize(n.clone()).unwrap_err();
+    assert_eq!(e.pos, 7);
+    assert!(f64::deserialize(n.clone()).is_err());
+    assert!(NodeRc::deserialize(n.clone()).is_err());
+    assert!(to_string(&n).is_err());
+    let n = NodeRc::new(Yaml::Float("1.2.3".into()), 7, "");
+    assert_eq!(f32::deserialize(n.clone()).unwrap_err().pos, 7);
+    assert!(to_string(&n).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_multi_doc() {
+    use crate::serde::{from_str_multi, MultiDoc};
+    use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+    let doc = "title: a\n---\n- 1\n---\n- 2\n- x\n";
+    let (h, a) = from_str_multi::<(BTreeMap<String, String>, Vec<u8>)>("title: a\n---\n[1]\n").unwrap();
+    assert_eq!((h["title"].as_str(), a), ("a", vec![1]));
+    assert!(from_str_multi::<(u8, u8)>("1\n").is_err());
+    let mut docs = MultiDoc::new(doc).unwrap();
+    assert_eq!(docs.next_doc::<BTreeMap<String, String>>().unwrap()["title"], "a");
+    assert_eq!(docs.clone().next_doc::<Vec<u8>>().unwrap(), [1]);
+    let e = docs.clone().rest::<Vec<u8>>().unwrap_err();
+    assert_eq!(e.pos, 27, "{e}");
+    assert_eq!(docs.rest::<Vec<NodeRc>>().unwrap().len(), 2);
+    let mut docs = MultiDoc::new("1\n").unwrap();
+    assert_eq!(docs.next_doc::<u8>().unwrap(), 1);
+    assert!(docs.is_empty());
+    assert_eq!(docs.next_doc::<u8>().unwrap_err().pos, 2);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_single_doc() {
+    use crate::serde::{from_str_opt, from_str_single};
+    assert_eq!(from_str_single::<Vec<u8>>("\u{feff}- 1\n- 2\n").unwrap(), [1, 2]);
+    assert_eq!(from_str_opt::<u8>("# a\n\n  # b\n").unwrap(), None);
+    assert_eq!(from_str_opt::<Option<u8>>("---\n").unwrap(), Some(None));
+    assert_eq!(from_str_single::<u8>("").unwrap_err().msg, "missing document");
+    let e = from_str_opt::<u8>("a: 1\n--- 2\n--- 3\n").unwrap_err();
+    assert_eq!((e.msg.as_str(), e.pos), ("expected a single document, found 3", 5));
+    let e = from_str_single::<u8>("x\n").unwrap_err();
+    assert_eq!(e.pos, 0, "{e}");
+}
+
+#[test]
+fn test_map_backend() {
+    let mut m = node!({"a" => 1, "b" => 2, "c" => 3}).as_map_checked().unwrap();
+    assert_eq!(m.insert(node!("a"), node!(4)), Some(node!(1)));
+    m.retain(|k, _| k != &node!("b"));
+    m.values_mut().for_each(|v| *v = node!(v.as_int_checked().unwrap() * 10));
+    assert_eq!(m.iter().next_back(), Some((&node!("c"), &node!(30))));
+    assert_eq!(NodeRc::from(m.clone()), node!({"a" => 40, "c" => 30}));
+    assert_ne!(NodeRc::from(m.clone()), node!({"c" => 30, "a" => 40}));
+    assert_eq!(NodeRc::from(m.clone()).get_str_checked("c").unwrap(), &node!(30));
+    assert_eq!(m.remove(&node!("a")), Some(node!(40)));
+    assert_eq!((m.len(), m.get(&node!("c"))), (1, Some(&node!(30))));
+    assert!(["btreemap", "indexmap", "ritelinked"].contains(&Map::<repr::RcRepr>::BACKEND));
+}
+
+#[test]
+fn test_tag_handle() {
+    use crate::parser::Loader;
+    let doc = "%TAG !e! tag:example.com,2000:app/\n---\n- !e!foo 1\n- !e!a%20b 2\n- !<x%2Cy> 3\n- !f!g 4\n";
+    let root = parse::<repr::RcRepr>(doc).unwrap_or_else(show_err);
+    let tags = root[0].as_seq_checked().unwrap().iter().map(|n| n.tag().to_string()).collect::<Vec<_>>();
+    assert_eq!(tags, ["tag:example.com,2000:app/foo", "tag:example.com,2000:app/a b", "x,y", "!f!g"]);
+    assert_eq!(root, parse::<repr::RcRepr>(&dump(&root, &[])).unwrap_or_else(show_err));
+    let e = Loader::<repr::RcRepr>::new(doc.as_bytes()).strict_tags(true).parse().unwrap_err();
+    assert!(matches!(e, PError::Terminate { name: "tag handle", .. }), "{e}");
+    let e = parse::<repr::RcRepr>("!a%2 b\n").unwrap_err();
+    assert!(matches!(e, PError::Terminate { name: "tag escape", .. }), "{e}");
+}
+
+#[test]
+fn test_error_context() {
+    let e = parse::<repr::RcRepr>("a:\n  b: {c: 'd' e}\n").unwrap_err();
+    let PError::Terminate { name, context, .. } = &e else { panic!("{e}") };
+    assert_eq!((*name, &**context), ("map terminator", ["doc", "map", "value", "map"].as_slice()));
+    assert!(e.to_string().starts_with("invalid map terminator in doc > map > value > map"), "{e}");
+    let e = parse::<repr::RcRepr>("- a\n- [b, 'c\n").unwrap_err();
+    let PError::Terminate { context, .. } = &e else { panic!("{e}") };
+    assert_eq!(context.join(" > "), "doc > seq > item > flow seq > item");
+    let e = parser::Parser::new(b"@").err::<()>("x").unwrap_err();
+    assert!(matches!(e, PError::Terminate { context, .. } if context.is_empty()));
+}
+
+#[test]
+fn test_grammar() {
+    use crate::parser::{Grammar, Loader, PResult, Parser};
+    use alloc::borrow::Cow;
+    struct Upper;
+    impl Grammar for Upper {
+        fn string_plain<'a>(&self, p: &mut Parser<'a>, level: usize, inner: bool) -> PResult<Cow<'a, str>> {
+            p.string_plain(level, inner).map(|s| Cow::Owned(s.to_uppercase()))
+        }
+        fn string_quoted<'a>(&self, p: &mut Parser<'a>, sym: u8, ignore: &[u8]) -> PResult<Cow<'a, str>> {
+            p.string_quoted(sym, ignore).map(|s| Cow::Owned(format!("<{s}>")))
+        }
+    }
+    let doc = b"a: [b, 'c']\nd: |\n  e\n";
+    let root = Loader::<repr::RcRepr>::new(doc).grammar(Upper).parse().unwrap_or_else(show_err);
+    assert_eq!(root[0], node!({"A" => node!(["B", "<c>"]), "D" => "e\n"}));
+    let root = Loader::<repr::RcRepr>::new(doc).grammar(parser::YamlGrammar).parse().unwrap_or_else(show_err);
+    assert_eq!(root, parse::<repr::RcRepr>(core::str::from_utf8(doc).unwrap()).unwrap());
+}
+
+#[test]
+fn test_dump_anchor_index() {
+    use crate::dumper::{dump_with_options, DumpOptions, NL};
+    let a = node!([1]);
+    let n = node!([a.clone(), node!([1]), a.clone()]);
+    let anchors = anchors!["a" => a];
+    assert_eq!(dump(core::slice::from_ref(&n), core::slice::from_ref(&anchors)), "- &a\n  - 1\n- \n  - 1\n- &a\n  - 1\n".replace('\n', NL));
+    let opt = DumpOptions::new().auto_anchor(true);
+    let doc = dump_with_options(core::slice::from_ref(&n), core::slice::from_ref(&anchors), opt);
+    assert_eq!(doc, "- &a\n  - 1\n- \n  - 1\n- *a\n".replace('\n', NL));
+    let n = node!([node!([1])]);
+    assert_eq!(dump(core::slice::from_ref(&n), &[anchors]), "- \n  - 1\n".replace('\n', NL));
+}
+
+#[test]
+fn test_node_anchor() {
+    use crate::{
+        dumper::{dump_with_options, DumpOptions},
+        parser::{AnchorPolicy, Loader},
+        repr::RcRepr,
+    };
+    let doc = "- &a [1, 2]\n- *a\n- &b {x: *a}\n";
+    let root = parse::<RcRepr>(doc).unwrap_or_else(show_err);
+    assert_eq!(root[0][Ind(0)].anchor(), "a");
+    assert_eq!(root[0][Ind(1)].anchor(), "");
+    assert_eq!(root[0][Ind(2)].anchor(), "b");
+    let dumped = dump(&root, &[]);
+    assert_eq!(dumped.matches("&a").count(), 1, "{dumped}");
+    let dumped = dump_with_options(&root, &[], DumpOptions::new().auto_anchor(true));
+    assert!(dumped.contains("&a") && dumped.matches("*a").count() == 2 && !dumped.contains("id001"), "{dumped}");
+    assert_eq!(parse::<RcRepr>(&dumped).unwrap_or_else(show_err), root);
+    let (root, anchors) = parse_cyclic::<RcRepr>(doc).unwrap_or_else(show_err);
+    assert_eq!(root[0][Ind(0)].anchor(), "a");
+    assert_eq!(anchors[0]["b"].anchor(), "b");
+    assert_eq!(dump(&root, &anchors), "- &a\n  - 1\n  - 2\n- *a\n- &b\n  x: *a\n".replace('\n', dumper::NL));
+    let resolved = anchor_resolve_checked(&root[0], &anchors[0], 2).unwrap();
+    assert_eq!(resolved[Ind(1)].anchor(), "");
+    let mut loader = Loader::<RcRepr>::new(b"- &a 1\n- &a 2\n").anchor_policy(AnchorPolicy::Rename);
+    let root = loader.parse().unwrap_or_else(show_err);
+    assert_eq!(loader.get_anchors()[0]["a-2"].anchor(), "a-2");
+    assert_eq!((root[0][Ind(0)].anchor(), root[0][Ind(1)].anchor()), ("a", "a"));
+}
+
+#[test]
+fn test_alias_tag() {
+    use crate::{parser::Loader, repr::RcRepr};
+    let doc = "- &a !point {x: 1}\n- *a\n- !other *a\n- &b 'q'\n- {*b : *a}\n";
+    let root = Loader::<RcRepr>::new(doc.as_bytes()).alias_props(true).parse().unwrap_or_else(show_err).remove(0);
+    assert_eq!(root[Ind(0)].anchor_name(), None);
+    assert_eq!(root[Ind(1)].anchor_name(), Some("a"));
+    assert_eq!(root[Ind(1)].tag(), "point");
+    assert_eq!(root[Ind(2)].anchor_name(), Some("a"));
+    assert_eq!(root[Ind(2)].tag(), "other");
+    assert_eq!(root[Ind(0)].tag(), "point");
+    let (k, v) = root[Ind(4)].as_map_checked().unwrap().into_iter().next().unwrap();
+    assert_eq!(k.anchor_name(), Some("b"));
+    assert_eq!(v.tag(), "point");
+    let mut loader = Loader::<RcRepr>::new(doc.as_bytes()).alias_props(true).cyclic_mode(true);
+    let root = loader.parse().unwrap_or_else(show_err).remove(0);
+    let root = anchor_resolve_checked(&root, &loader.get_anchors()[0], 2).unwrap();
+    assert_eq!(root[Ind(1)].anchor_name(), Some("a"));
+    assert_eq!(root[Ind(1)].tag(), "point");
+    assert_eq!(root[Ind(2)].tag(), "other");
+    let mut n = root[Ind(1)].clone();
+    n.set_anchor_name("");
+    assert_eq!(n.anchor_name(), None);
+    assert_eq!(n, root[Ind(0)]);
+}
+
+#[test]
+fn test_ind_range() {
+    use crate::repr::RcRepr;
+    let root = parse::<RcRepr>("!list [a, [b, c], d, e]").unwrap_or_else(show_err).remove(0);
+    assert_eq!(root[RevInd(1)], node!("e"));
+    assert_eq!(root[RevInd(3)][RevInd(2)], node!("b"));
+    assert_eq!(root.get_path_checked("[-3][1]").unwrap(), &node!("c"));
+    for ind in [0, 5] {
+        let e = root.get_rev_ind_checked(RevInd(ind)).unwrap_err();
+        assert_eq!(e.kind, NodeErrorKind::OutOfRange);
+        assert!(e.path.is_empty());
+    }
+    assert_eq!(root.get_path_checked("[-0]").unwrap_err().kind, NodeErrorKind::OutOfRange);
+    assert_eq!(root.get_path_checked("[18446744073709551616]").unwrap_err().kind, NodeErrorKind::InvalidPath);
+    assert_eq!(root.get_ind_checked(Ind(4)).unwrap_err().path.to_string(), "[4]");
+    let (start, end) = (1, -1);
+    let n = root.get_range(IndRange(start..end)).unwrap();
+    assert_eq!(n, node!([node!(["b", "c"]), "d"]));
+    assert_eq!(n.tag(), "list");
+    assert!(n[Ind(0)].ptr_eq(&root[Ind(1)]));
+    assert_eq!(root.get_range(IndRange(..)).unwrap(), root);
+    assert_eq!(root.get_range(IndRange(4..)).unwrap(), node!([]));
+    assert_eq!(root.get_range(IndRange((core::ops::Bound::Excluded(-3), core::ops::Bound::Unbounded))).unwrap(), node!(["d", "e"]));
+    assert!(root.get_range(IndRange(-5..)).is_err());
+    assert!(root.get_range(IndRange(end + 4..end + 3)).is_err());
+    assert!(node!({"a" => 1}).get_range(IndRange(..)).is_err());
+}
+
+#[test]
+fn test_get_ci() {
+    use crate::repr::RcRepr;
+    const DOC: &str = "Log-Level: debug\nlog_level: info\nTimeout: 30\n1: one\n";
+    let root = parse::<RcRepr>(DOC).unwrap_or_else(show_err).remove(0);
+    assert_eq!(root.get_ci("timeout").unwrap(), ("Timeout", &node!(30)));
+    assert_eq!(root.get_ci("LOG-LEVEL").unwrap(), ("Log-Level", &node!("debug")));
+    assert_eq!(root.get_normalized("log_level", normalize_key).unwrap(), ("log_level", &node!("info")));
+    assert_eq!(root.get_normalized("LOG LEVEL", normalize_key).unwrap(), ("Log-Level", &node!("debug")));
+    let e = root.get_ci("1").unwrap_err();
+    assert_eq!(e.kind, NodeErrorKind::MissingKey { key: "1".to_string() });
+    assert_eq!(e.pos, 0);
+    assert!(node!([1]).get_ci("a").is_err());
+}
+
+#[test]
+fn test_sort_dedup_seq() {
+    use crate::repr::RcRepr;
+    let root = parse::<RcRepr>("- &x !t [b, a, b, a]\n- *x\n- c").unwrap_or_else(show_err).remove(0);
+    let n = root[Ind(0)].dedup_seq().sort_seq_by(|a, b| a.as_str_checked().unwrap().cmp(b.as_str_checked().unwrap()));
+    assert_eq!(n, node!(["a", "b"]));
+    assert_eq!(n.tag(), "t");
+    assert_eq!(n.pos(), root[Ind(0)].pos());
+    assert_eq!(root[Ind(1)], node!(["b", "a", "b", "a"]));
+    assert!(root[Ind(0)].ptr_eq(&root[Ind(1)]));
+    // Stable sort by length, the other types are kept
+    let n = node!(["bb", "a", "cc", "d"]).sort_seq_by(|a, b| a.as_str_checked().unwrap().len().cmp(&b.as_str_checked().unwrap().len()));
+    assert_eq!(n, node!(["a", "d", "bb", "cc"]));
+    let n = node!({"a" => 1});
+    assert!(n.dedup_seq().ptr_eq(&n));
+    assert!(n.sort_seq_by(|_, _| core::cmp::Ordering::Less).ptr_eq(&n));
+}
+
+#[test]
+fn test_dump_filter() {
+    use crate::dumper::{dump_with_options, DumpOptions, Filter, NL};
+    let shared = node!({"host" => "localhost"});
+    let n = node!({"db" => shared.clone(), "cache" => shared, "keys" => node!(["sk-1", "pk-2", "sk-3"]), "auth" => node!({"token" => node!([1, 2])})});
+    let opt = DumpOptions::new().auto_anchor(true).with_filter(|path, value| match (path.to_string().as_str(), value) {
+        ("auth.token", _) => Filter::Redact("<hidden>"),
+        (_, Some(s)) if s.starts_with("sk-") => Filter::Skip,
+        _ => Filter::Emit,
+    });
+    let ans = "\
+db: &id001
+  host: localhost
+cache: *id001
+keys:
+  - pk-2
+auth:
+  token: <hidden>
+";
+    let root = [n];
+    assert_eq!(dump_with_options(&root, &[], opt), ans.replace('\n', NL));
+    assert_eq!(root[0].get_path_checked("keys[0]").unwrap(), &node!("sk-1"));
+    let opt = DumpOptions::new().with_filter(|_, _| Filter::Redact("***"));
+    assert_eq!(dump_with_options(&root, &[], opt), format!("'***'{NL}"));
+}
+
+#[test]
+fn test_coerce() {
+    use crate::{coerce::{Coercer, Kind}, repr::RcRepr};
+    const DOC: &str = "\
+replicas: '3'
+ratio: \"2\"
+debug: 'False'
+name: !id 42
+empty: ''
+items:
+  - {id: '1', on: 'true'}
+  - {id: '2', on: 'no'}
+";
+    let root = parse::<RcRepr>(DOC).unwrap_or_else(show_err).remove(0);
+    let coercer = Coercer::new().path("replicas", Kind::Int).path("ratio", Kind::Float).path("debug", Kind::Bool).path("name", Kind::Str).path("empty", Kind::Null).path("items[*].id", Kind::Int);
+    let n = coercer.coerce(&root).unwrap();
+    assert_eq!(n.get_checked("replicas").unwrap(), &node!(3));
+    assert_eq!(n.get_checked("ratio").unwrap().as_float_checked().unwrap(), 2.);
+    assert_eq!(n.get_checked("debug").unwrap(), &node!(false));
+    assert_eq!(n.get_checked("name").unwrap(), &node!("42"));
+    assert_eq!(n.get_checked("name").unwrap().tag(), "id");
+    assert_eq!(n.get_checked("name").unwrap().pos(), 50);
+    assert!(n.get_checked("empty").unwrap().is_null());
+    assert_eq!(n.get_path_checked("items[1].id").unwrap(), &node!(2));
+    assert_eq!(n.get_path_checked("items[1].on").unwrap(), &node!("no"));
+    let e = coercer.path("items[*].on", Kind::Bool).coerce(&root).unwrap_err();
+    assert_eq!(e.path.to_string(), "items[1].on");
+    assert_eq!(e.pos, 114);
+    assert_eq!(e.kind, NodeErrorKind::WrongType { expected: "bool", found: "str" });
+    let n = Coercer::new().path("k.*", Kind::Int).coerce(&node!({"k" => node!({"éa" => "3"})})).unwrap();
+    assert_eq!(n.get_path_checked("k.éa").unwrap(), &node!(3));
+    let n = Coercer::new().path("*", Kind::Int).coerce(&node!({"a" => "1", "b" => "2"})).unwrap();
+    assert_eq!(n, node!({"a" => 1, "b" => 2}));
+}
+
+#[test]
+fn test_big_int() {
+    use crate::repr::RcRepr;
+    const DOC: &str = "[123456789012345678901234567890, 0x7fffffffffffffffffffffffffffffff, 1234567890123456789012345678901234567890]";
+    let root = parse::<RcRepr>(DOC).unwrap_or_else(show_err).remove(0);
+    assert_eq!(root[Ind(0)].as_i128_checked().unwrap(), 123456789012345678901234567890);
+    assert_eq!(root[Ind(1)].as_u128_checked().unwrap(), i128::MAX as u128);
+    assert_eq!(root[Ind(0)], node!(123456789012345678901234567890u128));
+    assert_eq!(root[Ind(1)], node!(i128::MAX));
+    assert!(root[Ind(2)].as_u128_checked().is_err());
+    assert_eq!(root[Ind(2)], node!(Yaml::Int("1234567890123456789012345678901234567890".into())));
+    #[cfg(feature = "num-bigint")]
+    assert_eq!(root[Ind(2)].as_big_int().unwrap().to_string(), "1234567890123456789012345678901234567890");
+    #[cfg(feature = "serde")]
+    {
+        use ::serde::Deserialize;
+        assert_eq!(u128::deserialize(root[Ind(0)].clone()).unwrap(), 123456789012345678901234567890);
+        assert!(i64::deserialize(root[Ind(0)].clone()).is_err());
+        assert_eq!(NodeRc::deserialize(root[Ind(0)].clone()).unwrap(), root[Ind(0)]);
+        assert_eq!(serde::to_node(&root[Ind(1)]).unwrap(), root[Ind(1)]);
+        // Out of 128-bit range
+        assert_eq!(NodeRc::deserialize(root[Ind(2)].clone()).unwrap(), node!("1234567890123456789012345678901234567890"));
+        assert_eq!(serde::to_node(&root[Ind(2)]).unwrap(), node!("1234567890123456789012345678901234567890"));
+        // The integers are cast to the float targets
+        assert_eq!(f64::deserialize(root[Ind(0)].clone()).unwrap(), 123456789012345678901234567890.);
+        assert_eq!(serde::from_str::<f64>("-170141183460469231731687303715884105728\n").unwrap(), vec![i128::MIN as f64]);
+        assert_eq!(serde::from_str::<f32>("340282366920938463463374607431768211455\n").unwrap(), vec![u128::MAX as f32]);
+        assert!(f64::deserialize(root[Ind(2)].clone()).is_err());
+    }
+}
+
+#[test]
+fn test_dump_non_finite() {
+    use crate::{dumper::{try_dump_with_options, DumpOptions, NL}, repr::RcRepr};
+    let root = parse::<RcRepr>("a: .nan\nb: [.Inf, -.INF, 1.]\n").unwrap_or_else(show_err);
+    let doc = dump(&root, &[]);
+    assert_eq!(doc, "a: .nan\nb:\n  - .inf\n  - -.inf\n  - 1.\n".replace('\n', NL));
+    assert_eq!(parse::<RcRepr>(&doc).unwrap_or_else(show_err), root);
+    assert_eq!(dump(&[node!({f64::INFINITY => f32::NAN})], &[]), format!(".inf: .nan{NL}"));
+    let opt = DumpOptions::new().finite_only(true);
+    let e = try_dump_with_options(&root, &[], opt).unwrap_err();
+    assert_eq!(e.pos, 3);
+    #[cfg(feature = "std")]
+    {
+        let mut w = Vec::new();
+        let e = crate::dumper::dump_to_writer_with_options(&mut w, &root, &[], opt).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+        assert!(w.is_empty());
+    }
+    assert!(try_dump_with_options(&[node!([1.5])], &[], opt).is_ok());
+}
+
+#[test]
+fn test_comment_tokens() {
+    use crate::parser::Loader;
+    const DOC: &str = "\
+# header
+a: |
+  # literal
+b: 'c # quoted' # trailing
+# d:
+#   - commented out
+e: [f, # flow
+  g]
+";
+    let mut loader = Loader::<repr::RcRepr>::new(DOC.as_bytes());
+    loader.parse().unwrap_or_else(show_err);
+    let comments = loader.comments().iter().map(|c| (&DOC[c.range.start as usize..c.range.end as usize], c.text.as_str())).collect::<Vec<_>>();
+    assert_eq!(comments, [("# header", " header"), ("# trailing", " trailing"), ("# d:", " d:"), ("#   - commented out", "   - commented out"), ("# flow", " flow")]);
+}
+
+#[test]
+fn test_flow_trailing_comma() {
+    use crate::parser::{Loader, PError};
+    const DOC: &str = "\
+seq: [
+  a, # first
+  b,
+  # last
+]
+map: {
+  a: 1, # first
+  b: [c, d,],
+}
+";
+    let root = parse::<repr::RcRepr>(DOC).unwrap_or_else(show_err);
+    assert_eq!(root, [node!({"seq" => node!(["a", "b"]), "map" => node!({"a" => 1, "b" => node!(["c", "d"])})})]);
+    for (doc, name) in [("[a,, b]", "flow sequence item"), ("[,]", "flow sequence item"), ("{a: 1,,}", "flow map key")] {
+        let e = parse::<repr::RcRepr>(doc).unwrap_err();
+        assert!(matches!(e, PError::Terminate { name: n, .. } if n == name), "{doc}");
+    }
+    let root = Loader::<repr::RcRepr>::new(b"[a,, b, ,]: {, a: 1,,}").lenient_flow(true).parse().unwrap_or_else(show_err);
+    assert_eq!(root, [node!({node!(["a", "b"]) => node!({"a" => 1})})]);
+}
+
+#[test]
+fn test_zero_indent_seq() {
+    let seq = || node!(["a", "b"]);
+    for (doc, ans) in [
+        ("key:\n- a\n- b\n", node!({"key" => seq()})),
+        ("key:\n\n# c\n- a\n- b\nk: v\n", node!({"key" => seq(), "k" => "v"})),
+        ("x:\n  y:\n    key:\n    - a\n    - b\n  k: v\n", node!({"x" => node!({"y" => node!({"key" => seq()}), "k" => "v"})})),
+        ("- key:\n  - a\n  - b\n  k: v\n- c\n", node!([node!({"key" => seq(), "k" => "v"}), node!("c")])),
+        ("key:\n- k: v\n  key:\n  - a\n  - b\n", node!({"key" => node!([node!({"k" => "v", "key" => seq()})])})),
+        ("key:\n- - a\n  - b\n", node!({"key" => node!([seq()])})),
+        ("key: &x\n- a\n- b\nk: *x\n", node!({"key" => seq(), "k" => seq()})),
+        ("? key\n:\n- a\n- b\n", node!({"key" => seq()})),
+        ("x:\n  ? key\n  : # c\n  - a\n  - b\n", node!({"x" => node!({"key" => seq()})})),
+        ("?\n- a\n- b\n: v\n", node!({seq() => "v"})),
+        // Not nested in the sequence items
+        ("-\n- a\n", node!([node!(()), node!("a")])),
+    ] {
+        let root = parse::<repr::RcRepr>(doc).unwrap_or_else(show_err);
+        assert_eq!(root, [ans], "{doc}");
+    }
+    for doc in ["key: v\n- a\n", "key:\n  - a\n- b\n", "key:\n- a\n  - b\n"] {
+        assert!(parse::<repr::RcRepr>(doc).is_err(), "{doc}");
+    }
+}
+
+#[test]
+fn test_indent_hint() {
+    use crate::parser::{Loader, PError};
+    const DOC: &str = "\
+-   name: a
+    list:
+        -   x: 1
+            y: 2
+";
+    assert!(parse::<repr::RcRepr>(DOC).is_err());
+    let mut loader = Loader::<repr::RcRepr>::new(DOC.as_bytes()).with_indent_hint(4).strict_indent(true);
+    let root = loader.parse().unwrap_or_else(show_err);
+    assert_eq!(root, [node!([node!({"name" => "a", "list" => node!([node!({"x" => 1, "y" => 2})])})])]);
+    assert_eq!(loader.detected_indent(), [Some(0), None, Some(4)]);
+    let doc = "a:\n  b:\n      c: 1\n";
+    assert!(parse::<repr::RcRepr>(doc).is_ok());
+    let e = Loader::<repr::RcRepr>::new(doc.as_bytes()).strict_indent(true).parse().unwrap_err();
+    assert!(matches!(e, PError::Terminate { name: "inconsistent indentation", pos: 14, .. }));
+    // Not indented sequences are allowed
+    let doc = "a:\n- b:\n    c: 1\n";
+    assert!(Loader::<repr::RcRepr>::new(doc.as_bytes()).strict_indent(true).parse().is_ok());
+}
+
+#[cfg(feature = "proptest")]
+proptest::proptest! {
+    #[test]
+    fn test_round_trip(n in proptest::prelude::any::<NodeRc>()) {
+        use crate::dumper::{dump_with_options, DumpOptions};
+        for opt in [DumpOptions::new(), DumpOptions::new().prefer_flow(40)] {
+            let doc = dump_with_options(core::slice::from_ref(&n), &[], opt);
+            let root = parse::<repr::RcRepr>(&doc).unwrap_or_else(|e| panic!("{e}\n{doc}"));
+            proptest::prop_assert_eq!(root, alloc::vec![n.clone()], "{}", doc);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_dump_to_writer() {
+    use crate::dumper::{dump_to_writer_with_options, dump_with_options, DumpOptions};
+    use std::io::{Error, ErrorKind, Result, Write};
+
+    struct Full(usize);
+    impl Write for Full {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            if self.0 < buf.len() { return Err(Error::new(ErrorKind::WriteZero, "full")); }
+            self.0 -= buf.len();
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> Result<()> { Ok(()) }
+    }
+
+    for doc in [include_str!("complete_doc.yaml"), include_str!("indent.yaml"), include_str!("anchor.yaml")] {
+        let root = parse::<repr::RcRepr>(doc).unwrap_or_else(show_err);
+        for opt in [DumpOptions::new(), DumpOptions::new().auto_anchor(true).sort_keys(true), DumpOptions::new().prefer_flow(20)] {
+            let mut buf = Vec::new();
+            dump_to_writer_with_options(&mut buf, &root, &[], opt).unwrap();
+            assert_eq!(String::from_utf8(buf).unwrap(), dump_with_options(&root, &[], opt));
+        }
+        let e = dump_to_writer_with_options(Full(10), &root, &[], DumpOptions::new()).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::WriteZero);
+    }
+    #[cfg(feature = "serde")]
+    {
+        use crate::serde::{to_string, to_writer};
+        let data = alloc::vec![("a", alloc::vec![1, 2]), ("b", alloc::vec![])];
+        let mut buf = Vec::new();
+        to_writer(&mut buf, &data).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), to_string(&data).unwrap());
+        assert_eq!(to_writer(Full(0), &data).unwrap_err().msg, "full");
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_skip_items() {
+    use crate::{
+        dumper::{NullStyle, NL},
+        serde::{to_string_with_options, SerializerOptions},
+    };
+    use ::serde::Serialize;
+    use alloc::{collections::BTreeMap, vec, vec::Vec};
+
+    #[derive(Serialize)]
+    struct Inner {
+        a: Option<u8>,
+        b: Vec<u8>,
+    }
+
+    #[derive(Serialize)]
+    struct Outer {
+        unit: (),
+        inner: Inner,
+        opt: Option<Inner>,
+        list: Vec<Option<Inner>>,
+        map: BTreeMap<&'static str, Option<u8>>,
+    }
+
+    let mut map = BTreeMap::new();
+    map.insert("x", None);
+    map.insert("y", Some(1));
+    let empty = || Inner { a: None, b: vec![] };
+    let data = Outer { unit: (), inner: empty(), opt: Some(empty()), list: vec![None, Some(empty())], map };
+    let opt = SerializerOptions::new().null_style(NullStyle::Tilde);
+    let doc = "\
+unit: ~
+inner:
+  a: ~
+  b: []
+opt:
+  a: ~
+  b: []
+list:
+  - ~
+  - a: ~
+    b: []
+map:
+  x: ~
+  y: 1
+";
+    assert_eq!(to_string_with_options(&data, opt).unwrap(), doc.replace('\n', NL));
+    let doc = "\
+inner:
+  b: []
+opt:
+  b: []
+list:
+  - ~
+  - b: []
+map:
+  y: 1
+";
+    assert_eq!(to_string_with_options(&data, opt.skip_null(true)).unwrap(), doc.replace('\n', NL));
+    let doc = "list:\n  - null\n  - {}\nmap:\n  y: 1\n";
+    let opt = SerializerOptions::new().skip_null(true).skip_empty(true);
+    assert_eq!(to_string_with_options(&data, opt).unwrap(), doc.replace('\n', NL));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_binary() {
+    use crate::serde::{from_str, DeserializerOptions, NodeDeserializer};
+    use ::serde::{
+        de::{Deserializer, Error, Visitor},
+        Deserialize,
+    };
+    use alloc::vec::Vec;
+
+    #[derive(Debug, PartialEq)]
+    struct Bytes(Vec<u8>);
+
+    impl<'a> Deserialize<'a> for Bytes {
+        fn deserialize<D: Deserializer<'a>>(de: D) -> Result<Self, D::Error> {
+            struct V;
+            impl<'a> Visitor<'a> for V {
+                type Value = Bytes;
+                fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                    f.write_str("bytes")
+                }
+                fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Bytes, E> {
+                    Ok(Bytes(v))
+                }
+                fn visit_str<E: Error>(self, v: &str) -> Result<Bytes, E> {
+                    Ok(Bytes(v.as_bytes().to_vec()))
+                }
+            }
+            de.deserialize_byte_buf(V)
+        }
+    }
+
+    let doc = "\
+- !!binary |
+  R0lGODlhDAAMAIQAAP//9/X
+  17unp5WZmZgAAAOfn515eXg==
+- !!binary Zm9vYg
+- !!binary ''
+- Zm9v
+";
+    let v = from_str::<Vec<Bytes>>(doc).unwrap().remove(0);
+    assert_eq!(&v[0].0[..6], b"GIF89a");
+    assert_eq!(v[0].0.len(), 34);
+    assert_eq!(v[1..], [Bytes(b"foob".to_vec()), Bytes(Vec::new()), Bytes(b"Zm9v".to_vec())]);
+    let opt = DeserializerOptions::new().base64_bytes(true);
+    for (s, ans) in [("Zg==", &b"f"[..]), ("Zm8=", b"fo"), ("Zm9v", b"foo"), ("Zm9v\nYmFy", b"foobar")] {
+        let de = NodeDeserializer::with_options(node!(s), opt);
+        assert_eq!(Bytes::deserialize(de).unwrap().0, ans);
+    }
+    for s in ["Z", "Zg=a", "Zh==", "Zg===", "Z!=="] {
+        let de = NodeDeserializer::with_options(node!(s), opt);
+        let e = Bytes::deserialize(de).unwrap_err();
+        assert!(e.msg.ends_with("expected a base64 string"), "{}", e.msg);
+    }
+    // The sequences of the bytes are still accepted
+    assert_eq!(Bytes::deserialize(node!([1, 2])).ok(), None);
+    let e = from_str::<Vec<Bytes>>("- !!binary 'Zm9v!'\n").unwrap_err();
+    assert_eq!(e.pos, 11);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_unexpected_number() {
+    use crate::serde::from_str;
+    use ::serde::Deserialize;
+    use alloc::{string::String, vec::Vec};
+
+    let e = from_str::<Vec<String>>("[\"a\", 99999999999999999999999]").unwrap_err();
+    assert!(e.msg.starts_with("invalid type: integer `99999999999999999999999`, expected a string"), "{}", e.msg);
+    assert_eq!(e.pos, 6);
+    let e = from_str::<Vec<String>>("[18446744073709551615]").unwrap_err();
+    assert!(e.msg.starts_with("invalid type: integer `18446744073709551615`"), "{}", e.msg);
+    let e = from_str::<Vec<bool>>("[-.inf, .nan]").unwrap_err();
+    assert!(e.msg.starts_with("invalid type: floating point `-inf`"), "{}", e.msg);
+    // The lexemes of the nodes created manually are not checked
+    for (n, lexeme) in [(node!(Yaml::Float(".inf".into())), "floating point `.inf`"), (node!(Yaml::Int("0xzz".into())), "integer `0xzz`")] {
+        let e = String::deserialize(n).unwrap_err();
+        assert!(e.msg.starts_with(&format!("invalid type: {lexeme}")), "{}", e.msg);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_int_range() {
+    use crate::serde::{from_str, DeserializerOptions, NodeDeserializer};
+    use ::serde::Deserialize;
+
+    #[derive(Deserialize, Debug)]
+    struct Member {
+        #[allow(dead_code)]
+        age: u8,
+    }
+    let e = from_str::<Member>("age: 300\n").unwrap_err();
+    assert_eq!(e.msg, "invalid value: integer `300`, expected u8");
+    assert_eq!(e.pos, 5);
+    assert!(from_str::<i8>("-129\n").is_err());
+    assert_eq!(from_str::<i8>("-128\n").unwrap(), vec![i8::MIN]);
+    assert!(from_str::<u32>("-1\n").is_err());
+    assert_eq!(from_str::<u128>("0xff\n").unwrap(), vec![255]);
+    let opt = DeserializerOptions::new().lenient_number(true);
+    let de = NodeDeserializer::with_options(node!(256.), opt);
+    assert!(u8::deserialize(de).is_err());
+}
+
+#[test]
+fn test_dump_auto_anchor() {
+    use crate::dumper::{dump_with_options, DumpOptions};
+    const DOC: &str = include_str!("anchor.yaml");
+    let root = parse::<repr::RcRepr>(DOC).unwrap_or_else(show_err);
+    let doc = dump_with_options(&root, &[], DumpOptions::new().auto_anchor(true));
+    assert_eq!(doc.matches('&').count(), 4);
+    assert_eq!(doc.matches('*').count(), 4);
+    assert_eq!(parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err), root);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_parse_parallel() {
+    for doc in [
+        include_str!("complete_doc.yaml"),
+        include_str!("indent.yaml"),
+        include_str!("anchor.yaml"),
+        "%YAML 1.2\n---\na\n...\n%YAML 1.1\n---\nb\n--- c\n# comment\n---\nd: 1\n",
+        "\u{FEFF}a\n...\n\u{FEFF}---\nb\n",
+    ] {
+        let root = parse::<repr::ArcRepr>(doc).unwrap_or_else(show_err);
+        assert_eq!(parse_parallel(doc).unwrap_or_else(show_err), root);
+    }
+}
+
+#[test]
+fn test_seq_item_props() {
+    const DOC: &str = "\
+- &a !t value
+- !t &b value
+- &c !t
+  key: value
+- &d
+  - 1
+  - 2
+- &e !t key: value
+- [*a, *b, *c, *d, *e]
+";
+    let root = parse::<repr::RcRepr>(DOC).unwrap_or_else(show_err).remove(0);
+    let tags = root.as_seq_checked().unwrap().iter().map(|n| n.tag().to_string()).collect::<Vec<_>>();
+    assert_eq!(tags[..3], ["t", "t", "t"]);
+    assert_eq!(tags[4], "tag:yaml.org,2002:map");
+    let n = &root[Ind(4)];
+    assert_eq!(n.as_map_checked().unwrap().keys().next().unwrap().tag(), "t");
+    assert_eq!(
+        root[Ind(5)],
+        node!([
+            "value",
+            "value",
+            node!({"key" => "value"}),
+            node!([1, 2]),
+            "key",
+        ])
+    );
+}
+
+#[test]
+fn test_scalar_string() {
+    const DOC: &str = "- !!str abc\n- !local abc\n- abc\n";
+    let root = parser::Loader::<repr::RcRepr>::new(DOC.as_bytes()).intern_scalars(true).parse().unwrap_or_else(show_err);
+    let n = &root[0];
+    // The scalars are plain strings no matter which features are enabled
+    for (i, tag) in [(0, "tag:yaml.org,2002:str"), (1, "local"), (2, "tag:yaml.org,2002:str")] {
+        assert_eq!(n[Ind(i)].yaml(), &Yaml::Str(String::from("abc")));
+        assert_eq!(n[Ind(i)].tag(), tag);
+    }
+    assert_eq!(n.clone().into_yaml(), Yaml::Seq((0..3).map(|i| n[Ind(i)].clone()).collect()));
+    assert_eq!(n[Ind(2)].clone().try_into_string_checked(), Ok(String::from("abc")));
+}
+
+#[test]
+fn test_native_num() {
+    let n = node!({"a" => 0x10, "b" => 1.5, "c" => u64::MAX});
+    let doc = dump(core::slice::from_ref(&n), &[]);
+    let root = parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err).remove(0);
+    assert_eq!(root, n);
+    assert_eq!(root.get_checked("a").unwrap().as_int_checked(), Ok(16));
+    assert_eq!(root.get_checked("b").unwrap().as_number_checked(), Ok(1.5));
+    assert_eq!(root.get_checked("c").unwrap().as_value_checked(), Ok("18446744073709551615"));
+    assert!(root.get_checked("c").unwrap().as_int_checked().is_err());
+    // The data types are the same with or without the cached values
+    assert_eq!(node!(Yaml::Int(String::from("0x10"))), node!(16));
+    assert_eq!(node!(Yaml::Int(String::from("0x10"))).as_u64_checked(), Ok(16));
+    assert_ne!(node!(Yaml::Int("abc".into())), node!(16));
+    let mut m = node!({16 => "a"});
+    assert_eq!(m.get_checked(node!(Yaml::Int(String::from("0x10")))), Ok(&node!("a")));
+    m.set_yaml(Yaml::Float(String::from("NaN")));
+    assert_eq!(m, node!(f64::NAN));
+    assert_eq!(node!(Yaml::Float("1.2.3".into())), node!(Yaml::Float("1.2.3".into())));
+    assert_ne!(node!(Yaml::Float("1.2.3".into())), node!(1.2));
+}
+
+#[test]
+fn test_try_normalize() {
+    let n = node!([node!(Yaml::Int("-18446744073709551617".into())), node!(Yaml::Int("0xffffffffffffffffffffffffffffffff".into()))]);
+    let n = n.try_normalize_checked().unwrap();
+    assert_eq!(n[Ind(0)].as_value_checked(), Ok("-18446744073709551617"));
+    assert_eq!(n[Ind(1)].as_value_checked(), Ok("340282366920938463463374607431768211455"));
+    assert_eq!(n, node!([-18446744073709551617i128, u128::MAX]));
+    let e = NodeRc::new(Yaml::Int("0x1ffffffffffffffffffffffffffffffff".into()), 5, "").try_normalize_checked().unwrap_err();
+    assert_eq!((e.kind, e.pos), (NodeErrorKind::OutOfRange, 5));
+    // The metadata of the nodes are kept
+    let mut n = NodeRc::new(Yaml::Int("0o10".into()), 3, "t");
+    n.set_anchor("a");
+    n.set_anchor_name("b");
+    n.set_comment("c");
+    n.set_style(ScalarStyle::DoubleQuoted);
+    let n = node!([n]).try_normalize_checked().unwrap()[Ind(0)].clone();
+    assert_eq!(n.as_value_checked(), Ok("8"));
+    assert_eq!((n.pos(), n.tag(), n.anchor(), n.anchor_name(), n.comment(), n.style()), (3, "t", "a", Some("b"), "c", ScalarStyle::DoubleQuoted));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_raw_node() {
+    use crate::serde::{from_str, from_str_stream, to_node, to_string, Verbatim};
+    use ::serde::{Deserialize, Serialize};
+
+    #[derive(Deserialize, Serialize, PartialEq, Debug)]
+    struct Config {
+        name: String,
+        raw: Verbatim,
+        extra: Option<Verbatim>,
+        missing: Option<Verbatim>,
+    }
+    const DOC: &str = "\
+name: a
+raw: !point
+  x: 1
+  y: !!str 2
+  z: [!t b]
+extra: !t c
+missing: ~
+";
+    let tag = |n: &NodeRc| n.tag().to_string();
+    for c in [from_str::<Config>(DOC).unwrap(), from_str_stream::<Config>(DOC).unwrap()] {
+        let c = &c[0];
+        assert_eq!(tag(&c.raw), "point");
+        assert_eq!(tag(&c.raw["y"]), "tag:yaml.org,2002:str");
+        assert_eq!(tag(&c.raw["z"][Ind(0)]), "t");
+        assert_eq!(tag(c.extra.as_ref().unwrap()), "t");
+        assert_eq!(c.missing, None);
+        let n = to_node(c).unwrap();
+        assert_eq!(tag(&n["raw"]), "point");
+        assert_eq!(tag(&n["raw"]["z"][Ind(0)]), "t");
+        let doc = to_string(c).unwrap();
+        assert_eq!(&from_str::<Config>(&doc).unwrap()[0], c);
+        let root = parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err).remove(0);
+        assert_eq!(tag(&root["raw"]), "point");
+        assert_eq!(tag(&root["raw"]["y"]), "tag:yaml.org,2002:str");
+        assert_eq!(tag(&root["extra"]), "t");
+    }
+    // The plain nodes are serialized without the tags
+    let n = parse::<repr::RcRepr>(DOC).unwrap_or_else(show_err).remove(0);
+    let ans = ::serde_json::json!({"name": "a", "raw": {"x": 1, "y": 2, "z": ["b"]}, "extra": "c", "missing": null});
+    assert_eq!(::serde_json::to_value(&n).unwrap(), ans);
+    assert_eq!(to_string(&n).unwrap(), "name: a\nraw:\n  x: 1\n  y: 2\n  z:\n    - b\nextra: c\nmissing: null\n");
+    let v = ::serde_json::to_value(Verbatim(n["extra"].clone())).unwrap();
+    assert_eq!(v, ::serde_json::json!({"t": "c"}));
+}
+
+#[test]
+fn test_bom() {
+    let root = parse::<repr::RcRepr>("\u{FEFF}a: b\n...\n\u{FEFF}---\n- c\n").unwrap_or_else(show_err);
+    assert_eq!(root, [node!({"a" => "b"}), node!(["c"])]);
+    assert!(parser::Loader::<repr::RcRepr>::new(b"\xFF\xFEa\0").parse().is_err());
+    assert!(parser::decode(b"\xEF\xBB\xBFa").is_ok_and(|s| s == "a"));
+}
+
+#[cfg(feature = "utf16")]
+#[test]
+fn test_utf16() {
+    const DOC: &str = "a: [b, 中文]\n";
+    let le = DOC.encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<_>>();
+    let be = DOC.encode_utf16().flat_map(u16::to_be_bytes).collect::<Vec<_>>();
+    for doc in [
+        [&b"\xFF\xFE"[..], &le].concat(),
+        [&b"\xFE\xFF"[..], &be].concat(),
+        le,
+        be,
+    ] {
+        assert_eq!(parser::decode(&doc).unwrap(), DOC);
+    }
+    assert!(parser::decode(b"\xFF\xFEa").is_err());
+    assert!(parser::decode(b"\xFF\xFE\x00\xDC").is_err());
+}
+
+#[test]
+fn test_number_key() {
+    const DOC: &str = "\
+.nan: 1
+.NaN: 2
+0x10: 3
+16: 4
+-0.0: 5
+0.0: 6
+";
+    let root = parse::<repr::RcRepr>(DOC).unwrap_or_else(show_err).remove(0);
+    let m = root.as_map_checked().unwrap();
+    assert_eq!(m.len(), 3);
+    assert_eq!(m[&node!(f64::NAN)], node!(2));
+    assert_eq!(m[&node!(16)], node!(4));
+    assert_eq!(m[&node!(0.)], node!(6));
+    assert_eq!(root.get_checked(f64::NAN), Ok(&node!(2)));
+}
+
+#[test]
+fn test_crlf() {
+    use crate::dumper::{dump_with_options, DumpOptions, Newline};
+    for doc in [
+        "a: |+\n  x\n\n# c\nb: 1\n",
+        "a: |\n  x\n  y\nb: >-\n  p\n\n\n  q\n",
+        "- \"a\n\n  b\"\n- 'x\n  y'\n- [a, {b: c,\n  d: e}]\n",
+        "a:  # comment\n  - b # c\n  - c\n...\n--- !t\nd\n",
+        "a: \"x\\\n  y\"\nb: \"\\r\\n\"\nc: plain\n  text\n",
+    ] {
+        let lf = parse::<repr::RcRepr>(doc).unwrap_or_else(show_err);
+        let crlf = parse::<repr::RcRepr>(&doc.replace('\n', "\r\n")).unwrap_or_else(show_err);
+        assert_eq!(lf, crlf);
+    }
+    let root = [
+        node!({"a" => "b\nc\n", "d" => "e\r\nf", "g" => node!([1, "h"])}),
+        node!(["i\r", "j"]),
+    ];
+    for newline in [Newline::Lf, Newline::CrLf] {
+        let doc = dump_with_options(&root, &[], DumpOptions::new().newline(newline));
+        assert_eq!(doc.replace("\r\n", "").contains('\n'), newline == Newline::Lf);
+        assert_eq!(parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err), root);
+    }
+}
+
+#[test]
+fn test_escape() {
+    const DOC: &str = r#"
+- "\x41\u4e2d\U0001F004\0\a\v\e\N\_\L\P\/\ \t"
+- "a \
+   b\
+  \  c"
+- "a\n
+  b \t
+  c"
+"#;
+    let root = parse::<repr::RcRepr>(DOC).unwrap_or_else(show_err);
+    let ans = "A中🀄\0\x07\x0B\x1B\u{85}\u{A0}\u{2028}\u{2029}/ \t";
+    assert_eq!(root, [node!([ans, "a b  c", "a\nb \t c"])]);
+    for doc in ["\"\\q\"", "\"\\x4\"", "\"\\uD800\"", "\"a"] {
+        assert!(parse::<repr::RcRepr>(doc).is_err(), "{doc}");
+    }
+    assert_eq!(parser::Parser::unescape(r"a\tb\qc"), "a\tb\\qc");
+}
+
+#[test]
+fn test_escape_for_double_quotes() {
+    use crate::dumper::escape_for_double_quotes;
+    use alloc::string::String;
+
+    let s = (0..0x300).chain([0x2028, 0x2029, 0xD7FF, 0xE000, 0xFEFF, 0xFFFE, 0xFFFF, 0x1F600, 0x10FFFF]).filter_map(char::from_u32).collect::<String>();
+    let escaped = escape_for_double_quotes(&s);
+    assert!(!escaped.contains(['\0', '\x7F', '\u{85}', '\u{FEFF}', '\u{FFFF}', '\n']), "{escaped}");
+    assert!(escaped.contains("\\x7F\\x80") && escaped.contains('😀'), "{escaped}");
+    assert_eq!(parser::Parser::unescape(&escaped), s);
+    let n = node!(s);
+    let doc = dump(core::slice::from_ref(&n), &[]);
+    assert_eq!(parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err), [n]);
+}
+
+#[test]
+fn test_folded() {
+    const DOC: &str = "
+a: >
+
+    folded
+    line
+
+    next
+    line
+      * bullet
+
+      * list
+    last
+    line
+
+b: |
+     literal
+       text
+";
+    let root = parse::<repr::RcRepr>(DOC).unwrap_or_else(show_err);
+    let folded = "\nfolded line\nnext line\n  * bullet\n\n  * list\nlast line\n";
+    assert_eq!(root, [node!({"a" => folded, "b" => "literal\n  text\n"})]);
+}
+
+#[test]
+fn test_literal_round_trip() {
+    let root = [
+        node!({
+            "clip" => "a\n\n\nb\n",
+            "strip" => "a\n  b",
+            "keep" => "a\n\n\n",
+            "single" => "a\n",
+            "leading" => "\n\na\n",
+            "indented" => "  a\nb\n",
+            "nested" => node!(["x\n\ny\n\n", node!({"z" => "1\n2"})]),
+        }),
+        node!("root\n\nscalar\n"),
+    ];
+    let doc = dump(&root, &[]);
+    assert_eq!(parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err), root);
+    assert!(doc.contains("strip: |-\n  a\n    b\n"));
+}
+
+#[test]
+fn test_plain() {
+    for (doc, ans) in [
+        ("a: 12:30:00\n", node!({"a" => "12:30:00"})),
+        ("a: a:b\n", node!({"a" => "a:b"})),
+        ("a:: b\n", node!({"a:" => "b"})),
+        ("a: x#y # c\n", node!({"a" => "x#y"})),
+        ("a: x\t# c\n", node!({"a" => "x"})),
+        ("a: http://x.y:80/z?q#f\n", node!({"a" => "http://x.y:80/z?q#f"})),
+        ("- 0.1 blabla\n- 12:30\n- 0x1f\n- -1.5\n", node!(["0.1 blabla", "12:30", 31, -1.5])),
+        ("- -a\n- :a\n- ?a\n- a[b]{c}\n", node!(["-a", ":a", "?a", "a[b]{c}"])),
+        ("a: x\n  y\n\n  z # c\nb: w\n", node!({"a" => "x y\nz", "b" => "w"})),
+        ("[a:b, http://x.y/z, c: d]\n", node!(["a:b", "http://x.y/z", node!({"c" => "d"})])),
+        ("[x # c\n, y]\n", node!(["x", "y"])),
+        ("{a:b, c: d:e, f}\n", node!({"a:b" => (), "c" => "d:e", "f" => ()})),
+        ("{a: [b]}\n", node!({"a" => node!(["b"])})),
+    ] {
+        assert_eq!(parse::<repr::RcRepr>(doc).unwrap_or_else(show_err), [ans], "{doc}");
+    }
+}
+
+#[test]
+fn test_explicit_key() {
+    const DOC: &str = "\
+? explicit key # Empty value
+? |
+  block key
+: - one # Explicit compact
+  - two # block value
+? - c
+  - d
+:
+  e: f
+? ? x
+  : y
+: z
+a:
+b: c
+";
+    let root = parse::<repr::RcRepr>(DOC).unwrap_or_else(show_err);
+    let ans = node!({
+        "explicit key" => (),
+        "block key\n" => node!(["one", "two"]),
+        node!(["c", "d"]) => node!({"e" => "f"}),
+        node!({"x" => "y"}) => "z",
+        "a" => (),
+        "b" => "c",
+    });
+    assert_eq!(root, [ans]);
+}
+
+#[test]
+fn test_flow_props() {
+    const DOC: &str = "\
+- [!t foo, &a bar, *a, !!str x, !t , &b
+  [c], *b]
+- {&k a: &v b, c: *v, ? !t d : e, f: !u
+  {g: h}}
+";
+    let root = parse::<repr::RcRepr>(DOC).unwrap_or_else(show_err);
+    let ans = node!([
+        node!(["foo", "bar", "bar", "x", (), node!(["c"]), node!(["c"])]),
+        node!({"a" => "b", "c" => "b", "d" => "e", "f" => node!({"g" => "h"})}),
+    ]);
+    assert_eq!(root, [ans]);
+    let seq = root[0][Ind(0)].as_seq_checked().unwrap();
+    assert_eq!(seq[0].tag(), "t");
+    assert_eq!(seq[3].tag(), "tag:yaml.org,2002:str");
+    assert_eq!(seq[4].tag(), "t");
+    let map = root[0][Ind(1)].as_map_checked().unwrap();
+    assert!(map.keys().any(|k| k.tag() == "t"));
+    assert_eq!(map[&node!("f")].tag(), "u");
+}
+
+#[test]
+fn test_max_depth() {
+    // The default depth is safe for the stack of the test threads
+    for (open, close) in [("[", "]"), ("{a: ", "}"), ("[a: ", "]"), ("{", ": b}")] {
+        let doc = format!("{}a{}", open.repeat(64), close.repeat(64));
+        assert!(parse::<repr::RcRepr>(&doc).is_ok(), "{doc}");
+        let doc = format!("{}a{}", open.repeat(65), close.repeat(65));
+        let e = parse::<repr::RcRepr>(&doc).unwrap_err();
+        assert!(e.to_string().contains("nesting depth"), "{doc}");
+    }
+    let doc = format!("{}a{}", "[".repeat(100000), "]".repeat(100000));
+    let e = parse::<repr::RcRepr>(&doc).unwrap_err();
+    assert!(e.to_string().contains("nesting depth"));
+    assert!(parse::<repr::RcRepr>(&("- ".repeat(64) + "a")).is_ok());
+    let doc = "- ".repeat(1000) + "a";
+    let e = parse::<repr::RcRepr>(&doc).unwrap_err();
+    assert!(e.to_string().contains("nesting depth"));
+}
+
+#[test]
+fn test_limits() {
+    use crate::parser::Loader;
+
+    let e = Loader::<repr::RcRepr>::new(b"a\n---\nb\n").max_docs(1).parse().unwrap_err();
+    let PError::LimitExceeded { name, pos, .. } = e else { panic!("{e}") };
+    assert_eq!((name, pos), ("document count", 2));
+    let e = Loader::<repr::RcRepr>::new(b"{a: 'bcd'}").max_scalar_bytes(3).parse().unwrap_err();
+    assert!(e.to_string().starts_with("scalar bytes limit exceeded"), "{e}");
+    let e = Loader::<repr::RcRepr>::new(b"- - a").max_depth(1).parse().unwrap_err();
+    assert!(matches!(e, PError::LimitExceeded { name: "nesting depth", .. }));
+}
+
+#[test]
+fn test_get_str() {
+    let root = parse::<repr::RcRepr>("a: 1\n'b': 2\n\"c\\td\": 3\n4: 5\n").unwrap();
+    for k in ["a", "b", "c\td", "4", "e"] {
+        assert_eq!(root[0].get_str_checked(k), root[0].get_checked(k));
+    }
+    assert_eq!(root[0].get_str_checked("b"), Ok(&node!(2)));
+}
+
+#[test]
+fn test_dump_sort_keys() {
+    use crate::dumper::{dump_with_options, DumpOptions};
+
+    let shared = node!({"y" => 1, "x" => 2});
+    let n = node!({"b" => shared.clone(), "a" => node!([shared])});
+    let opt = DumpOptions::new().auto_anchor(true).sort_keys(true);
+    let doc = dump_with_options(&[n], &[], opt);
+    const ANS: &str = "\
+a:
+  - &id001
+    x: 2
+    y: 1
+b: *id001
+";
+    assert_eq!(doc, ANS.replace('\n', dumper::NL));
+}
+
+#[test]
+fn test_dump_canonical() {
+    use crate::dumper::{dump_with_options, DumpOptions};
+
+    let opt = DumpOptions::new().canonical(true).sort_keys(true);
+    let canonical = |doc| dump_with_options(&parse::<repr::RcRepr>(doc).unwrap(), &[], opt);
+    let doc = canonical("b: [0x1F, 1e3]\na: {? [x] : 'y', z: ~}\n");
+    assert_eq!(doc, canonical("a:\n  z: null\n  ? - x\n  : y\nb:\n- 31\n- 1000.0\n"));
+    const ANS: &str = r#"!!map
+!!str "a": !!map
+  ? !!seq
+    - !!str "x"
+  : !!str "y"
+  !!str "z": !!null ""
+!!str "b": !!seq
+  - !!int "31"
+  - !!float "1000.0"
+"#;
+    assert_eq!(doc, ANS.replace('\n', dumper::NL));
+    parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_digest() {
+    use std::{collections::hash_map::DefaultHasher, hash::Hasher};
+
+    let digest = |doc| {
+        let (root, anchors) = parse_cyclic::<repr::RcRepr>(doc).unwrap_or_else(show_err);
+        let mut state = DefaultHasher::new();
+        root[0].digest(&anchors[0], true, &mut state);
+        state.finish()
+    };
+    // Recursive aliases are hashed by their names
+    let a = digest("&a [b, *a]");
+    assert_eq!(a, digest("&a [b, *a]\n"));
+    assert_ne!(a, digest("&c [b, *c]"));
+    assert_eq!(digest("[&a 1.0, *a]"), digest("[1., 1e0]"));
+}
+
+#[test]
+fn test_schema() {
+    use crate::parser::{Loader, Schema};
+
+    let load = |doc: &str, schema| {
+        Loader::<repr::RcRepr>::new(doc.as_bytes())
+            .schema(schema)
+            .parse()
+            .unwrap_or_else(show_err)
+    };
+    let root = load("[01, -0, 1., 1e5, .5, -1.5e-3, NULL, True]", Schema::Json);
+    let ans = node!(["01", 0, "1.", 1e5, ".5", -1.5e-3, "NULL", "True"]);
+    assert_eq!(root, [ans]);
+    let root = load("[Off, y, 'yes', ON, no way, ~, .inf]", Schema::Yaml11);
+    let ans = node!([false, true, "yes", true, "no way", (), f64::INFINITY]);
+    assert_eq!(root, [ans]);
+}
+
+#[test]
+fn test_style() {
+    use ScalarStyle::*;
+
+    const DOC: &str = "\
+- |
+  a
+- >
+  b
+- !t 'c'
+- &x \"d\"
+- *x
+- 'k': v
+- [e, 'f']
+";
+    let root = parse::<repr::RcRepr>(DOC).unwrap_or_else(show_err);
+    let seq = root[0].as_seq_checked().unwrap();
+    let styles = seq.iter().map(Node::style).collect::<Vec<_>>();
+    assert_eq!(styles, [Literal, Folded, SingleQuoted, DoubleQuoted, Plain, Plain, Plain]);
+    let map = seq[5].as_map_checked().unwrap();
+    let (k, v) = map.iter().next().unwrap();
+    assert_eq!((k.style(), v.style()), (SingleQuoted, Plain));
+    assert_eq!(seq[6][Ind(1)].style(), SingleQuoted);
+    let root = parse::<repr::RcRepr>("\"a\"").unwrap_or_else(show_err);
+    assert_eq!(root[0].style(), DoubleQuoted);
+}
+
+#[test]
+fn test_parse_fragment() {
+    use crate::parser::parse_fragment;
+
+    let (n, len) = parse_fragment::<repr::RcRepr>("- a\n- b\nc: d").unwrap_or_else(show_err);
+    assert_eq!((n, len), (node!(["a", "b"]), 7));
+    let (n, len) = parse_fragment::<repr::RcRepr>("'q''' x").unwrap_or_else(show_err);
+    assert_eq!((n, len), (node!("q'"), 5));
+    assert!(parse_fragment::<repr::RcRepr>("*x").is_err());
+}
+
+#[test]
+fn test_anchors() {
+    use crate::parser::Anchors;
+
+    let (root, anchors) = parse_cyclic::<repr::RcRepr>("&a [1, &b {x: *a}, *b]").unwrap_or_else(show_err);
+    let anchors = &anchors[0];
+    assert_eq!(anchors.name_of(&root[0]), Some("a"));
+    assert_eq!(anchors.name_of(&root[0][Ind(1)]), Some("b"));
+    assert_eq!(anchors.name_of(&node!({"x" => node!(*"a")})), None);
+    assert!(anchors.resolve_checked(&root[0], true).is_err());
+    assert_eq!(anchors.resolve_checked(&root[0][Ind(2)], false).unwrap(), root[0][Ind(1)]);
+    let anchors = anchors!["a" => node!(*"b"), "b" => node!([node!(*"c")])];
+    assert_eq!(anchors.resolve_checked(&node!(*"a"), false).unwrap(), node!(*"b"));
+    assert!(anchors.resolve_checked(&node!(*"a"), true).is_err());
+    let n = node!([1, 2]);
+    let root = node!([n.clone(), node!({"k" => n.clone()}), n.clone()]);
+    let ans: Anchors<_> = anchors!["id001" => n];
+    assert_eq!(Anchors::from_node(&root), ans);
+}
+
+#[test]
+fn test_anchor_resolve() {
+    let (root, anchors) = parse_cyclic::<repr::RcRepr>("&a {x: &b [1], y: *b, z: *a}").unwrap_or_else(show_err);
+    assert!(anchor_resolve_checked(&root[0], &anchors[0], usize::MAX).is_err());
+    let n = node!([node!(*"b"), node!({node!(*"b") => 2})]);
+    let ans = node!([node!([1]), node!({node!([1]) => 2})]);
+    assert_eq!(anchor_resolve_checked(&n, &anchors[0], usize::MAX).unwrap(), ans);
+    assert_eq!(anchor_resolve_checked(&node!(*"c"), &anchors[0], 1).unwrap_err().kind, NodeErrorKind::MissingKey { key: "c".to_string() });
+    let e = anchor_resolve_checked(&root[0], &anchors[0], usize::MAX).unwrap_err();
+    assert_eq!(e.kind, NodeErrorKind::CyclicAlias { anchor: "a".to_string() });
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_foreign() {
+    use crate::serde::{to_node, Foreign};
+    use ::serde::{Deserialize, Serialize};
+
+    #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
+    struct Member {
+        name: String,
+        age: u8,
+    }
+    const DOC: &str = "\
+members:
+- &bob {name: Bob, age: 46}
+- *bob
+- *amy
+";
+    let (root, anchors) = parse_cyclic::<repr::RcRepr>(DOC).unwrap_or_else(show_err);
+    let members = <Vec<Foreign<Member>>>::deserialize(root[0]["members"].clone()).unwrap();
+    let bob = Member { name: "Bob".to_string(), age: 46 };
+    assert_eq!(members[0], Foreign::Data(bob.clone()));
+    assert_eq!(members[1], Foreign::Anchor("bob".to_string()));
+    assert_eq!(to_node(&members).unwrap()[Ind(2)], node!(*"amy"));
+    let mut members = members.into_iter().map(|m| m.resolve(&anchors[0]));
+    assert_eq!(members.nth(1).unwrap().unwrap(), bob);
+    assert_eq!(members.next().unwrap().unwrap_err().msg, "missing anchor amy");
+}
+
+#[test]
+fn test_typed_accessors() {
+    const DOC: &str = "{a: 0x10, b: \"0o10\", c: '-5', d: 'False', e: x, f: 18446744073709551615}";
+    let root = parse::<repr::RcRepr>(DOC).unwrap_or_else(show_err);
+    let n = &root[0];
+    assert_eq!(n["a"].as_u64_checked(), Ok(16));
+    assert_eq!(n["b"].as_u64_checked().map_err(u64::from), Err(13));
+    assert_eq!(n["b"].lenient().as_usize_checked(), Ok(8));
+    assert_eq!(n["c"].lenient().as_i128_checked(), Ok(-5));
+    assert_eq!(n["c"].lenient().as_u64_checked().map_err(u64::from), Err(24));
+    assert_eq!(n["d"].lenient().as_bool_checked(), Ok(false));
+    assert_eq!(n["e"].lenient().as_int_checked().map_err(u64::from), Err(45));
+    assert_eq!(n["f"].as_int_checked().map_err(u64::from), Err(51));
+    assert_eq!(n["f"].as_i128_checked(), Ok(u64::MAX.into()));
+}
+
+#[test]
+fn test_try_into() {
+    use alloc::rc::Rc;
+
+    let n = node!([node!({"a" => "b"})]);
+    let shared = n.clone();
+    let v = n.try_into_seq_checked().unwrap();
+    assert_eq!(Rc::strong_count(v[0].rc_ref()), 2);
+    drop(v);
+    let v = shared.try_into_seq_checked().unwrap();
+    assert_eq!(Rc::strong_count(v[0].rc_ref()), 1);
+    let (k, v) = v.into_iter().next().unwrap().try_into_map_checked().unwrap().into_iter().next().unwrap();
+    assert_eq!(k.try_into_string_checked().unwrap(), "a");
+    assert_eq!(v.try_into_seq_checked().unwrap_err().kind, NodeErrorKind::WrongType { expected: "seq", found: "str" });
+}
+
+#[test]
+fn test_node_iter() {
+    let root = parse::<repr::RcRepr>("a: [1, 2]\nb: {c: d}\n").unwrap_or_else(show_err);
+    let n = &root[0];
+    assert_eq!((n.len(), n["a"].len(), n["b"].len(), n["b"]["c"].len()), (2, 2, 1, 0));
+    let keys = n.entries().map(|(k, _)| k.as_str_checked().unwrap()).collect::<Vec<_>>();
+    assert_eq!(keys, ["a", "b"]);
+    let items = n["a"].iter().collect::<Vec<_>>();
+    assert_eq!(items, [&node!(1), &node!(2)]);
+    assert_eq!(n.iter().count(), 0);
+    assert_eq!(n["a"].entries().count(), 0);
+    assert!(node!([]).is_empty() && node!(()).is_empty());
+}
+
+#[cfg(not(feature = "compact-errors"))]
+#[test]
+fn test_error_info() {
+    let info = |doc: &str| match parse::<repr::RcRepr>(doc).unwrap_err() {
+        PError::Terminate { name, pos, expected, found, .. } => (name, pos, expected, found),
+        e => panic!("{e}"),
+    };
+    assert_eq!(info("a: 'b' c\n"), ("map terminator", 6, &["newline"][..], " c".to_string()));
+    assert_eq!(info("- \"x\n"), ("quoted string", 5, &["\""][..], String::new()));
+    let (name, pos, _, found) = info("%YAML 2.0\n---\na");
+    assert_eq!((name, pos, found.as_str()), ("invalid version", 6, "2.0"));
+    let (_, _, _, found) = info(&format!("[a, 'b' {}]", "c".repeat(20)));
+    assert_eq!(found.len(), 16);
+    #[cfg(feature = "serde")]
+    assert_eq!(crate::serde::from_str::<NodeRc>("a: 'b' c\n").unwrap_err().pos, 6);
+}
+
+#[test]
+fn test_dump_flow() {
+    use crate::dumper::{dump_with_options, DumpOptions};
+
+    let shared = node!([1, 2]);
+    let n = node!({
+        "m" => node!([node!([1, 0]), node!([0, 1])]),
+        "e" => node!([node!([]), node!({}), node!("")]),
+        "s" => node!(["a b", "c,d", "[e]"]),
+        "t" => node!([NodeRc::new(Yaml::Null, 0, "t"), node!(2)]),
+        "r" => node!([shared.clone(), shared]),
+        "long" => node!([123456789, 123456789]),
+    });
+    let opt = DumpOptions::new().prefer_flow(20).auto_anchor(true);
+    let doc = dump_with_options(core::slice::from_ref(&n), &[], opt);
+    const ANS: &str = "\
+m: [[1, 0], [0, 1]]
+e: [[], {}, '']
+s: [a b, 'c,d', '[e]']
+t:
+  - !t null
+  - 2
+r:
+  - &id001 [1, 2]
+  - *id001
+long:
+  - 123456789
+  - 123456789
+";
+    assert_eq!(doc, ANS.replace('\n', dumper::NL));
+    assert_eq!(parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err), [n]);
+    let doc = dump_with_options(&[node!({"b" => 1, "a" => 2})], &[], opt.sort_keys(true));
+    assert_eq!(doc, format!("{{a: 2, b: 1}}{}", dumper::NL));
+}
+
+#[test]
+fn test_dump_quoting() {
+    for (s, ans) in [
+        ("a b", "a b"),
+        ("a:b", "a:b"),
+        ("http://a.b#c", "http://a.b#c"),
+        ("-a", "-a"),
+        ("y", "y"),
+        ("yes", "'yes'"),
+        ("Off", "'Off'"),
+        ("null", "'null'"),
+        ("~", "'~'"),
+        ("12:30", "'12:30'"),
+        ("0x1F", "'0x1F'"),
+        ("1_000", "'1_000'"),
+        ("0b101", "'0b101'"),
+        ("2001-12-14", "'2001-12-14'"),
+        ("<<", "'<<'"),
+        (".inf", "'.inf'"),
+        (" a", "' a'"),
+        ("a ", "'a '"),
+        ("-", "'-'"),
+        ("- a", "'- a'"),
+        ("a: b", "'a: b'"),
+        ("a #b", "'a #b'"),
+        ("a:", "'a:'"),
+        ("*a", "'*a'"),
+        ("it's", "it's"),
+        ("'a", "'''a'"),
+        ("", "''"),
+        ("a\tb", "'a\tb'"),
+        ("\x1B[0m", "\"\\e[0m\""),
+        ("\u{7F}\u{FEFF}", "\"\\x7F\\uFEFF\""),
+    ] {
+        let n = node!(s);
+        let doc = dump(core::slice::from_ref(&n), &[]);
+        assert_eq!(doc, format!("{ans}{}", dumper::NL), "{s:?}");
+        assert_eq!(parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err), [n]);
+    }
+}
+
+#[test]
+fn test_anchor_namer() {
+    use crate::dumper::{dump_with_options, DumpOptions};
+
+    let a = node!([1]);
+    let b = node!([2]);
+    let c = node!([3]);
+    let n = node!({"x" => node!({"x y" => a.clone()}), "y" => node!({"x y" => b.clone()}), "z" => node!([a, b, c.clone(), c])});
+    let opt = DumpOptions::new().auto_anchor(true).anchor_namer(|k, _| k.into());
+    let doc = dump_with_options(core::slice::from_ref(&n), &[], opt);
+    assert!(doc.contains("&x-y") && doc.contains("&x-y-2") && doc.contains("&id003"), "{doc}");
+    assert_eq!(parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err), core::slice::from_ref(&n));
+    let doc = dump_with_options(core::slice::from_ref(&n), &[anchors!["x-y" => 0]], opt);
+    assert!(doc.contains("&x-y-2") && doc.contains("&x-y-3") && !doc.contains("&x-y\n"), "{doc}");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_origins() {
+    use crate::serde::{to_node, to_node_with_origins, Origins};
+    use ::serde::Serialize;
+    use alloc::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    enum Shape {
+        Circle(f64),
+        Rect(f64, f64),
+        Poly { points: Vec<(i32, i32)> },
+    }
+    #[derive(Serialize)]
+    struct Scene {
+        shapes: Vec<Shape>,
+        names: BTreeMap<&'static str, u8>,
+    }
+
+    let scene = Scene {
+        shapes: vec![Shape::Circle(1.), Shape::Rect(2., 3.), Shape::Poly { points: vec![(0, 1)] }],
+        names: [("a", 1)].into_iter().collect(),
+    };
+    let mut origins = Origins::new(100);
+    let n = to_node_with_origins(&scene, &mut origins).unwrap();
+    assert_eq!(n, to_node(&scene).unwrap());
+    let label = |path: &str| origins.label(n.get_path_checked(path).unwrap_or_else(|_| unreachable!()).pos());
+    assert_eq!(label(""), Some("Scene"));
+    assert_eq!(label("shapes[0]"), Some("Scene.shapes[0]"));
+    assert_eq!(label("shapes[0].Circle"), Some("Shape::Circle"));
+    assert_eq!(label("shapes[1].Rect[1]"), Some("Shape::Rect[1]"));
+    assert_eq!(label("shapes[2].Poly"), Some("Shape::Poly"));
+    assert_eq!(label("shapes[2].Poly.points[0][1]"), Some("Shape::Poly.points[0][1]"));
+    assert_eq!(label("names.a"), Some("Scene.names.a"));
+    let (k, v) = n.get_checked("names").unwrap_or_else(|_| unreachable!()).entries().next().unwrap();
+    assert_eq!(k.pos(), v.pos());
+    // The origins are shared by the calls
+    let len = origins.len();
+    let n = to_node_with_origins(&[1, 2], &mut origins).unwrap();
+    assert_eq!(n.pos(), 100 + len as u64);
+    assert_eq!(origins.label(n.pos() + 2), Some("[i32; 2][1]"));
+    assert_eq!(origins.label(99), None);
+    assert!(to_node(&scene).unwrap().find_all(|n| n.pos() != 0).is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_anchor_detection() {
+    use crate::serde::{to_node, to_string_with_options, SerializerOptions};
+    use alloc::collections::BTreeMap;
+
+    let point = vec![1, 2];
+    let mut data = BTreeMap::new();
+    data.insert("a", vec![point.clone(), point.clone(), vec![3]]);
+    data.insert("b", vec![point, vec![2, 1], vec![4]]);
+    let opt = SerializerOptions::new().anchor_detection(true);
+    let doc = to_string_with_options(&data, opt).unwrap();
+    assert_eq!(doc.matches("*id001").count(), 2);
+    assert!(!doc.contains("id002"));
+    let root = parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err);
+    assert_eq!(root, [to_node(&data).unwrap()]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_map_keys() {
+    use crate::serde::{from_str, to_string};
+    use ::serde::{Deserialize, Serialize};
+    use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    enum Key {
+        Unit,
+        Tuple(u8, u8),
+        Struct { x: i8 },
+    }
+
+    let mut m = BTreeMap::new();
+    m.insert(Key::Unit, 1);
+    m.insert(Key::Tuple(1, 2), 2);
+    m.insert(Key::Struct { x: -1 }, 3);
+    let doc = to_string(&m).unwrap();
+    assert_eq!(m, from_str(&doc).unwrap().remove(0));
+    let mut m = BTreeMap::new();
+    m.insert(Vec::<String>::new(), 1);
+    m.insert(vec![String::from("a")], 2);
+    let doc = to_string(&m).unwrap();
+    assert_eq!(m, from_str(&doc).unwrap().remove(0));
+    let mut m = BTreeMap::new();
+    m.insert(Some(-1), true);
+    m.insert(None, false);
+    let doc = to_string(&m).unwrap();
+    assert_eq!(m, from_str(&doc).unwrap().remove(0));
+    // Scalar keys as string keys
+    let m = from_str::<BTreeMap<String, u8>>("1: 1\n2.5: 2\nfalse: 3")
+        .unwrap()
+        .remove(0);
+    let keys = m.keys().map(String::as_str).collect::<Vec<_>>();
+    assert_eq!(keys, ["1", "2.5", "false"]);
+    assert!(from_str::<BTreeMap<String, u8>>("~: 1").is_err());
+    assert!(from_str::<BTreeMap<u8, String>>("1: 1").is_err());
+    // Empty collections
+    let doc = to_string(&node!({"a" => node!([]), "b" => node!({})})).unwrap();
+    assert_eq!("a: []\nb: {}\n", doc);
+}
+
+#[test]
+fn test_get_path() {
+    let doc = "a:\n  b: [x, {c: [1, 2]}]\n";
+    let root = parse::<repr::RcRepr>(doc).unwrap_or_else(show_err).remove(0);
+    assert_eq!(node!(2), *root.get_path_checked("a.b[1].c[1]").unwrap());
+    assert_eq!(node!("x"), *root.get_path_checked("a.b[0]").unwrap());
+    assert_eq!(root.get_checked("a").unwrap(), root.get_path_checked("a").unwrap());
+    // The error position is the last found node
+    assert_eq!(Err(8), root.get_path_checked("a.b[2]").map_err(u64::from));
+    assert_eq!(Err(12), root.get_path_checked("a.b[1].d").map_err(u64::from));
+    assert_eq!(Err(8), root.get_path_checked("a.b[1").map_err(u64::from));
+    assert_eq!(Err(12), root.get_path_checked("a.b[1]c").map_err(u64::from));
+    assert_eq!(Err(2), root.get_path_checked("a..b").map_err(u64::from));
+    let n = node!([node!([1])]);
+    assert_eq!(node!(1), *n.get_path_checked("[0][0]").unwrap());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_flatten_node() {
+    use crate::serde::{from_str, to_string};
+    use ::serde::{Deserialize, Serialize};
+    use alloc::string::String;
+
+    #[derive(Serialize, Deserialize)]
+    struct Spec {
+        name: String,
+        #[serde(flatten)]
+        rest: NodeRc,
+    }
+
+    let spec = from_str::<Spec>("name: a\n").unwrap().remove(0);
+    assert_eq!(node!({}), spec.rest);
+    assert_eq!("name: a\n", to_string(&spec).unwrap());
+    let doc = "name: a\n1: x\nb:\n  c: [1, 2]\n";
+    let spec = from_str::<Spec>(doc).unwrap().remove(0);
+    assert_eq!("a", spec.name);
+    let ans = node!({1 => "x", "b" => node!({"c" => node!([1, 2])})});
+    assert_eq!(ans, spec.rest);
+    let spec = Spec { name: "a".into(), rest: node!({"b" => NodeRc::new(2, 0, "t")}) };
+    assert_eq!("name: a\nb: 2\n", to_string(&spec).unwrap());
+    #[derive(Serialize)]
+    struct VerbatimSpec {
+        name: String,
+        #[serde(flatten)]
+        rest: crate::serde::Verbatim,
+    }
+    let spec = VerbatimSpec { name: spec.name, rest: spec.rest.into() };
+    assert_eq!("name: a\nb: !t 2\n", to_string(&spec).unwrap());
+    let spec = Spec { name: "a".into(), rest: node!([1]) };
+    assert!(to_string(&spec).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_tagged_enum() {
+    use crate::serde::from_str;
+    use ::serde::Deserialize;
+    use alloc::{string::String, vec::Vec};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Inner {
+        y: String,
+        z: Option<u32>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(tag = "type")]
+    enum Internal {
+        A { x: u8 },
+        B(Inner),
+        C,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(tag = "t", content = "c")]
+    enum Adjacent {
+        A { x: u8 },
+        B(u32),
+        C,
+        D(u8, bool),
+    }
+
+    let doc = "- type: A\n  x: 1\n- type: B\n  y: '2'\n- {type: C}\n";
+    let v = from_str::<Vec<Internal>>(doc).unwrap().remove(0);
+    assert_eq!(v, [Internal::A { x: 1 }, Internal::B(Inner { y: "2".into(), z: None }), Internal::C]);
+    let doc = "- t: A\n  c: {x: 1}\n- {t: B, c: 3}\n- {t: C}\n- {c: [1, true], t: D}\n";
+    let v = from_str::<Vec<Adjacent>>(doc).unwrap().remove(0);
+    assert_eq!(v, [Adjacent::A { x: 1 }, Adjacent::B(3), Adjacent::C, Adjacent::D(1, true)]);
+    // The errors of the buffered fields are reported at the map
+    let e = from_str::<Vec<Internal>>("- type: C\n- type: A\n  x: abc\n").unwrap_err();
+    assert_eq!(e.pos, 12);
+    let e = from_str::<Vec<Internal>>("- type: Q\n").unwrap_err();
+    assert!(e.msg.contains("unknown variant `Q`"), "{}", e.msg);
+    assert_eq!(e.pos, 8);
+    // The content after the tag is not buffered
+    let e = from_str::<Vec<Adjacent>>("- t: B\n  c: x\n").unwrap_err();
+    assert_eq!(e.pos, 12);
+    // Zero is a provided position
+    let n = NodeRc::new(Yaml::Seq([NodeRc::new(Yaml::Seq([node!("x")].into_iter().collect()), 5, "")].into_iter().collect()), 9, "");
+    assert_eq!(Vec::<Vec<u8>>::deserialize(n).unwrap_err().pos, 0);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_alias_error() {
+    use crate::serde::{DeserializerOptions, NodeDeserializer};
+    use ::serde::Deserialize;
+    use alloc::{string::String, vec::Vec};
+
+    let (root, anchors) = parse_cyclic::<repr::RcRepr>("a: &x [1]\nb: *x\nc: *y\n").unwrap_or_else(show_err);
+    let b = root[0]["b"].clone();
+    // Both the raw node and the typed data give the alias error
+    for e in [NodeRc::deserialize(b.clone()).unwrap_err(), Vec::<u8>::deserialize(b.clone()).unwrap_err()] {
+        assert!(e.msg.starts_with("unresolved alias *x"), "{}", e.msg);
+        assert_eq!(e.pos, 13);
+    }
+    let e = String::deserialize(root[0]["c"].clone()).unwrap_err();
+    assert!(e.msg.starts_with("unresolved alias *y"), "{}", e.msg);
+    let opt = DeserializerOptions::new();
+    let de = NodeDeserializer::with_anchors(b, opt, &anchors[0]).unwrap();
+    assert_eq!(Vec::<u8>::deserialize(de).unwrap(), [1]);
+    let e = NodeDeserializer::with_anchors(root[0].clone(), opt, &anchors[0]).err().unwrap();
+    assert_eq!(e.pos, 19);
+}
+
+#[cfg(feature = "compact-errors")]
+#[test]
+fn test_compact_errors() {
+    use crate::parser::PError;
+    const DOC: &str = "a: 'b' c\n";
+
+    let e = parse::<repr::RcRepr>(DOC).unwrap_err();
+    let PError::Terminate { name, pos, found, msg, .. } = &e else { panic!("{e}") };
+    assert_eq!((*name, *pos), ("map terminator", 6));
+    assert!(found.is_empty() && msg.is_empty());
+    assert_eq!(e.to_string(), "invalid map terminator in doc > map at 6");
+    assert_eq!(e.render(DOC.as_bytes()), indicated_msg(DOC.as_bytes(), 6));
+    let e = parser::Loader::<repr::RcRepr>::new(b"[a]").max_depth(0).parse().unwrap_err();
+    assert_eq!(e.to_string(), "nesting depth limit exceeded at 1");
+}
+
+#[test]
+fn test_map_order() {
+    let a = node!({"a" => 1, "b" => 2});
+    let b = node!({"b" => 2, "a" => 1});
+    assert_ne!(a, b);
+    assert_eq!(a, node!({"a" => 1, "b" => 2}));
+    let keys = b.entries().map(|(k, _)| k.as_str_checked().unwrap()).collect::<Vec<_>>();
+    assert_eq!(keys, ["b", "a"]);
+    assert_eq!(b.get_str_checked("a"), Ok(&node!(1)));
+    let mut m = b.as_map_checked().unwrap();
+    m.insert(node!("c"), node!(3));
+    assert_eq!(NodeRc::from(m), node!({"b" => 2, "a" => 1, "c" => 3}));
+}
+
+#[cfg(feature = "arena")]
+#[test]
+fn test_arena_repr() {
+    use crate::repr::{arena_chunks, ArenaRepr};
+    use alloc::format;
+
+    let base = arena_chunks();
+    let doc = (0..300).map(|i| format!("k{i}: [{i}, {{a: &x{i} b}}, *x{i}]\n")).collect::<String>();
+    {
+        let root = parse::<ArenaRepr>(&doc).unwrap_or_else(show_err).remove(0);
+        assert!(arena_chunks() > base + 1);
+        let rc = root.to_repr::<repr::RcRepr>();
+        assert_eq!(rc, parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err)[0]);
+        assert_eq!(root, rc.to_repr::<ArenaRepr>());
+        let item = root["k299"].clone();
+        drop(root);
+        assert_eq!(item[Ind(2)].as_str_checked(), Ok("b"));
+        // The last holder moves the data out
+        let v = item.try_into_seq_checked().unwrap();
+        assert_eq!(v.len(), 3);
+    }
+    // Only the current chunk is kept
+    assert_eq!(arena_chunks(), 1);
+}
+
+#[test]
+fn test_borrowed_scalars() {
+    use crate::parser::Parser;
+    use alloc::borrow::Cow;
+
+    for (doc, v) in [("a b # c", "a b"), ("a:b, c", "a:b"), ("a\n  b", "a b"), ("a\n\n  b", "a\nb")] {
+        let s = Parser::new(doc.as_bytes()).string_plain(1, true).unwrap_or_else(show_err);
+        assert_eq!(s, v);
+        assert_eq!(matches!(s, Cow::Borrowed(_)), !doc.contains('\n'));
+    }
+    for (doc, v) in [(r#""a b""#, "a b"), (r#"" a\tb""#, " a\tb"), ("\"a\n b\"", "a b")] {
+        let s = Parser::new(doc.as_bytes()).string_quoted(b'"', b"\\\"").unwrap_or_else(show_err);
+        assert_eq!(s, v);
+        assert_eq!(matches!(s, Cow::Borrowed(_)), !doc.contains(['\\', '\n']));
+    }
+    assert_eq!(parse::<repr::RcRepr>("- a: 'b c'\n  d: e\n    f\n").unwrap_or_else(show_err)[0], node!([node!({"a" => "b c", "d" => "e f"})]));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_parse_stats() {
+    use crate::parser::parse_stats;
+
+    let stats = parse_stats::<repr::RcRepr>("- [a, [bc]]\n- {d: 'ef'}\n").unwrap_or_else(show_err);
+    assert_eq!((stats.docs, stats.nodes, stats.scalar_bytes, stats.anchors, stats.depth), (1, 8, 6, 0, 3));
+}
+
+#[test]
+fn test_scalar_matcher() {
+    use crate::parser::Loader;
+
+    let rgb = |s: &str| s.strip_prefix("rgb:").map(|s| Node::new(s, 0, "color"));
+    let doc = "- !!str 0x10\n- 0x10\n- key: 0x10\n  rgb:fff: 'rgb:fff'\n";
+    let root = Loader::<repr::RcRepr>::new(doc.as_bytes())
+        .scalar_matcher(|s| (s == "0x10").then(|| Node::new("sixteen", 0, "hex")))
+        .scalar_fallback(rgb)
+        .parse()
+        .unwrap_or_else(show_err);
+    // The data are resolved regardless of the tags like the built-in types
+    assert_eq!(root[0], node!(["sixteen", "sixteen", node!({"key" => "sixteen", "fff" => "rgb:fff"})]));
+    assert_eq!(root[0][Ind(0)].tag(), "tag:yaml.org,2002:str");
+    assert_eq!(root[0][Ind(1)].tag(), "hex");
+    assert_eq!(root[0][Ind(2)]["key"].tag(), "hex");
+    let (k, v) = root[0][Ind(2)].as_map_checked().unwrap().into_iter().nth(1).unwrap();
+    assert_eq!((k.tag(), v.tag()), ("color", "tag:yaml.org,2002:str"));
+    let root = Loader::<repr::RcRepr>::new(b"rgb:000\n").scalar_fallback(rgb).parse().unwrap_or_else(show_err);
+    assert_eq!((root[0].as_str_checked(), root[0].tag()), (Ok("000"), "color"));
+    let root = Loader::<repr::RcRepr>::new(b"[rgb:1, 12]").scalar_fallback(rgb).parse().unwrap_or_else(show_err);
+    assert_eq!(root[0], node!(["1", 12]));
+}
+
+#[test]
+fn test_parse_directives() {
+    use crate::parser::{Loader, Version};
+
+    let doc = b"%TAG !a! tag:a.com:\n--- !a!x 1\n...\n%YAML 1.1\n%TAG !! tag:b.com:\n--- !!y 2\n";
+    let mut loader = Loader::<repr::RcRepr>::new(doc).default_version(Version::V1_1);
+    let info = loader.parse_directives().unwrap_or_else(show_err);
+    assert_eq!((info.version(), info.tags().len()), (Version::V1_1, 1));
+    assert_eq!(info.tags()["a"], "tag:a.com:");
+    assert_eq!(loader.parse_directives().unwrap_or_else(show_err), info);
+    let root = loader.parse().unwrap_or_else(show_err);
+    assert_eq!((root[0].tag(), root[1].tag()), ("tag:a.com:x", "tag:b.com:y"));
+    assert_eq!(loader.doc_info()[0], info);
+    assert_eq!(loader.doc_info()[1].tags()["!!"], "tag:b.com:");
+    let mut loader = Loader::<repr::RcRepr>::new(b"%YAML 1.2\n%YAML 1.2\n---\na\n");
+    assert!(loader.parse_directives().is_err());
+    assert_eq!(loader.pos, 0);
+}
+
+#[test]
+fn test_documents() {
+    use crate::parser::Loader;
+
+    let doc = b"a: &x 1\nb: *x\n---\n- 2\n...\n--- 3\n---\n[4\n";
+    let mut loader = Loader::<repr::RcRepr>::new(doc);
+    let docs = loader.documents().take(3).collect::<Result<Vec<_>, _>>().unwrap_or_else(show_err);
+    assert_eq!(docs, [node!({"a" => 1, "b" => 1}), node!([2]), node!(3)]);
+    // The rest is not parsed
+    assert_eq!(loader.doc_info().len(), 3);
+    assert!(loader.get_anchors().iter().all(|a| a.is_empty()));
+    let mut loader = Loader::<repr::RcRepr>::new(doc).max_docs(2);
+    let docs = loader.documents().collect::<Vec<_>>();
+    assert_eq!(docs.len(), 3);
+    assert!(matches!(docs[2], Err(PError::LimitExceeded { name: "document count", .. })));
+    let mut loader = Loader::<repr::RcRepr>::new(doc).max_docs(2).truncate_docs(true);
+    assert_eq!(loader.documents().count(), 2);
+    assert_eq!(Loader::<repr::RcRepr>::new(b"").documents().collect::<Result<Vec<_>, _>>().unwrap_or_else(show_err), [node!(())]);
+}
+
+#[test]
+fn test_undefined_aliases() {
+    use crate::parser::Loader;
+
+    let doc = b"- *a\n- &a [*a, *b]\n---\n- *a\n";
+    let mut loader = Loader::<repr::RcRepr>::new(doc).cyclic_mode(true);
+    assert_eq!(loader.documents().count(), 2);
+    assert_eq!(loader.undefined_aliases(), [("b".into(), 15), ("a".into(), 25)]);
+    let mut loader = Loader::<repr::RcRepr>::new(b"&a [*a]").cyclic_mode(true);
+    loader.parse().unwrap_or_else(show_err);
+    assert!(loader.undefined_aliases().is_empty());
+}
+
+#[test]
+fn test_node_error_path() {
+    let doc = "a:\n  list: [0, 1, 2, {b: 1}]\n";
+    let root = parse::<repr::RcRepr>(doc).unwrap_or_else(show_err).remove(0);
+    let e = root.get_path_checked("a.list[3].c").unwrap_err();
+    assert_eq!(e.path, NodePath::new().key("a").key("list").ind(3).key("c"));
+    assert_eq!(e.to_string(), "missing key \"c\" at a.list[3].c (pos 21)");
+    assert_eq!(root.get_path_checked("a.list[4]").unwrap_err().path.to_string(), "a.list[4]");
+    assert_eq!(root.get_path_checked("a.list[1]x").unwrap_err().path.to_string(), "a.list[1]");
+    assert_eq!(root.get_path_checked("a..b").unwrap_err().path.to_string(), "a");
+    assert_eq!(root.get_path_checked("a.list[0].b").unwrap_err().path.to_string(), "a.list[0]");
+    let path = NodePath::new().key("a").key("list").ind(9);
+    let e = root.get_at(&path).unwrap_err();
+    assert_eq!((e.path, e.pos), (path, 11));
+    assert!(root.get_checked("x").unwrap_err().path.segments() == [PathSegment::Key("x".to_string())]);
+    assert!(root["a"].as_int_checked().unwrap_err().path.is_empty());
+}
+
+#[test]
+fn test_node_error() {
+    let root = parse::<repr::RcRepr>("a: [1, -2]\nb: x\n").unwrap_or_else(show_err).remove(0);
+    let e = root.get_checked("c").unwrap_err();
+    assert_eq!(e.kind, NodeErrorKind::MissingKey { key: "c".to_string() });
+    assert_eq!(e.pos, 0);
+    let e = root["b"].get_ind_checked(Ind(0)).unwrap_err();
+    assert_eq!(e.kind, NodeErrorKind::WrongType { expected: "seq", found: "str" });
+    assert_eq!(e.pos, 14);
+    assert_eq!(root["a"].get_ind_checked(Ind(2)).unwrap_err().kind, NodeErrorKind::OutOfRange);
+    assert_eq!(root["a"][Ind(1)].as_u64_checked().unwrap_err().kind, NodeErrorKind::OutOfRange);
+    assert_eq!(root.get_path_checked("a[x]").unwrap_err().kind, NodeErrorKind::InvalidPath);
+    assert_eq!(root.get_path_checked("a[0].b").unwrap_err().kind, NodeErrorKind::WrongType { expected: "map", found: "int" });
+    assert_eq!(root.get_default("b", 0, Node::as_int_checked).unwrap_err().kind, NodeErrorKind::WrongType { expected: "int", found: "str" });
+    assert_eq!(root.get_default::<_, _, _, u64>("b", 0, |n| Ok(n.as_int_checked()?)), Err(14));
+    // The original methods are kept for the position only
+    #[allow(deprecated)]
+    {
+        assert_eq!(root.get("c"), Err(0));
+        assert_eq!(root["a"].as_int(), Err(3));
+        assert_eq!(root["b"].as_str(), Ok("x"));
+        assert_eq!(root["b"].clone().try_into_seq(), Err(14));
+        assert_eq!(root.try_normalize(), Ok(root.clone()));
+        assert_eq!(anchor_resolve(&node!(*"x"), &parser::Anchors::new(), 1), Err(0));
+        assert_eq!(parser::Anchors::new().resolve(&node!(*"x"), false), Err(0));
+    }
+}
+
+#[test]
+fn test_yaml11_collections() {
+    let doc = "\
+set: !!set
+  ? a
+  ? b
+omap: !!omap
+  - a: 1
+  - b: 2
+pairs: !!pairs
+  - a: 1
+  - a: 2
+";
+    let root = parse::<repr::RcRepr>(doc).unwrap_or_else(show_err);
+    assert_eq!(root[0]["set"].as_set().unwrap_or_else(|_| unreachable!()), [&node!("a"), &node!("b")]);
+    assert_eq!(root[0]["omap"].as_pairs().unwrap_or_else(|_| unreachable!()), [(&node!("a"), &node!(1)), (&node!("b"), &node!(2))]);
+    assert_eq!(root[0]["pairs"].as_pairs().unwrap_or_else(|_| unreachable!()).len(), 2);
+    assert_eq!(root[0]["set"].as_pairs().map_err(u64::from), Err(root[0]["set"].pos()));
+    assert_eq!(root[0]["pairs"].as_set().map_err(u64::from), Err(root[0]["pairs"].pos()));
+    assert_eq!(dump(&root, &[]), doc.replace('\n', dumper::NL));
+    let n = node!(!"!!set" {"a" => (), "b" => 1});
+    assert_eq!(n.as_set().map_err(u64::from), Err(0));
+    assert_eq!(dump(&[n], &[]), "!!set\na: null\nb: 1\n".replace('\n', dumper::NL));
+}
+
+#[test]
+fn test_tag_api() {
+    let n = node!(!"!!set" {"a" => (), "b" => node!(!"point" [1, 2])});
+    assert!(n.is_tag("!!set"));
+    assert!(n.is_tag("tag:yaml.org,2002:set"));
+    assert_eq!(n.tag_suffix("!!"), Some("set"));
+    assert_eq!(n.tag_suffix("tag:x.com,2000:"), None);
+    let doc = dump(core::slice::from_ref(&n), &[]);
+    let root = parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err).remove(0);
+    assert_eq!(root, n);
+    assert!(root.is_tag("!!set"));
+    assert!(root.get_checked("b").unwrap_or_else(|_| unreachable!()).is_tag("!point"));
+    let n = node!(arc !"!<tag:x.com,2000:y>" 1);
+    assert_eq!(n.tag(), "tag:x.com,2000:y");
+    assert_eq!(n.tag_suffix("tag:x.com,2000:"), Some("y"));
+    assert_eq!(node!(1).with_tag("!!str").tag(), "tag:yaml.org,2002:str");
+}
+
+#[test]
+fn test_builder() {
+    let mut rows = SeqBuilder::<repr::RcRepr>::new();
+    for i in 0..3 {
+        let row = (0..i).fold(SeqBuilder::new(), |row, j| row.push(j)).with_anchor(format!("r{i}"));
+        rows = rows.push(MapBuilder::new().entry("id", i).entry("row", row));
+    }
+    let (n, anchors) = rows.with_tag("rows").with_anchor("all").build_with_anchors();
+    assert_eq!(n, node!([node!({"id" => 0, "row" => node!([])}), node!({"id" => 1, "row" => node!([0])}), node!({"id" => 2, "row" => node!([0, 1])})]));
+    assert_eq!(n.tag(), "rows");
+    assert_eq!(anchors.keys().collect::<Vec<_>>(), ["all", "r0", "r1", "r2"]);
+    assert_eq!(anchors["r2"], node!([0, 1]));
+    assert_eq!(anchors["all"], n);
+    let n = MapBuilder::<repr::RcRepr>::new().entry(node!(1), SeqBuilder::new().with_anchor("a")).entry(node!(1), 2).build();
+    assert_eq!(n, node!({1 => 2}));
+}
+
+#[test]
+fn test_find_all() {
+    let root = parse::<repr::RcRepr>("a: &x {k: [1, 2]}\nb: [*x, {k: 3}]\n").unwrap_or_else(show_err).remove(0);
+    let paths = root.find_all(|n| n.get_checked("k").is_ok());
+    assert_eq!(paths, [NodePath::new().key("a"), NodePath::new().key("b").ind(0), NodePath::new().key("b").ind(1)]);
+    assert!(root.get_path_checked(&paths[0].to_string()).unwrap_or_else(|_| unreachable!()).ptr_eq(root.get_path_checked(&paths[1].to_string()).unwrap_or_else(|_| unreachable!())));
+    assert_eq!(root.find_all(|n| n.is_null()), []);
+    assert_eq!(root.find_all(|_| true)[0], NodePath::new());
+    // A tree of 2^64 paths but 65 data holders
+    let mut n = node!(0);
+    for _ in 0..64 {
+        n = node!([n.clone(), n]);
+    }
+    assert!(n.contains(&node!(0)));
+    assert!(!n.contains(&node!(1)));
+    assert_eq!(n, n.clone());
+}
+
+#[test]
+fn test_anchor_policy() {
+    use crate::parser::{AnchorPolicy, Loader};
+
+    let doc = b"a: &x {k: 1}\nb: *x\nc: &x [2]\nd: [*x, &x 3, *x]\n---\ne: &x 4\n";
+    let e = Loader::<repr::RcRepr>::new(doc).parse().unwrap_err();
+    assert!(matches!(e, PError::Terminate { name: "duplicated anchor definition", .. }));
+    let ans = node!({"a" => node!({"k" => 1}), "b" => node!({"k" => 1}), "c" => node!([2]), "d" => node!([node!([2]), 3, 3])});
+    let mut loader = Loader::<repr::RcRepr>::new(doc).anchor_policy(AnchorPolicy::LastWins);
+    assert_eq!(loader.parse().unwrap_or_else(show_err), [ans.clone(), node!({"e" => 4})]);
+    let mut loader = Loader::<repr::RcRepr>::new(doc).anchor_policy(AnchorPolicy::Rename);
+    assert_eq!(loader.parse().unwrap_or_else(show_err), [ans, node!({"e" => 4})]);
+    let anchors = loader.get_anchors();
+    assert_eq!(anchors[0], anchors!["x" => 3, "x-2" => node!({"k" => 1}), "x-3" => node!([2])]);
+    assert_eq!(anchors[1], anchors!["x" => 4]);
+}
+
+#[test]
+fn test_doc_pos() {
+    use crate::parser::Loader;
+
+    let doc = b"# head\na: 1\n...\n%YAML 1.2\n---\n- 2\n---\n{'a' b: c}\n";
+    let mut loader = Loader::<repr::RcRepr>::new(doc);
+    let e = loader.parse().unwrap_err();
+    let starts = loader.doc_info().iter().map(|info| info.pos()).collect::<Vec<_>>();
+    assert_eq!(starts, [0, 16, 34]);
+    assert_eq!(loader.doc_index(e.pos()), Some(2));
+    assert_eq!(loader.doc_index(20), Some(1));
+    let mut loader = Loader::<repr::RcRepr>::new(doc);
+    assert_eq!(loader.documents().next().unwrap().unwrap_or_else(show_err)["a"].pos(), 10);
+    assert_eq!(loader.doc_index(10), Some(0));
+}
+
+#[test]
+fn test_dump_comment() {
+    use crate::dumper::{dump_with_options, DumpOptions, NL};
+
+    let mut a = node!(1);
+    a.set_comment("first\n\nitem");
+    let mut b = node!({"c" => 2});
+    b.set_comment("second");
+    let mut k = node!("k");
+    k.set_comment("key");
+    let n = node!({k => node!([a, b]), "d" => node!([3, 4])});
+    let mut doc2 = node!("e");
+    doc2.set_comment("doc 2");
+    let doc = dump_with_options(&[n, doc2], &[], DumpOptions::new().prefer_flow(20));
+    let ans = "\
+# key
+k:
+  # first
+  #
+  # item
+  - 1
+  # second
+  - {c: 2}
+d: [3, 4]
+---
+# doc 2
+e
+";
+    assert_eq!(doc, ans.replace('\n', NL));
+    let root = parse::<repr::RcRepr>(&doc).unwrap_or_else(show_err);
+    assert_eq!(root, [node!({"k" => node!([1, node!({"c" => 2})]), "d" => node!([3, 4])}), node!("e")]);
+    assert_eq!(root[0].comment(), "");
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn test_wasm_tagged_scalar() {
+    let doc = "- !!str 1\n- !custom x\n- !t {a: !!binary aGk=}\n---\n!!null\n";
+    let v = crate::wasm::parse_with(doc, ::serde_json::value::Serializer).unwrap_or_else(|_| panic!("parse error"));
+    assert_eq!(v, ::serde_json::json!([[1, "x", {"a": "aGk="}], null]));
+}
@@ -0,0 +1,66 @@
+//! The WebAssembly bindings of `wasm-bindgen` crate.
+//!
+//! The functions are exported as `parseYaml` and `dumpYaml`, the documents
+//! are converted between YAML and the JSON-compatible JavaScript values, so
+//! the maps become plain objects. The errors are thrown as JavaScript `Error`.
+//!
+//! ```js
+//! import { parseYaml, dumpYaml } from "yaml-peg";
+//!
+//! const docs = parseYaml("a: [1, 2]\n---\nb: c\n");
+//! console.log(docs[0].a); // [1, 2]
+//! console.log(dumpYaml(docs));
+//! ```
+//!
+//! # Size Optimization
+//!
+//! Disable the default features and enable `compact-errors`, so the standard
+//! I/O functions and the formatted error messages are not linked into the
+//! module. The messages are replaced by the error names and the positions.
+//!
+//! ```toml
+//! [dependencies.yaml-peg]
+//! version = "1"
+//! default-features = false
+//! features = ["wasm", "indexmap", "compact-errors"]
+//!
+//! [profile.release]
+//! opt-level = "z"
+//! lto = true
+//! codegen-units = 1
+//! panic = "abort"
+//! ```
+//!
+//! The output can be shrunk further by `wasm-opt -Oz`.
+use crate::{dump, parse, repr::RcRepr, NodeRc};
+use ::serde::{Serialize, Serializer};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use wasm_bindgen::prelude::*;
+
+/// Parse the YAML documents into an array of JavaScript values.
+///
+/// The aliases are replaced by the anchored values, see [`parse`].
+#[wasm_bindgen(js_name = parseYaml)]
+pub fn parse_yaml(doc: &str) -> Result<JsValue, JsError> {
+    parse_with(doc, &serde_wasm_bindgen::Serializer::json_compatible())
+}
+
+/// Parse the YAML documents and serialize them as the plain values, the tags
+/// are not included.
+pub(crate) fn parse_with<S: Serializer>(doc: &str, serializer: S) -> Result<S::Ok, JsError> {
+    let nodes = parse::<RcRepr>(doc).map_err(|e| JsError::new(&e.to_string()))?;
+    nodes
+        .serialize(serializer)
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Dump an array of JavaScript values as the YAML documents, see [`dump`].
+#[wasm_bindgen(js_name = dumpYaml)]
+pub fn dump_yaml(docs: JsValue) -> Result<String, JsError> {
+    let nodes = serde_wasm_bindgen::from_value::<Vec<NodeRc>>(docs)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(dump(&nodes, &[]))
+}
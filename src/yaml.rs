@@ -1,14 +1,10 @@
 use crate::{repr::*, *};
-use alloc::{
-    string::{String, ToString},
-    vec::Vec,
-};
+use alloc::string::{String, ToString};
 use core::{
     fmt::{Debug, Formatter},
     hash::{Hash, Hasher},
     iter::FromIterator,
 };
-use ritelinked::LinkedHashMap;
 
 macro_rules! impl_from {
     ($(impl $($from_ty:ty),+ => $ty:ident)+) => {
@@ -34,12 +30,48 @@ macro_rules! impl_iter {
 pub type YamlRc = Yaml<RcRepr>;
 /// A YAML data with [`alloc::sync::Arc`] holder.
 pub type YamlArc = Yaml<ArcRepr>;
+/// The short strings held by the nodes, such as the tags.
+///
+/// Enable `sso` feature to use the inline string of `smartstring` crate, which
+/// avoids allocation for the short strings.
+#[cfg(not(feature = "sso"))]
+pub(crate) type SmallStr = String;
+#[cfg(feature = "sso")]
+pub(crate) type SmallStr = smartstring::alias::String;
 /// The sequence data structure of YAML.
-pub type Seq<R> = Vec<Node<R>>;
-/// The map data structure of YAML.
-pub type Map<R> = LinkedHashMap<Node<R>, Node<R>>;
+///
+/// Enable `smallvec` feature to store short sequences inline.
+#[cfg(not(feature = "smallvec"))]
+pub type Seq<R> = alloc::vec::Vec<Node<R>>;
+/// The sequence data structure of YAML.
+///
+/// Enable `smallvec` feature to store short sequences inline.
+#[cfg(feature = "smallvec")]
+pub type Seq<R> = smallvec::SmallVec<[Node<R>; 4]>;
+
+/// The number lexeme which might hold the parsed value.
+pub(crate) trait NumLexeme: AsRef<str> {
+    fn int(&self) -> Option<i64> {
+        None
+    }
+
+    fn float(&self) -> Option<f64> {
+        None
+    }
+}
 
-pub(crate) fn to_i64(s: &str) -> Result<i64, core::num::ParseIntError> {
+impl NumLexeme for str {}
+impl NumLexeme for String {}
+impl NumLexeme for &str {}
+
+pub(crate) fn to_i64<S>(s: &S) -> Result<i64, core::num::ParseIntError>
+where
+    S: NumLexeme + ?Sized,
+{
+    if let Some(n) = s.int() {
+        return Ok(n);
+    }
+    let s = s.as_ref();
     if s.contains("0x") {
         i64::from_str_radix(&s.replace("0x", ""), 16)
     } else if s.contains("0o") {
@@ -49,8 +81,115 @@ pub(crate) fn to_i64(s: &str) -> Result<i64, core::num::ParseIntError> {
     }
 }
 
-pub(crate) fn to_f64(s: &str) -> Result<f64, core::num::ParseFloatError> {
-    s.parse()
+pub(crate) fn to_u64<S>(s: &S) -> Result<u64, core::num::ParseIntError>
+where
+    S: NumLexeme + ?Sized,
+{
+    if let Some(n) = s.int().and_then(|n| u64::try_from(n).ok()) {
+        return Ok(n);
+    }
+    let s = s.as_ref();
+    if s.contains("0x") {
+        u64::from_str_radix(&s.replace("0x", ""), 16)
+    } else if s.contains("0o") {
+        u64::from_str_radix(&s.replace("0o", ""), 8)
+    } else {
+        s.parse()
+    }
+}
+
+pub(crate) fn to_i128<S>(s: &S) -> Result<i128, core::num::ParseIntError>
+where
+    S: NumLexeme + ?Sized,
+{
+    if let Some(n) = s.int() {
+        return Ok(n.into());
+    }
+    let s = s.as_ref();
+    if s.contains("0x") {
+        i128::from_str_radix(&s.replace("0x", ""), 16)
+    } else if s.contains("0o") {
+        i128::from_str_radix(&s.replace("0o", ""), 8)
+    } else {
+        s.parse()
+    }
+}
+
+pub(crate) fn to_u128<S>(s: &S) -> Result<u128, core::num::ParseIntError>
+where
+    S: NumLexeme + ?Sized,
+{
+    if let Some(n) = s.int().and_then(|n| u128::try_from(n).ok()) {
+        return Ok(n);
+    }
+    let s = s.as_ref();
+    if s.contains("0x") {
+        u128::from_str_radix(&s.replace("0x", ""), 16)
+    } else if s.contains("0o") {
+        u128::from_str_radix(&s.replace("0o", ""), 8)
+    } else {
+        s.parse()
+    }
+}
+
+pub(crate) fn to_f64<S>(s: &S) -> Result<f64, core::num::ParseFloatError>
+where
+    S: NumLexeme + ?Sized,
+{
+    match s.float() {
+        Some(n) => Ok(n),
+        None => s.as_ref().parse(),
+    }
+}
+
+/// The parsed value of the number scalars, which is cached in the nodes with
+/// `native-num` feature. The value is `None` if the lexeme is out of range or
+/// invalid.
+#[cfg(feature = "native-num")]
+#[derive(Clone, Copy)]
+pub(crate) enum Num {
+    None,
+    Int(i64),
+    Float(f64),
+}
+
+#[cfg(feature = "native-num")]
+impl Num {
+    pub(crate) fn new<R: Repr>(yaml: &Yaml<R>) -> Self {
+        match yaml {
+            Yaml::Int(s) => to_i64(s).map_or(Self::None, Self::Int),
+            Yaml::Float(s) => to_f64(s).map_or(Self::None, Self::Float),
+            _ => Self::None,
+        }
+    }
+}
+
+/// The number lexeme with the cached value of the node.
+#[cfg(feature = "native-num")]
+pub(crate) struct Cached<'a>(pub(crate) Num, pub(crate) &'a str);
+
+#[cfg(feature = "native-num")]
+impl AsRef<str> for Cached<'_> {
+    fn as_ref(&self) -> &str {
+        self.1
+    }
+}
+
+#[cfg(feature = "native-num")]
+impl NumLexeme for Cached<'_> {
+    fn int(&self) -> Option<i64> {
+        match self.0 {
+            Num::Int(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn float(&self) -> Option<f64> {
+        match self.0 {
+            Num::Float(f) => Some(f),
+            _ => None,
+        }
+    }
 }
 
 /// YAML data types, but it is recommended to use [`Node`] for shorten code.
@@ -60,8 +199,8 @@ pub(crate) fn to_f64(s: &str) -> Result<f64, core::num::ParseFloatError> {
 /// ```
 /// use yaml_peg::YamlRc;
 ///
-/// assert_eq!(YamlRc::Int("20".to_string()), YamlRc::from(20));
-/// assert_eq!(YamlRc::Float("0.001".to_string()), 1e-3.into());
+/// assert_eq!(YamlRc::Int("20".into()), YamlRc::from(20));
+/// assert_eq!(YamlRc::Float("0.001".into()), 1e-3.into());
 /// ```
 ///
 /// Also, the iterators can turned to sequence and map.
@@ -71,7 +210,7 @@ pub(crate) fn to_f64(s: &str) -> Result<f64, core::num::ParseFloatError> {
 /// use yaml_peg::{node, YamlRc};
 ///
 /// let v = vec![node!(1), node!(2), node!(3)];
-/// assert_eq!(YamlRc::Seq(v.clone()), YamlRc::from_iter(v));
+/// assert_eq!(YamlRc::Seq(v.clone().into()), YamlRc::from_iter(v));
 /// let m = vec![(node!(1), node!(2)), (node!(3), node!(4))];
 /// assert_eq!(
 ///     YamlRc::Map(m.clone().into_iter().collect()),
@@ -79,7 +218,11 @@ pub(crate) fn to_f64(s: &str) -> Result<f64, core::num::ParseFloatError> {
 /// );
 /// ```
 ///
-/// The digit NaN (not-a-number) will be equal in the comparison.
+/// The digit NaN (not-a-number) will be equal in the comparison, and the
+/// numbers are hashed by their values, so `.nan` and `.NaN` are the same map
+/// key.
+/// The invalid number lexemes are compared as strings, use
+/// [`Node::try_normalize_checked`] to check them.
 pub enum Yaml<R: Repr> {
     /// Null
     Null,
@@ -137,25 +280,33 @@ impl<R: Repr> Hash for Yaml<R> {
                 state.write_u8(2);
                 b.hash(state)
             }
-            Self::Int(s) => {
-                state.write_u8(3);
-                s.hash(state)
-            }
-            Self::Float(s) => {
-                state.write_u8(4);
-                s.hash(state)
-            }
-            Self::Str(s) => {
-                state.write_u8(5);
-                s.hash(state)
-            }
+            // Hash the parsed numbers to be consistent with the equality
+            Self::Int(s) => match (to_i128(s), to_u128(s)) {
+                (Ok(n), _) => hash_int(n, state),
+                (_, Ok(n)) => {
+                    state.write_u8(3);
+                    n.hash(state)
+                }
+                _ => {
+                    state.write_u8(3);
+                    s.as_str().hash(state)
+                }
+            },
+            Self::Float(s) => match to_f64(s) {
+                Ok(f) => hash_float(f, state),
+                Err(_) => {
+                    state.write_u8(4);
+                    s.as_str().hash(state)
+                }
+            },
+            Self::Str(s) => hash_str(s, state),
             Self::Seq(s) => {
                 state.write_u8(6);
                 s.hash(state)
             }
             Self::Map(m) => {
                 state.write_u8(7);
-                m.hash(state)
+                m.iter().for_each(|e| e.hash(state))
             }
             Self::Alias(a) => {
                 state.write_u8(8);
@@ -165,24 +316,58 @@ impl<R: Repr> Hash for Yaml<R> {
     }
 }
 
+/// Hash the integer as [`Yaml::Int`], which is used by the cached numbers.
+pub(crate) fn hash_int<H: Hasher>(n: i128, state: &mut H) {
+    state.write_u8(3);
+    n.hash(state)
+}
+
+/// Hash the float as [`Yaml::Float`], which is used by the cached numbers.
+pub(crate) fn hash_float<H: Hasher>(f: f64, state: &mut H) {
+    state.write_u8(4);
+    // NaNs are equal, and zero is equal to negative zero (`-0. + 0.`)
+    if f.is_nan() {
+        f64::NAN.to_bits().hash(state)
+    } else {
+        (f + 0.).to_bits().hash(state)
+    }
+}
+
+/// Test the equality of the floats as [`Yaml::Float`], the NaNs are equal.
+pub(crate) fn float_eq(f1: f64, f2: f64) -> bool {
+    f1 == f2 || f1.is_nan() && f2.is_nan()
+}
+
+/// Hash the string as [`Yaml::Str`], the key can be looked up without
+/// building the node.
+pub(crate) fn hash_str<H: Hasher>(s: &str, state: &mut H) {
+    state.write_u8(5);
+    s.hash(state)
+}
+
 impl<R: Repr> PartialEq for Yaml<R> {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Null, Self::Null) => true,
             (Self::Bool(b1), Self::Bool(b2)) => b1 == b2,
-            (Self::Int(s1), Self::Int(s2)) => to_i64(s1).unwrap() == to_i64(s2).unwrap(),
-            (Self::Float(s1), Self::Float(s2)) => {
-                let f1 = to_f64(s1).unwrap();
-                let f2 = to_f64(s2).unwrap();
-                if f1.is_nan() && f2.is_nan() {
-                    true
-                } else {
-                    f1 == f2
-                }
-            }
+            (Self::Int(s1), Self::Int(s2)) => match (to_i128(s1), to_i128(s2)) {
+                (Ok(n1), Ok(n2)) => n1 == n2,
+                // Out of range of signed integer
+                _ => match (to_u128(s1), to_u128(s2)) {
+                    (Ok(n1), Ok(n2)) => n1 == n2,
+                    // Invalid lexeme
+                    _ => s1.as_str() == s2.as_str(),
+                },
+            },
+            (Self::Float(s1), Self::Float(s2)) => match (to_f64(s1), to_f64(s2)) {
+                (Ok(f1), Ok(f2)) => float_eq(f1, f2),
+                // Invalid lexeme
+                _ => s1.as_str() == s2.as_str(),
+            },
             (Self::Str(s1), Self::Str(s2)) => s1 == s2,
             (Self::Seq(s1), Self::Seq(s2)) => s1 == s2,
-            (Self::Map(m1), Self::Map(m2)) => m1 == m2,
+            // The order is significant for all map backends
+            (Self::Map(m1), Self::Map(m2)) => m1.len() == m2.len() && m1.iter().eq(m2),
             (Self::Alias(a1), Self::Alias(a2)) => a1 == a2,
             _ => false,
         }